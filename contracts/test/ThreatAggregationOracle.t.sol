@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {CryptoHealthOracle} from "../src/ThreatAggregationOracle.sol";
+
+/// Conformance cases shared with prover/src/oracle.rs: the Rust
+/// ThreatAggregator runs the same report sequences and expects the same scores.
+contract ThreatAggregationOracleTest is Test {
+    CryptoHealthOracle public oracle;
+
+    address public alice = address(0xa11c);
+
+    CryptoHealthOracle.ThreatCategory constant CRYPTO = CryptoHealthOracle.ThreatCategory.CRYPTOGRAPHIC_WEAKNESS;
+    CryptoHealthOracle.ThreatCategory constant IMPL = CryptoHealthOracle.ThreatCategory.IMPLEMENTATION_VULN;
+    CryptoHealthOracle.ThreatCategory constant REG = CryptoHealthOracle.ThreatCategory.REGULATORY_CHANGE;
+    CryptoHealthOracle.ThreatCategory constant NET = CryptoHealthOracle.ThreatCategory.NETWORK_ATTACK;
+    CryptoHealthOracle.ThreatCategory constant HW = CryptoHealthOracle.ThreatCategory.HARDWARE_COMPROMISE;
+
+    function setUp() public {
+        oracle = new CryptoHealthOracle(50);
+    }
+
+    function _average(CryptoHealthOracle.ThreatCategory category) internal view returns (uint8 avg) {
+        (avg,,,) = oracle.aggregatedThreats(category);
+    }
+
+    function testWeightedOverallAndHistory() public {
+        oracle.reportThreat(CRYPTO, 90, "");
+        oracle.reportThreat(IMPL, 33, "");
+        oracle.reportThreat(REG, 47, "");
+        oracle.reportThreat(NET, 12, "");
+        oracle.reportThreat(HW, 99, "");
+
+        uint8[5] memory expected = [31, 39, 49, 50, 55];
+        assertEq(oracle.getScoreHistoryLength(), 5);
+        for (uint256 i = 0; i < 5; i++) {
+            (uint8 score,) = oracle.getHistoricalScore(i);
+            assertEq(score, expected[i]);
+        }
+        assertEq(oracle.overallThreatScore(), 55);
+        assertTrue(oracle.isMorphRecommended());
+        assertEq(oracle.getThreatLevelString(), "HIGH");
+        assertEq(oracle.getRecommendedBackendName(), "BLS12-381");
+    }
+
+    function testReportersAverageAndMax() public {
+        oracle.addReporter(alice);
+        assertEq(oracle.getReporterCount(), 2);
+
+        oracle.reportThreat(CRYPTO, 80, "");
+        vm.prank(alice);
+        oracle.reportThreat(CRYPTO, 45, "");
+
+        (uint8 avg, uint8 max, uint8 count,) = oracle.aggregatedThreats(CRYPTO);
+        assertEq(avg, 62);
+        assertEq(max, 80);
+        assertEq(count, 2);
+        assertEq(oracle.overallThreatScore(), 21);
+
+        vm.prank(address(0x3));
+        vm.expectRevert("Not authorized reporter");
+        oracle.reportThreat(NET, 10, "");
+
+        vm.expectRevert("Score must be 0-100");
+        oracle.reportThreat(NET, 101, "");
+    }
+
+    function testReportCooldown() public {
+        oracle.updateReportCooldown(3600);
+
+        vm.warp(1000);
+        oracle.reportThreat(CRYPTO, 10, "");
+        vm.warp(2000);
+        vm.expectRevert("Report cooldown active");
+        oracle.reportThreat(CRYPTO, 20, "");
+        vm.warp(4600);
+        oracle.reportThreat(CRYPTO, 20, "");
+
+        // The batch call skips the cooldown
+        vm.warp(4601);
+        CryptoHealthOracle.ThreatCategory[] memory categories = new CryptoHealthOracle.ThreatCategory[](1);
+        uint8[] memory scores = new uint8[](1);
+        string[] memory descriptions = new string[](1);
+        categories[0] = CRYPTO;
+        scores[0] = 30;
+        oracle.reportMultipleThreats(categories, scores, descriptions);
+
+        assertEq(_average(CRYPTO), 30);
+        assertEq(oracle.getScoreHistoryLength(), 3);
+    }
+
+    function testRemovedAndReaddedReporter() public {
+        oracle.addReporter(alice);
+        oracle.reportThreat(CRYPTO, 80, "");
+        vm.prank(alice);
+        oracle.reportThreat(CRYPTO, 40, "");
+        assertEq(_average(CRYPTO), 60);
+
+        // Removal does not re-aggregate until that category is reported again
+        oracle.removeReporter(alice);
+        oracle.reportThreat(REG, 50, "");
+        assertEq(oracle.overallThreatScore(), 31);
+        oracle.reportThreat(CRYPTO, 80, "");
+        assertEq(oracle.overallThreatScore(), 38);
+
+        // Re-adding lists alice twice, so her old report counts twice
+        oracle.addReporter(alice);
+        oracle.reportThreat(CRYPTO, 80, "");
+        assertEq(oracle.overallThreatScore(), 28);
+        (uint8 avg,, uint8 count,) = oracle.aggregatedThreats(CRYPTO);
+        assertEq(avg, 53);
+        assertEq(count, 3);
+
+        vm.expectRevert("Cannot remove owner");
+        oracle.removeReporter(address(this));
+    }
+
+    function testEmergencyReset() public {
+        vm.warp(1);
+        oracle.reportThreat(CRYPTO, 90, "");
+        assertEq(oracle.overallThreatScore(), 31);
+
+        vm.prank(alice);
+        vm.expectRevert("Not owner");
+        oracle.emergencyReset();
+
+        vm.warp(2);
+        oracle.emergencyReset();
+        assertEq(oracle.overallThreatScore(), 0);
+        (uint8 last, uint256 at) = oracle.getHistoricalScore(oracle.getScoreHistoryLength() - 1);
+        assertEq(last, 0);
+        assertEq(at, 2);
+
+        // Old reports survive the reset and come back when re-aggregated
+        vm.warp(3);
+        oracle.reportThreat(REG, 50, "");
+        assertEq(oracle.overallThreatScore(), 10);
+        oracle.reportThreat(IMPL, 10, "");
+        assertEq(oracle.overallThreatScore(), 12);
+        assertEq(_average(CRYPTO), 0);
+    }
+}
@@ -0,0 +1,10 @@
+// Generate the gRPC messages, client and server from proto/chameleon.proto.
+// protoc comes vendored so the build needs nothing installed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_prost_build::configure().compile_protos(&["proto/chameleon.proto"], &["proto"])?;
+    Ok(())
+}
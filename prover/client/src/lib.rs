@@ -0,0 +1,165 @@
+// Client for the Chameleon prover's gRPC interface
+//
+// `pb` holds everything generated from proto/chameleon.proto, including
+// the server trait the prover implements. `Client` wraps the generated
+// client with plainer signatures for the common calls:
+//
+//     let mut client = Client::connect("http://127.0.0.1:50051").await?;
+//     let proof = client.prove(3, 7).await?;
+//     assert!(client.verify(proof).await?);
+//
+// Morphs need an operator token, sent as `authorization: Bearer` metadata:
+//
+//     let mut client = Client::connect(endpoint).await?.with_token(token)?;
+
+pub mod pb {
+    tonic::include_proto!("chameleon.v1");
+}
+
+pub use pb::prover_client::ProverClient;
+pub use pb::{Backend, MorphDecision, MorphEvent, MorphResult, ThreatLevel, UniversalProof};
+
+use pb::{BatchVerifyRequest, BatchVerifyResult, MorphRequest, MorphResponse, ProveRequest};
+use pb::{ResumeAutoMorphRequest, ResumeAutoMorphResponse};
+use pb::{StatusRequest, StatusResponse, WatchMorphEventsRequest};
+use tonic::metadata::errors::InvalidMetadataValue;
+use tonic::metadata::AsciiMetadataValue;
+use tonic::transport::Channel;
+use tonic::{Request, Status, Streaming};
+
+impl Backend {
+    /// Name as the prover's CLI and config use it: "bn254", "bls12-381"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bn254" => Some(Backend::Bn254),
+            "bls12-381" | "bls12_381" | "bls" => Some(Backend::Bls12381),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: ProverClient<Channel>,
+    authorization: Option<AsciiMetadataValue>,
+}
+
+impl Client {
+    /// Connect to a prover, e.g. "http://127.0.0.1:50051"
+    pub async fn connect(endpoint: impl Into<String>) -> Result<Self, tonic::transport::Error> {
+        Ok(Self {
+            inner: ProverClient::connect(endpoint.into()).await?,
+            authorization: None,
+        })
+    }
+
+    /// Operator token to morph with
+    pub fn with_token(mut self, token: &str) -> Result<Self, InvalidMetadataValue> {
+        self.authorization = Some(format!("Bearer {}", token).parse()?);
+        Ok(self)
+    }
+
+    /// The generated client, for calls and options not wrapped here
+    pub fn inner(&mut self) -> &mut ProverClient<Channel> {
+        &mut self.inner
+    }
+
+    /// Prove a * b on the current backend for the active jurisdiction
+    pub async fn prove(&mut self, a: u64, b: u64) -> Result<UniversalProof, Status> {
+        self.prove_for(a, b, None).await
+    }
+
+    pub async fn prove_for(
+        &mut self,
+        a: u64,
+        b: u64,
+        jurisdiction: Option<&str>,
+    ) -> Result<UniversalProof, Status> {
+        let request = ProveRequest {
+            a,
+            b,
+            jurisdiction: jurisdiction.map(str::to_string),
+        };
+        Ok(self.inner.prove(request).await?.into_inner())
+    }
+
+    pub async fn verify(&mut self, proof: UniversalProof) -> Result<bool, Status> {
+        Ok(self.inner.verify(proof).await?.into_inner().valid)
+    }
+
+    /// One result per proof, in order
+    pub async fn batch_verify(
+        &mut self,
+        proofs: Vec<UniversalProof>,
+    ) -> Result<Vec<BatchVerifyResult>, Status> {
+        let request = BatchVerifyRequest { proofs };
+        Ok(self.inner.batch_verify(request).await?.into_inner().results)
+    }
+
+    pub async fn morph(&mut self, to: Backend, reason: &str) -> Result<MorphResponse, Status> {
+        self.send_morph(to, reason, false).await
+    }
+
+    /// Morph past the cooldown; the reason is required
+    pub async fn emergency_morph(
+        &mut self,
+        to: Backend,
+        reason: &str,
+    ) -> Result<MorphResponse, Status> {
+        self.send_morph(to, reason, true).await
+    }
+
+    /// Lift a flapping freeze on automatic morphs
+    pub async fn resume_auto_morph(&mut self) -> Result<ResumeAutoMorphResponse, Status> {
+        let request = self.authorized(ResumeAutoMorphRequest {});
+        Ok(self.inner.resume_auto_morph(request).await?.into_inner())
+    }
+
+    async fn send_morph(
+        &mut self,
+        to: Backend,
+        reason: &str,
+        emergency: bool,
+    ) -> Result<MorphResponse, Status> {
+        let request = self.authorized(MorphRequest {
+            to: to.into(),
+            reason: reason.to_string(),
+            emergency,
+        });
+        Ok(self.inner.morph(request).await?.into_inner())
+    }
+
+    /// `message` with the operator token attached, if there is one
+    fn authorized<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.clone());
+        }
+        request
+    }
+
+    pub async fn status(&mut self) -> Result<StatusResponse, Status> {
+        Ok(self.inner.get_status(StatusRequest {}).await?.into_inner())
+    }
+
+    /// Morphs as they happen; the stream ends when the prover shuts down
+    pub async fn watch_morph_events(&mut self) -> Result<Streaming<MorphEvent>, Status> {
+        let request = WatchMorphEventsRequest {};
+        Ok(self.inner.watch_morph_events(request).await?.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_names() {
+        assert_eq!(Backend::parse("bn254"), Some(Backend::Bn254));
+        assert_eq!(Backend::parse("BLS12-381"), Some(Backend::Bls12381));
+        assert_eq!(Backend::parse("secp256k1"), None);
+        assert_eq!(Backend::Bls12381.as_str_name(), "BACKEND_BLS12_381");
+    }
+}
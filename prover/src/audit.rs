@@ -0,0 +1,372 @@
+// Append-only, hash-chained audit log of morph events, and of auto-morph
+// freezes and the operator confirmations that lift them
+//
+// Every entry stores the hash of the entry before it, so editing or
+// removing an entry in the middle breaks the chain. A small `.head` file
+// next to the log records the entry count and latest hash, which catches
+// entries cut off the end of the file. It is moved after the entry is
+// synced; if a crash lands in between, opening the log catches it up.
+
+use crate::morph::MorphContext;
+use crate::simulator::ThreatLevel;
+use crate::types::{BackendType, MorphTrigger};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `prev_hash` of the first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub index: u64,
+    pub timestamp: String,
+    pub old_backend: BackendType,
+    pub new_backend: BackendType,
+    pub trigger: MorphTrigger,
+    pub operator: String,
+    pub reason: String,
+    pub threat: Option<ThreatLevel>,
+    pub commitment_proof_ids: Vec<String>,
+    /// Index of the entry this one undoes, for rollbacks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback_of: Option<u64>,
+    /// Journal id of the morph, so recovery can tell it was already recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub morph_id: Option<u64>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// SHA-256 over every field except `hash` itself
+    pub fn compute_hash(&self) -> String {
+        let mut unsealed = self.clone();
+        unsealed.hash = String::new();
+        let bytes = serde_json::to_vec(&unsealed).expect("audit entry is always serializable");
+        hex::encode(Sha256::digest(&bytes))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuditHead {
+    count: u64,
+    hash: String,
+}
+
+/// Summary of a successful `verify()`
+#[derive(Debug, Clone)]
+pub struct AuditReport {
+    pub entries: usize,
+    pub head_hash: String,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Open a log, creating an empty one if the file does not exist
+    pub fn open(path: &str) -> Result<Self, String> {
+        let path = PathBuf::from(path);
+        let entries = if path.exists() {
+            read_entries(&path)?
+        } else {
+            Vec::new()
+        };
+
+        let log = Self { path, entries };
+        if log.head_one_behind() {
+            eprintln!(
+                "[WARN] Audit head of {} is one entry behind (append interrupted), updating it",
+                log.path.display()
+            );
+            log.write_head()?;
+        }
+        Ok(log)
+    }
+
+    /// `append` syncs the entry before it moves the head, so a crash in
+    /// between leaves the head on the entry before the last. Anything else,
+    /// a missing head included, is left for `verify` to report.
+    fn head_one_behind(&self) -> bool {
+        let Some(last) = self.last() else {
+            return false;
+        };
+        let head = match fs::read_to_string(head_path(&self.path)) {
+            Ok(json) => serde_json::from_str::<AuditHead>(&json).ok(),
+            Err(_) if self.entries.len() == 1 => Some(AuditHead {
+                count: 0,
+                hash: GENESIS_HASH.to_string(),
+            }),
+            Err(_) => None,
+        };
+        head.is_some_and(|head| {
+            head.count + 1 == self.entries.len() as u64
+                && head.hash == last.prev_hash
+                && last.compute_hash() == last.hash
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    pub fn last(&self) -> Option<&AuditEntry> {
+        self.entries.last()
+    }
+
+    /// Latest entry that switched backends
+    pub fn last_morph(&self) -> Option<&AuditEntry> {
+        self.entries.iter().rev().find(|e| e.trigger.is_morph())
+    }
+
+    /// The freeze auto-morph is still under, if no confirmation followed it
+    pub fn frozen(&self) -> Option<&AuditEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|e| !e.trigger.is_morph())
+            .filter(|e| e.trigger == MorphTrigger::Freeze)
+    }
+
+    /// Seal and append an entry for a morph from `old_backend` to `new_backend`
+    pub fn append(
+        &mut self,
+        old_backend: BackendType,
+        new_backend: BackendType,
+        context: &MorphContext,
+    ) -> Result<AuditEntry, String> {
+        let prev_hash = self
+            .last()
+            .map(|e| e.hash.clone())
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+        let mut entry = AuditEntry {
+            index: self.entries.len() as u64,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            old_backend,
+            new_backend,
+            trigger: context.trigger,
+            operator: context.operator.clone(),
+            reason: context.reason.clone(),
+            threat: context.threat.clone(),
+            commitment_proof_ids: context.commitment_proof_ids.clone(),
+            rollback_of: context.rollback_of,
+            morph_id: context.morph_id,
+            prev_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create audit directory: {}", e))?;
+            }
+        }
+
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open audit log: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))?;
+        file.sync_all()
+            .map_err(|e| format!("Failed to sync audit log: {}", e))?;
+
+        self.entries.push(entry.clone());
+        self.write_head()?;
+
+        Ok(entry)
+    }
+
+    /// Re-read the log from disk and check every link in the chain
+    pub fn verify(&self) -> Result<AuditReport, String> {
+        verify_file(&self.path)
+    }
+
+    fn write_head(&self) -> Result<(), String> {
+        let head = AuditHead {
+            count: self.entries.len() as u64,
+            hash: self
+                .last()
+                .map(|e| e.hash.clone())
+                .unwrap_or_else(|| GENESIS_HASH.to_string()),
+        };
+        let json = serde_json::to_string(&head)
+            .map_err(|e| format!("Failed to serialize audit head: {}", e))?;
+
+        let head_path = head_path(&self.path);
+        let tmp_path = head_path.with_extension("head.tmp");
+        fs::write(&tmp_path, json).map_err(|e| format!("Failed to write audit head: {}", e))?;
+        fs::rename(&tmp_path, &head_path).map_err(|e| format!("Failed to write audit head: {}", e))
+    }
+}
+
+/// Check the hash chain of the log at `path` against its head file
+pub fn verify_file(path: &Path) -> Result<AuditReport, String> {
+    let entries = read_entries(path)?;
+
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.index != i as u64 {
+            return Err(format!(
+                "Entry {}: expected index {}, found {} (entry missing or reordered)",
+                i, i, entry.index
+            ));
+        }
+        if entry.prev_hash != prev_hash {
+            return Err(format!(
+                "Entry {}: prev_hash does not match entry {} (entry missing or edited)",
+                i,
+                i as i64 - 1
+            ));
+        }
+        if entry.compute_hash() != entry.hash {
+            return Err(format!(
+                "Entry {}: contents do not match its hash (edited)",
+                i
+            ));
+        }
+        prev_hash = entry.hash.clone();
+    }
+
+    let head_path = head_path(path);
+    let head_json = fs::read_to_string(&head_path)
+        .map_err(|e| format!("Failed to read audit head {}: {}", head_path.display(), e))?;
+    let head: AuditHead = serde_json::from_str(&head_json)
+        .map_err(|e| format!("Failed to parse audit head: {}", e))?;
+
+    if head.count != entries.len() as u64 || head.hash != prev_hash {
+        return Err(format!(
+            "Log has {} entries but head records {} (entries truncated or head edited)",
+            entries.len(),
+            head.count
+        ));
+    }
+
+    Ok(AuditReport {
+        entries: entries.len(),
+        head_hash: prev_hash,
+    })
+}
+
+fn head_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".head");
+    PathBuf::from(name)
+}
+
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read audit log {}: {}", path.display(), e))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| format!("Failed to parse audit log line {}: {}", n + 1, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("chameleon-audit-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.jsonl", name));
+        fs::remove_file(&path).ok();
+        fs::remove_file(head_path(&path)).ok();
+        path.to_string_lossy().to_string()
+    }
+
+    fn filled_log(name: &str) -> AuditLog {
+        let mut log = AuditLog::open(&temp_log(name)).unwrap();
+        let ctx = MorphContext::manual("alice", "scheduled drill");
+        log.append(BackendType::BN254, BackendType::BLS12_381, &ctx)
+            .unwrap();
+        log.append(BackendType::BLS12_381, BackendType::BN254, &ctx)
+            .unwrap();
+        log.append(BackendType::BN254, BackendType::BLS12_381, &ctx)
+            .unwrap();
+        log
+    }
+
+    fn rewrite_lines(log: &AuditLog, edit: impl Fn(&mut Vec<String>)) {
+        let content = fs::read_to_string(log.path()).unwrap();
+        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+        edit(&mut lines);
+        fs::write(log.path(), lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn test_chain_verifies_and_reopens() {
+        let log = filled_log("intact");
+        let report = log.verify().expect("Untouched log should verify");
+        assert_eq!(report.entries, 3);
+
+        let reopened = AuditLog::open(log.path().to_str().unwrap()).unwrap();
+        assert_eq!(reopened.entries().len(), 3);
+        assert_eq!(reopened.entries()[1].prev_hash, reopened.entries()[0].hash);
+    }
+
+    #[test]
+    fn test_edited_entry_detected() {
+        let log = filled_log("edited");
+        rewrite_lines(&log, |lines| {
+            lines[1] = lines[1].replace("alice", "mallory");
+        });
+        assert!(log.verify().unwrap_err().contains("Entry 1"));
+    }
+
+    #[test]
+    fn test_deleted_entry_detected() {
+        let log = filled_log("deleted");
+        rewrite_lines(&log, |lines| {
+            lines.remove(1);
+        });
+        assert!(log.verify().is_err());
+    }
+
+    #[test]
+    fn test_truncated_tail_detected() {
+        let log = filled_log("truncated");
+        rewrite_lines(&log, |lines| {
+            lines.pop();
+        });
+        assert!(log.verify().unwrap_err().contains("truncated"));
+        // Reopening does not paper over it
+        AuditLog::open(log.path().to_str().unwrap()).unwrap();
+        assert!(log.verify().unwrap_err().contains("truncated"));
+    }
+
+    #[test]
+    fn test_head_caught_up_after_interrupted_append() {
+        let log = filled_log("interrupted");
+        // Crash after the third entry was synced but before the head moved
+        let head = AuditHead {
+            count: 2,
+            hash: log.entries()[1].hash.clone(),
+        };
+        fs::write(head_path(log.path()), serde_json::to_string(&head).unwrap()).unwrap();
+        assert!(log.verify().is_err());
+
+        let reopened = AuditLog::open(log.path().to_str().unwrap()).unwrap();
+        assert_eq!(reopened.verify().unwrap().entries, 3);
+    }
+}
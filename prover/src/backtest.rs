@@ -0,0 +1,320 @@
+// Backtesting morph policies against recorded threat logs
+//
+// Reads the per-cycle lines that scripts/threat_simulator.sh writes to
+// simulator/logs/threat_log.txt:
+//
+//   [09:55:36] Cycle=1 Q=10 R=6 G=4 Score=8 Level=LOW Backend=BN254
+//
+// replays them through a ThreatSimulator on a simulated clock that follows
+// the log's timestamps, and sums up what the policy would have cost.
+
+use crate::optimizer::required_security;
+use crate::simulator::{ThreatLevel, ThreatSimulator};
+use crate::threat::ThreatModel;
+use crate::types::BackendType;
+use std::fs;
+use std::time::Duration;
+
+const DAY_SECONDS: u32 = 24 * 60 * 60;
+
+/// One `Cycle=` line of a threat log
+#[derive(Debug, Clone)]
+pub struct LogReading {
+    /// Seconds since midnight
+    pub time: u32,
+    pub cycle: Option<u32>,
+    pub threat: ThreatLevel,
+    /// Backend the recorded run was on at this reading
+    pub backend: Option<BackendType>,
+}
+
+/// Short log keys and the categories they stand for
+const LOG_CATEGORIES: [(&str, &str); 4] = [
+    ("Q", "quantum"),
+    ("R", "regulatory"),
+    ("G", "geographic"),
+    ("P", "performance"),
+];
+
+pub fn load_log(path: &str, model: &ThreatModel) -> Result<Vec<LogReading>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read threat log: {}", e))?;
+    parse_log(&content, model)
+}
+
+/// Parse every reading in a log, skipping the morph output in between
+pub fn parse_log(content: &str, model: &ThreatModel) -> Result<Vec<LogReading>, String> {
+    let mut readings = Vec::new();
+
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if !line.contains("Cycle=") {
+            continue;
+        }
+        let bad = |what: &str| format!("Threat log line {}: {}", n + 1, what);
+
+        let (stamp, rest) = line
+            .strip_prefix('[')
+            .and_then(|l| l.split_once(']'))
+            .ok_or_else(|| bad("missing [HH:MM:SS] timestamp"))?;
+        let time = parse_time(stamp).ok_or_else(|| bad("bad timestamp"))?;
+
+        let mut scores = Vec::new();
+        let mut overall = None;
+        let mut cycle = None;
+        let mut backend = None;
+        for field in rest.split_whitespace() {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            let number = || {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| bad(&format!("bad {}", key)))
+            };
+            match key {
+                "Cycle" => cycle = Some(number()?),
+                "Score" => overall = Some(number()?),
+                "Backend" => {
+                    backend = Some(
+                        BackendType::parse(value)
+                            .ok_or_else(|| bad(&format!("unknown backend {}", value)))?,
+                    )
+                }
+                _ => {
+                    if let Some((_, category)) = LOG_CATEGORIES.iter().find(|(k, _)| *k == key) {
+                        scores.push((*category, number()?));
+                    }
+                }
+            }
+        }
+
+        let mut threat = model.level(&scores);
+        // The script folds categories it does not log (performance) into
+        // Score, so the recorded overall is the one to trust
+        if let Some(overall) = overall {
+            threat.overall = overall;
+        }
+        readings.push(LogReading {
+            time,
+            cycle,
+            threat,
+            backend,
+        });
+    }
+
+    if readings.is_empty() {
+        return Err("Threat log has no Cycle= readings".to_string());
+    }
+    Ok(readings)
+}
+
+fn parse_time(stamp: &str) -> Option<u32> {
+    let mut parts = stamp.split(':').map(|p| p.parse::<u32>().ok());
+    let (h, m, s) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || h > 23 || m > 59 || s > 59 {
+        return None;
+    }
+    Some(h * 3600 + m * 60 + s)
+}
+
+/// How long each reading was in effect: until the next one, with the last
+/// reading lasting as long as the one before it
+pub fn durations(readings: &[LogReading]) -> Vec<Duration> {
+    let mut gaps: Vec<Duration> = readings
+        .windows(2)
+        .map(|pair| {
+            // Logs run past midnight
+            let gap = (pair[1].time + DAY_SECONDS - pair[0].time) % DAY_SECONDS;
+            Duration::from_secs(gap as u64)
+        })
+        .collect();
+    gaps.push(gaps.last().copied().unwrap_or_default());
+    gaps
+}
+
+/// Backend the policy holds after each reading
+pub fn replay(readings: &[LogReading], simulator: &mut ThreatSimulator) -> Vec<BackendType> {
+    simulator.use_simulated_clock();
+
+    readings
+        .iter()
+        .zip(durations(readings))
+        .map(|(reading, held)| {
+            simulator.step(&reading.threat);
+            simulator.advance(held);
+            simulator.current_backend
+        })
+        .collect()
+}
+
+/// Backends as recorded in the log, if every reading has one
+pub fn recorded_backends(readings: &[LogReading]) -> Option<Vec<BackendType>> {
+    readings.iter().map(|r| r.backend).collect()
+}
+
+/// Proving cost assumptions for the estimate
+#[derive(Debug, Clone, Copy)]
+pub struct ProvingCost {
+    pub bn254_prove_ms: u64,
+    pub bls12_381_prove_ms: u64,
+    /// Application proofs generated per hour
+    pub proofs_per_hour: u64,
+}
+
+impl ProvingCost {
+    pub fn prove_ms(&self, backend: BackendType) -> u64 {
+        match backend {
+            BackendType::BN254 => self.bn254_prove_ms,
+            BackendType::BLS12_381 => self.bls12_381_prove_ms,
+        }
+    }
+}
+
+impl Default for ProvingCost {
+    /// Prove times from the README demo run
+    fn default() -> Self {
+        Self {
+            bn254_prove_ms: 45,
+            bls12_381_prove_ms: 78,
+            proofs_per_hour: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BacktestReport {
+    pub readings: usize,
+    pub morphs: usize,
+    pub time_on_bn254: Duration,
+    pub time_on_bls12_381: Duration,
+    /// On BN254 while a reading was at or over an upgrade threshold
+    pub under_protected: Duration,
+    /// Proving time spent beyond running everything on BN254, including
+    /// the state proofs of each morph
+    pub extra_proving: Duration,
+}
+
+/// Totals for a run that held `backends[i]` after reading `i`, starting
+/// from `initial`. A reading is "high" when the overall score reaches
+/// `upgrade_threshold` or any category its elevated threshold, so different
+/// policies are measured with the same yardstick.
+pub fn summarize(
+    readings: &[LogReading],
+    backends: &[BackendType],
+    initial: BackendType,
+    model: &ThreatModel,
+    upgrade_threshold: u32,
+    cost: &ProvingCost,
+) -> BacktestReport {
+    let mut report = BacktestReport {
+        readings: readings.len(),
+        ..BacktestReport::default()
+    };
+    let extra_ms_per_proof = cost.bls12_381_prove_ms.saturating_sub(cost.bn254_prove_ms);
+    let mut extra_ms = 0.0;
+    let mut previous = initial;
+
+    for ((reading, backend), held) in readings.iter().zip(backends).zip(durations(readings)) {
+        if *backend != previous {
+            report.morphs += 1;
+            // A morph proves the state on both curves
+            extra_ms += (cost.prove_ms(previous) + cost.prove_ms(*backend)) as f64;
+        }
+        previous = *backend;
+
+        match backend {
+            BackendType::BN254 => {
+                report.time_on_bn254 += held;
+                let required = required_security(&reading.threat, model, upgrade_threshold);
+                if backend.security_bits() < required {
+                    report.under_protected += held;
+                }
+            }
+            BackendType::BLS12_381 => {
+                report.time_on_bls12_381 += held;
+                let proofs = held.as_secs_f64() / 3600.0 * cost.proofs_per_hour as f64;
+                extra_ms += proofs * extra_ms_per_proof as f64;
+            }
+        }
+    }
+
+    report.extra_proving = Duration::from_millis(extra_ms.round() as u64);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{MorphPolicy, PolicyRule, StaleData};
+
+    // Excerpt of simulator/logs/threat_log.txt, with the morph output cut
+    const LOG: &str = "Simulation started at Thu Apr 16 09:55:27 AM IST 2026
+[09:55:36] Cycle=1 Q=10 R=6 G=4 Score=8 Level=LOW Backend=BN254
+[09:55:42] Cycle=2 Q=40 R=24 G=16 Score=28 Level=MEDIUM Backend=BN254
+[09:55:48] Cycle=3 Q=80 R=48 G=32 Score=54 Level=HIGH Backend=BN254
+[09:56:19] AUTO-MORPH COMPLETE (ms)
+[10:02:23] Cycle=4 Q=90 R=54 G=36 Score=61 Level=HIGH Backend=BLS12-381
+[10:02:29] Cycle=5 Q=40 R=24 G=16 Score=28 Level=MEDIUM Backend=BLS12-381
+[10:04:56] Cycle=6 Q=10 R=6 G=4 Score=8 Level=LOW Backend=BN254
+";
+
+    fn simulator(policy: Option<MorphPolicy>) -> ThreatSimulator {
+        let mut sim = ThreatSimulator::new(BackendType::BN254, 50);
+        sim.recovery_threshold = 20;
+        sim.policy = policy;
+        sim
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let readings = parse_log(LOG, &ThreatModel::default()).unwrap();
+        assert_eq!(readings.len(), 6);
+        assert_eq!(readings[0].time, 9 * 3600 + 55 * 60 + 36);
+        assert_eq!(readings[2].threat.quantum, 80);
+        assert_eq!(readings[2].threat.score("geographic"), 32);
+        assert_eq!(readings[2].threat.overall, 54);
+        assert_eq!(readings[3].backend, Some(BackendType::BLS12_381));
+
+        let late = "[23:59:58] Cycle=1 Q=1 R=1 G=1 Score=1\n[00:00:03] Cycle=2 Q=1 R=1 G=1 Score=1";
+        let readings = parse_log(late, &ThreatModel::default()).unwrap();
+        assert_eq!(durations(&readings)[0], Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_compare_policies_on_same_log() {
+        let model = ThreatModel::default();
+        let readings = parse_log(LOG, &model).unwrap();
+        let cost = ProvingCost::default();
+        let summary = |backends: &[BackendType]| {
+            summarize(&readings, backends, BackendType::BN254, &model, 50, &cost)
+        };
+
+        // The recorded run only morphed after the slow shell morph finished
+        let recorded = summary(&recorded_backends(&readings).unwrap());
+        assert_eq!(recorded.morphs, 2);
+        assert_eq!(recorded.under_protected, Duration::from_secs(6 * 60 + 35));
+
+        let builtin = summary(&replay(&readings, &mut simulator(None)));
+        assert_eq!(builtin.morphs, 2);
+        assert_eq!(builtin.under_protected, Duration::ZERO);
+        assert!(builtin.extra_proving > Duration::ZERO);
+
+        // A policy that never upgrades is cheaper but leaves us exposed
+        let never = MorphPolicy {
+            name: "never".to_string(),
+            stale_data: StaleData::Ignore,
+            rules: vec![PolicyRule {
+                name: "hold".to_string(),
+                when: Vec::new(),
+                target: None,
+                emergency: false,
+                reason: "Stay put".to_string(),
+            }],
+        };
+        let lazy = summary(&replay(&readings, &mut simulator(Some(never))));
+        assert_eq!(lazy.morphs, 0);
+        assert_eq!(lazy.extra_proving, Duration::ZERO);
+        assert!(lazy.under_protected > builtin.under_protected);
+    }
+}
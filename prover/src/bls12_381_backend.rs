@@ -47,6 +47,12 @@ pub struct BLS12_381Backend {
     pub verifying_key: Option<VerifyingKey<Bls12_381>>, //it stores the vk-verifier
 }
 
+impl Default for BLS12_381Backend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BLS12_381Backend {
     pub fn new() -> Self {
         //intializes backend without keys
@@ -107,6 +113,25 @@ impl BLS12_381Backend {
         })
     }
 
+    /// Verify-only backend from a compressed verifying key, as exported
+    /// alongside a proof
+    pub fn from_verifying_key(bytes: &[u8]) -> Result<Self, String> {
+        let vk = VerifyingKey::<Bls12_381>::deserialize_compressed(bytes)
+            .map_err(|e| format!("Invalid verifying key: {:?}", e))?;
+        Ok(Self {
+            proving_key: None,
+            verifying_key: Some(vk),
+        })
+    }
+
+    pub fn verifying_key_bytes(&self) -> Result<Vec<u8>, String> {
+        let vk = self.verifying_key.as_ref().ok_or("Setup not performed")?;
+        let mut bytes = Vec::new();
+        vk.serialize_compressed(&mut bytes)
+            .map_err(|e| format!("Serialization failed: {:?}", e))?;
+        Ok(bytes)
+    }
+
     pub fn verify(&self, proof: &UniversalProof) -> Result<bool, String> {
         let vk = self.verifying_key.as_ref().ok_or("Setup not performed")?;
 
@@ -46,6 +46,12 @@ pub struct BN254Backend {
     pub verifying_key: Option<VerifyingKey<Bn254>>, //here the vk is the verifier or verify the proof
 }
 
+impl Default for BN254Backend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BN254Backend {
     pub fn new() -> Self {
         Self {
@@ -117,6 +123,25 @@ impl BN254Backend {
         })
     }
 
+    /// Verify-only backend from a compressed verifying key, as exported
+    /// alongside a proof
+    pub fn from_verifying_key(bytes: &[u8]) -> Result<Self, String> {
+        let vk = VerifyingKey::<Bn254>::deserialize_compressed(bytes)
+            .map_err(|e| format!("Invalid verifying key: {:?}", e))?;
+        Ok(Self {
+            proving_key: None,
+            verifying_key: Some(vk),
+        })
+    }
+
+    pub fn verifying_key_bytes(&self) -> Result<Vec<u8>, String> {
+        let vk = self.verifying_key.as_ref().ok_or("Setup not performed")?;
+        let mut bytes = Vec::new();
+        vk.serialize_compressed(&mut bytes)
+            .map_err(|e| format!("Serialization failed: {:?}", e))?;
+        Ok(bytes)
+    }
+
     //get verifying key
     pub fn verify(&self, proof: &UniversalProof) -> Result<bool, String> {
         let vk = self.verifying_key.as_ref().ok_or("Setup not performed")?;
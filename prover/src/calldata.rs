@@ -0,0 +1,137 @@
+// Solidity calldata for Groth16 proofs
+//
+// The verifier contracts take a proof as verifyProof(pA, pB, pC, pubSignals),
+// the layout snarkjs generates: A and C are G1 points [x, y], B is a G2 point
+// whose coordinates are in F_p^2 and go in as [im, re] pairs. Coordinates
+// are big-endian field elements; 32 bytes on BN254, 48 on BLS12-381 (left
+// for the caller to pad to the 64-byte words of the EIP-2537 precompiles).
+
+use crate::types::{BackendType, UniversalProof};
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ff::{BigInteger, Fp2, Fp2Config, PrimeField};
+use ark_groth16::Proof;
+use ark_serialize::CanonicalDeserialize;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Calldata {
+    pub backend: BackendType,
+    pub a: [String; 2],
+    pub b: [[String; 2]; 2],
+    pub c: [String; 2],
+    pub inputs: Vec<String>,
+}
+
+impl Calldata {
+    pub fn for_proof(proof: &UniversalProof) -> Result<Self, String> {
+        match proof.backend {
+            BackendType::BN254 => {
+                let groth: Proof<ark_bn254::Bn254> = deserialize(&proof.proof_bytes)?;
+                let inputs = words::<ark_bn254::Fr>(&proof.public_inputs)?;
+                Ok(Self {
+                    backend: proof.backend,
+                    a: g1(&groth.a),
+                    b: g2(&groth.b),
+                    c: g1(&groth.c),
+                    inputs,
+                })
+            }
+            BackendType::BLS12_381 => {
+                let groth: Proof<ark_bls12_381::Bls12_381> = deserialize(&proof.proof_bytes)?;
+                let inputs = words::<ark_bls12_381::Fr>(&proof.public_inputs)?;
+                Ok(Self {
+                    backend: proof.backend,
+                    a: g1(&groth.a),
+                    b: g2(&groth.b),
+                    c: g1(&groth.c),
+                    inputs,
+                })
+            }
+        }
+    }
+
+    /// Arguments as pasted into `cast call` or Remix
+    pub fn to_args(&self) -> String {
+        let pair = |p: &[String; 2]| format!("[\"{}\",\"{}\"]", p[0], p[1]);
+        let inputs: Vec<String> = self.inputs.iter().map(|i| format!("\"{}\"", i)).collect();
+        format!(
+            "{},[{},{}],{},[{}]",
+            pair(&self.a),
+            pair(&self.b[0]),
+            pair(&self.b[1]),
+            pair(&self.c),
+            inputs.join(",")
+        )
+    }
+}
+
+fn deserialize<T: CanonicalDeserialize>(bytes: &[u8]) -> Result<T, String> {
+    T::deserialize_compressed(bytes).map_err(|e| format!("Deserialization failed: {:?}", e))
+}
+
+fn word<F: PrimeField>(f: &F) -> String {
+    format!("0x{}", hex::encode(f.into_bigint().to_bytes_be()))
+}
+
+fn words<F: PrimeField>(inputs: &[String]) -> Result<Vec<String>, String> {
+    inputs
+        .iter()
+        .map(|input| {
+            let value: u64 = input
+                .parse()
+                .map_err(|_| format!("Invalid public input '{}'", input))?;
+            Ok(word(&F::from(value)))
+        })
+        .collect()
+}
+
+fn g1<P: SWCurveConfig>(point: &Affine<P>) -> [String; 2]
+where
+    P::BaseField: PrimeField,
+{
+    [word(&point.x), word(&point.y)]
+}
+
+fn g2<P, C>(point: &Affine<P>) -> [[String; 2]; 2]
+where
+    P: SWCurveConfig<BaseField = Fp2<C>>,
+    C: Fp2Config,
+    C::Fp: PrimeField,
+{
+    let pair = |f: &Fp2<C>| [word(&f.c1), word(&f.c0)];
+    [pair(&point.x), pair(&point.y)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLS12_381Backend, BN254Backend};
+
+    #[test]
+    fn test_bn254_calldata_words() {
+        let mut backend = BN254Backend::new();
+        backend.setup().unwrap();
+        let proof = backend.prove(3, 7).unwrap();
+        let calldata = Calldata::for_proof(&proof).unwrap();
+        // 0x + 32 bytes
+        assert!(calldata.a.iter().all(|w| w.len() == 66));
+        assert!(calldata.b.iter().flatten().all(|w| w.len() == 66));
+        assert_eq!(calldata.inputs, vec![format!("0x{:064x}", 21)]);
+        assert!(calldata.to_args().starts_with("[\"0x"));
+    }
+
+    #[test]
+    fn test_bls12_381_calldata_words() {
+        let mut backend = BLS12_381Backend::new();
+        backend.setup().unwrap();
+        let proof = backend.prove(3, 7).unwrap();
+        let calldata = Calldata::for_proof(&proof).unwrap();
+        // 0x + 48 bytes
+        assert!(calldata.c.iter().all(|w| w.len() == 98));
+        assert_eq!(calldata.inputs.len(), 1);
+
+        let mut garbled = proof.clone();
+        garbled.proof_bytes.truncate(10);
+        assert!(Calldata::for_proof(&garbled).is_err());
+    }
+}
@@ -25,16 +25,87 @@ pub enum Commands {
 
         #[arg(short, long, default_value = "proof.json")]
         output: String,
+
+        /// Jurisdiction the proof is for (default: active_jurisdiction in
+        /// config/jurisdictions.json)
+        #[arg(short, long)]
+        jurisdiction: Option<String>,
+
+        /// Prove on both BN254 and BLS12-381 and save a dual proof, plus
+        /// its verifying keys as OUTPUT.bn254.vk and OUTPUT.bls12-381.vk
+        /// for verifiers (ignores --backend)
+        #[arg(long)]
+        hedged: bool,
     },
 
     Verify {
         #[arg(short, long, default_value = "proof.json")]
         proof: String,
+
+        /// Halves of a dual proof that must verify: both, either, bn254 or
+        /// bls12-381 (default: morph.hedge_verify in config.json)
+        #[arg(long)]
+        require: Option<String>,
+
+        /// Trusted BN254 verifying key (hex) to check a dual proof against
+        /// (default: morph.hedge_bn254_vk in config.json)
+        #[arg(long, value_name = "FILE")]
+        bn254_vk: Option<String>,
+
+        /// Trusted BLS12-381 verifying key (hex) to check a dual proof
+        /// against (default: morph.hedge_bls12_381_vk in config.json)
+        #[arg(long, value_name = "FILE")]
+        bls12_381_vk: Option<String>,
+
+        /// Print verifyProof(pA, pB, pC, pubSignals) arguments
+        #[arg(long)]
+        calldata: bool,
     },
 
     Morph {
         #[arg(short, long)]
         to: String,
+
+        /// Who is requesting the morph (recorded in the audit log)
+        #[arg(long, default_value = "cli")]
+        operator: String,
+
+        #[arg(short, long)]
+        reason: Option<String>,
+
+        /// Bypass the cooldown; requires --reason
+        #[arg(long, requires = "reason")]
+        emergency: bool,
+
+        /// State commitment proof ids taken around this morph
+        #[arg(long = "commitment-proof")]
+        commitment_proofs: Vec<String>,
+
+        /// Prove on both backends before switching (on by default if
+        /// canary_enabled is set in config)
+        #[arg(long)]
+        canary: bool,
+    },
+
+    /// Undo the last morph while its grace period is open
+    Rollback {
+        #[arg(long, default_value = "cli")]
+        operator: String,
+
+        #[arg(short, long)]
+        reason: String,
+    },
+
+    /// Confirm proofs on the backend of the last morph as committed
+    /// downstream; rollback is refused from then on
+    Confirm {
+        #[arg(long, default_value = "cli")]
+        operator: String,
+    },
+
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
     },
 
     Benchmark {
@@ -48,5 +119,162 @@ pub enum Commands {
 
         #[arg(short, long, default_value = "80")]
         level: u32,
+
+        /// Morph policy file (default: config/morph_policy.json if present)
+        #[arg(long)]
+        policy: Option<String>,
+
+        /// Jurisdiction the proofs are used in, for jurisdiction rules
+        #[arg(long)]
+        jurisdiction: Option<String>,
+
+        /// Show every rule checked and which one fired
+        #[arg(long)]
+        explain: bool,
+
+        /// Take the threat level from signed reports (JSONL) instead of
+        /// --threat/--level
+        #[arg(long)]
+        reports: Option<String>,
+
+        /// Registered reporter keys and quorum (default:
+        /// config/reporters.json)
+        #[arg(long)]
+        reporters: Option<String>,
+
+        /// Take the quantum level from the hardware forecast (default:
+        /// config/quantum_forecast.json) instead of --threat/--level
+        #[arg(long)]
+        quantum_forecast: bool,
+    },
+
+    /// Estimate what Shor's algorithm needs against each curve and the
+    /// quantum threat the hardware forecast implies
+    Quantum {
+        /// Year to assess from (default: now)
+        #[arg(short, long)]
+        year: Option<f64>,
+
+        #[arg(short, long)]
+        forecast: Option<String>,
+    },
+
+    /// Create and sign threat reports
+    Report {
+        #[command(subcommand)]
+        action: ReportAction,
+    },
+
+    /// Run a threat timeline through the policy cycle by cycle
+    Scenario {
+        /// rising, falling, rising_falling, spike, sawtooth, random_walk or
+        /// csv:<path> (default: threat_pattern from threat_config.json)
+        #[arg(short, long)]
+        pattern: Option<String>,
+
+        #[arg(short, long)]
+        cycles: Option<u32>,
+
+        /// Simulated seconds between readings
+        #[arg(short, long)]
+        interval: Option<u32>,
+
+        #[arg(long, default_value = "1")]
+        seed: u64,
+
+        #[arg(long)]
+        policy: Option<String>,
+
+        /// within:<backend>:<cycles>, rate:<max>/<seconds>, max-morphs:<n>
+        /// or ends-on:<backend>; repeatable
+        #[arg(long = "assert")]
+        assertions: Vec<String>,
+
+        /// Carry out each morph on a real controller (slow)
+        #[arg(long)]
+        prove: bool,
+
+        /// Also let the cost optimizer pick backends on the same timeline
+        /// and compare it with the policy
+        #[arg(long)]
+        optimize: bool,
+    },
+
+    /// Replay a recorded threat log through one or more policies
+    Backtest {
+        #[arg(short, long, default_value = "simulator/logs/threat_log.txt")]
+        log: String,
+
+        /// Policy file; repeat to compare policies side by side (default:
+        /// config/morph_policy.json or the built-in thresholds)
+        #[arg(short, long = "policy")]
+        policies: Vec<String>,
+
+        /// Application proofs per hour, for the proving cost estimate
+        #[arg(long, default_value = "1000")]
+        proofs_per_hour: u64,
+    },
+
+    /// Run the prover as a daemon with an HTTP/JSON API
+    Serve {
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        listen: String,
+
+        /// Also serve the gRPC interface on ADDR
+        #[arg(long, value_name = "ADDR")]
+        grpc: Option<String>,
+
+        /// Morph policy file (default: config/morph_policy.json if present)
+        #[arg(short, long)]
+        policy: Option<String>,
+
+        /// Serve Prometheus metrics on ADDR/metrics (default: metrics.listen
+        /// in config.json)
+        #[arg(long, value_name = "ADDR")]
+        metrics: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportAction {
+    /// Generate a reporter key pair; register the printed public key in
+    /// config/reporters.json
+    Keygen {
+        #[arg(short, long)]
+        out: String,
+    },
+
+    /// Sign a threat score and append it to a reports file
+    Sign {
+        /// Secret key file from `report keygen`
+        #[arg(short, long)]
+        key: String,
+
+        #[arg(short, long)]
+        reporter: String,
+
+        #[arg(short, long)]
+        category: String,
+
+        #[arg(short, long)]
+        score: u32,
+
+        #[arg(short, long, default_value = "output/reports/threat_reports.jsonl")]
+        out: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditAction {
+    /// Check the hash chain for edited or deleted entries
+    Verify {
+        #[arg(short, long)]
+        log: Option<String>,
+    },
+
+    /// List recorded morphs
+    Show {
+        #[arg(short, long)]
+        log: Option<String>,
     },
 }
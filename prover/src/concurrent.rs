@@ -0,0 +1,411 @@
+// A MorphController many threads can prove through at once
+//
+// Proving does not need the controller, only the keys in its BackendPool,
+// so each proof takes a lease on the backend it proves on instead of a
+// lock. A morph or rollback closes the gate to new leases, waits up to the
+// drain timeout for the leases on the old backend to be returned, then
+// switches and opens the gate on the new backend. Leases still out when the
+// timeout passes are stragglers: their proofs finish on the old backend, are
+// reported as such and do not count towards the new backend's commit point.
+
+use crate::morph::{MorphContext, MorphController};
+use crate::pool::BackendPool;
+use crate::types::{BackendType, MorphResult, UniversalProof};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Who may prove on what, guarded separately from the controller so that
+/// leases never wait on a morph's work
+struct Gate {
+    backend: BackendType,
+    /// Bumped on every switch
+    epoch: u64,
+    /// Set while a morph drains; no leases are handed out meanwhile
+    switching: bool,
+    /// Leases outstanding per backend, including those pinned to a backend
+    /// that is not current
+    in_flight: BTreeMap<&'static str, usize>,
+}
+
+/// Permission to prove on `backend`, returned on drop
+pub struct Lease<'a> {
+    owner: &'a ConcurrentController,
+    pub backend: BackendType,
+    pub epoch: u64,
+}
+
+impl Drop for Lease<'_> {
+    fn drop(&mut self) {
+        let mut gate = self.owner.gate();
+        let key = self.backend.name();
+        if let Some(count) = gate.in_flight.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                gate.in_flight.remove(key);
+            }
+        }
+        self.owner.changed.notify_all();
+    }
+}
+
+/// A proof and the lease it was made under
+#[derive(Debug, Clone)]
+pub struct LeasedProof {
+    pub proof: UniversalProof,
+    pub backend: BackendType,
+    pub epoch: u64,
+    /// A morph switched away from `backend` before the proof finished
+    pub superseded: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DrainOutcome {
+    pub result: MorphResult,
+    /// Leases on the old backend when the morph started
+    pub in_flight: usize,
+    /// Of those, still out when the drain timed out
+    pub stragglers: usize,
+    pub waited: Duration,
+}
+
+pub struct ConcurrentController {
+    controller: Mutex<MorphController>,
+    pool: Arc<BackendPool>,
+    gate: Mutex<Gate>,
+    /// Signalled when a lease is returned or the gate reopens
+    changed: Condvar,
+    drain_timeout: Duration,
+}
+
+impl ConcurrentController {
+    pub fn new(controller: MorphController, drain_timeout: Duration) -> Self {
+        let gate = Gate {
+            backend: controller.current_backend(),
+            epoch: 0,
+            switching: false,
+            in_flight: BTreeMap::new(),
+        };
+        Self {
+            pool: Arc::clone(controller.pool()),
+            controller: Mutex::new(controller),
+            gate: Mutex::new(gate),
+            changed: Condvar::new(),
+            drain_timeout,
+        }
+    }
+
+    pub fn current_backend(&self) -> BackendType {
+        self.gate().backend
+    }
+
+    /// Keys the leases prove with
+    pub fn pool(&self) -> &Arc<BackendPool> {
+        &self.pool
+    }
+
+    /// Leases outstanding, across all backends
+    pub fn in_flight(&self) -> usize {
+        self.gate().in_flight.values().sum()
+    }
+
+    /// Lease the current backend, waiting out a morph in progress
+    pub fn lease(&self) -> Lease<'_> {
+        self.lease_backend(None)
+    }
+
+    /// Lease `backend` whether or not it is current, waiting out a morph in
+    /// progress. A later morph away from `backend` drains this lease too.
+    pub fn lease_on(&self, backend: BackendType) -> Lease<'_> {
+        self.lease_backend(Some(backend))
+    }
+
+    fn lease_backend(&self, backend: Option<BackendType>) -> Lease<'_> {
+        let mut gate = self.gate();
+        while gate.switching {
+            gate = self.wait(gate);
+        }
+        let backend = backend.unwrap_or(gate.backend);
+        *gate.in_flight.entry(backend.name()).or_default() += 1;
+        Lease {
+            owner: self,
+            backend,
+            epoch: gate.epoch,
+        }
+    }
+
+    pub fn prove(&self, a: u64, b: u64) -> Result<LeasedProof, String> {
+        let lease = self.lease();
+        self.prove_with(&lease, a, b, None)
+    }
+
+    /// Prove on `lease`'s backend for use in `jurisdiction` (the registry's
+    /// active one if `None`), even if a morph has since moved on from it
+    pub fn prove_with(
+        &self,
+        lease: &Lease<'_>,
+        a: u64,
+        b: u64,
+        jurisdiction: Option<&str>,
+    ) -> Result<LeasedProof, String> {
+        self.controller()
+            .check_jurisdiction(lease.backend, jurisdiction)?;
+        let proof = self.pool.prove(lease.backend, a, b)?;
+        self.controller().note_proof(lease.backend);
+        Ok(LeasedProof {
+            proof,
+            backend: lease.backend,
+            epoch: lease.epoch,
+            superseded: self.gate().epoch != lease.epoch,
+        })
+    }
+
+    /// Prove on `backend` whether or not it is current, e.g. for a job
+    /// pinned to the backend it was submitted under. The proof holds a
+    /// lease on `backend`, so a morph away from it still drains it.
+    pub fn prove_on(
+        &self,
+        backend: BackendType,
+        a: u64,
+        b: u64,
+        jurisdiction: Option<&str>,
+    ) -> Result<UniversalProof, String> {
+        let lease = self.lease_on(backend);
+        self.prove_with(&lease, a, b, jurisdiction)
+            .map(|leased| leased.proof)
+    }
+
+    pub fn verify(&self, proof: &UniversalProof) -> Result<bool, String> {
+        self.pool.verify(proof)
+    }
+
+    /// Stop leasing the current backend, drain its leases for up to the
+    /// drain timeout, then morph on behalf of `context`
+    pub fn morph(
+        &self,
+        target_backend: BackendType,
+        context: MorphContext,
+    ) -> Result<DrainOutcome, String> {
+        self.drain_then(Some(target_backend), |controller| {
+            controller.morph_with(target_backend, context)
+        })
+    }
+
+    /// Undo the last morph, draining the current backend's leases first
+    /// just as a morph does
+    pub fn rollback(&self, operator: &str, reason: &str) -> Result<DrainOutcome, String> {
+        self.drain_then(None, |controller| controller.rollback(operator, reason))
+    }
+
+    /// Close the gate, wait up to the drain timeout for leases on the
+    /// current backend, run `switch` on the controller and reopen the gate
+    /// on whatever backend it left current
+    fn drain_then(
+        &self,
+        target_backend: Option<BackendType>,
+        switch: impl FnOnce(&mut MorphController) -> Result<MorphResult, String>,
+    ) -> Result<DrainOutcome, String> {
+        let start = Instant::now();
+        let mut gate = self.gate();
+        while gate.switching {
+            gate = self.wait(gate);
+        }
+        if target_backend == Some(gate.backend) {
+            return Err("Cannot morph to same backend".to_string());
+        }
+        gate.switching = true;
+        let old_backend = gate.backend;
+        let in_flight = gate.in_flight.get(old_backend.name()).copied().unwrap_or(0);
+
+        let deadline = start + self.drain_timeout;
+        while gate.in_flight.contains_key(old_backend.name()) {
+            let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+                break;
+            };
+            gate = self
+                .changed
+                .wait_timeout(gate, left)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        let stragglers = gate.in_flight.get(old_backend.name()).copied().unwrap_or(0);
+        let waited = start.elapsed();
+        drop(gate);
+
+        let mut controller = self.controller();
+        let result = switch(&mut controller);
+        let backend = controller.current_backend();
+        drop(controller);
+
+        let mut gate = self.gate();
+        if gate.backend != backend {
+            gate.backend = backend;
+            gate.epoch += 1;
+        }
+        gate.switching = false;
+        drop(gate);
+        self.changed.notify_all();
+
+        if stragglers > 0 {
+            eprintln!(
+                "[WARN] Morph drain timed out after {}ms: {} proof(s) still on {}",
+                waited.as_millis(),
+                stragglers,
+                old_backend.name()
+            );
+        }
+        Ok(DrainOutcome {
+            result: result?,
+            in_flight,
+            stragglers,
+            waited,
+        })
+    }
+
+    /// Run `f` on the controller, for anything that does not switch
+    /// backends; morphs and rollbacks go through `morph` and `rollback` so
+    /// that proofs in flight are drained
+    pub fn with_controller<R>(&self, f: impl FnOnce(&mut MorphController) -> R) -> R {
+        let mut controller = self.controller();
+        let before = controller.current_backend();
+        let value = f(&mut controller);
+        debug_assert_eq!(
+            controller.current_backend(),
+            before,
+            "with_controller must not switch backends"
+        );
+        value
+    }
+
+    fn controller(&self) -> MutexGuard<'_, MorphController> {
+        self.controller.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn gate(&self) -> MutexGuard<'_, Gate> {
+        self.gate.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wait<'a>(&self, gate: MutexGuard<'a, Gate>) -> MutexGuard<'a, Gate> {
+        self.changed.wait(gate).unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::latency::Operation;
+    use std::thread;
+
+    #[test]
+    fn test_morph_waits_for_leases_to_drain() {
+        let concurrent = Arc::new(ConcurrentController::new(
+            MorphController::new(BackendType::BN254),
+            Duration::from_secs(30),
+        ));
+        let lease = concurrent.lease();
+        assert_eq!(lease.backend, BackendType::BN254);
+
+        let morphing = Arc::clone(&concurrent);
+        let morph = thread::spawn(move || {
+            morphing.morph(
+                BackendType::BLS12_381,
+                MorphContext::manual("test", "drain"),
+            )
+        });
+        while !concurrent.gate().switching {
+            thread::yield_now();
+        }
+        thread::sleep(Duration::from_millis(50));
+        assert!(!morph.is_finished());
+        assert_eq!(concurrent.current_backend(), BackendType::BN254);
+
+        drop(lease);
+        let outcome = morph.join().unwrap().unwrap();
+        assert_eq!(outcome.in_flight, 1);
+        assert_eq!(outcome.stragglers, 0);
+        assert_eq!(outcome.result.new_backend, BackendType::BLS12_381);
+        assert_eq!(concurrent.in_flight(), 0);
+
+        let next = concurrent.lease();
+        assert_eq!(next.backend, BackendType::BLS12_381);
+        assert_eq!(next.epoch, 1);
+    }
+
+    #[test]
+    fn test_straggler_reported_on_old_backend() {
+        let concurrent = ConcurrentController::new(
+            MorphController::new(BackendType::BN254),
+            Duration::from_millis(20),
+        );
+        let lease = concurrent.lease();
+        let outcome = concurrent
+            .morph(
+                BackendType::BLS12_381,
+                MorphContext::manual("test", "timeout"),
+            )
+            .unwrap();
+        assert_eq!(outcome.stragglers, 1);
+        assert_eq!(concurrent.current_backend(), BackendType::BLS12_381);
+
+        let leased = concurrent.prove_with(&lease, 3, 4, None).unwrap();
+        assert_eq!(leased.backend, BackendType::BN254);
+        assert_eq!(leased.proof.backend, BackendType::BN254);
+        assert!(leased.superseded);
+        assert!(concurrent.verify(&leased.proof).unwrap());
+        drop(lease);
+
+        // Does not count towards the commit point of the new backend
+        let fresh = concurrent.prove(3, 4).unwrap();
+        assert_eq!(fresh.backend, BackendType::BLS12_381);
+        assert!(!fresh.superseded);
+        let counted = concurrent.with_controller(|c| c.confirm_commit_point("alice"));
+        assert_eq!(counted.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_rollback_drains_pinned_leases() {
+        let concurrent = ConcurrentController::new(
+            MorphController::new(BackendType::BN254),
+            Duration::from_millis(20),
+        );
+        // A job pinned to BLS12-381 before it became current
+        let pinned = concurrent.lease_on(BackendType::BLS12_381);
+        let outcome = concurrent
+            .morph(BackendType::BLS12_381, MorphContext::manual("test", "up"))
+            .unwrap();
+        assert_eq!(outcome.in_flight, 0);
+
+        let outcome = concurrent.rollback("test", "back").unwrap();
+        assert_eq!(outcome.in_flight, 1);
+        assert_eq!(outcome.stragglers, 1);
+        assert_eq!(outcome.result.new_backend, BackendType::BN254);
+        assert_eq!(concurrent.current_backend(), BackendType::BN254);
+        drop(pinned);
+        assert_eq!(concurrent.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_provers_share_keys() {
+        let concurrent = Arc::new(ConcurrentController::new(
+            MorphController::new(BackendType::BN254),
+            Duration::from_secs(30),
+        ));
+        let provers: Vec<_> = (0..4)
+            .map(|i| {
+                let concurrent = Arc::clone(&concurrent);
+                thread::spawn(move || concurrent.prove(i, i + 1).unwrap())
+            })
+            .collect();
+        for prover in provers {
+            let leased = prover.join().unwrap();
+            assert_eq!(leased.backend, BackendType::BN254);
+            assert!(concurrent.verify(&leased.proof).unwrap());
+        }
+        // Four proofs, one setup
+        let latency = concurrent.pool.latency();
+        let setups = latency.summary(BackendType::BN254, Operation::Setup);
+        assert_eq!(setups.unwrap().samples, 1);
+        let proofs = latency.summary(BackendType::BN254, Operation::Prove);
+        assert_eq!(proofs.unwrap().samples, 4);
+    }
+}
@@ -1,8 +1,18 @@
 // Configuration management for Chameleon-ZK
 
+use crate::flapping::FlapConfig;
+use crate::hedge::HedgeRequirement;
+use crate::jobs::JobsConfig;
+use crate::latency::Slo;
+use crate::metrics::MetricsConfig;
+use crate::prewarm::PrewarmConfig;
+use crate::server::ServerConfig;
+use crate::threat::{CategoryFreshness, CategoryThresholds};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -11,6 +21,14 @@ pub struct Config {
     pub morph: MorphConfig,
     pub output: OutputConfig,
     pub benchmark: BenchmarkConfig,
+    #[serde(default)]
+    pub latency: LatencyConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,15 +42,175 @@ pub struct BackendsConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackendInfo {
     pub enabled: bool,
-    pub security_bits: u32,
     pub description: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MorphConfig {
     pub auto_morph_enabled: bool,
+    /// Overall threat score at or above which we upgrade to BLS12-381.
+    /// morph_threshold in simulator/threat_config.json replaces it when
+    /// that file exists, so the thresholds are set in one place.
+    #[serde(default = "default_threat_threshold")]
     pub threat_threshold: u32,
+    /// Overall threat score below which we fall back to BN254; replaced by
+    /// recovery_threshold in simulator/threat_config.json likewise
+    #[serde(default = "default_recovery_threshold")]
+    pub recovery_threshold: u32,
+    /// Minimum time between two non-emergency morphs
     pub cooldown_seconds: u32,
+    /// How long after a morph `rollback()` is still allowed
+    #[serde(default = "default_rollback_grace_seconds")]
+    pub rollback_grace_seconds: u32,
+    /// Prove and verify on the target backend before switching
+    #[serde(default)]
+    pub canary_enabled: bool,
+    /// Canary fails if the target backend's prove+verify takes longer
+    #[serde(default = "default_canary_latency_budget_ms")]
+    pub canary_latency_budget_ms: u32,
+    /// How long a morph waits for proofs in flight on the old backend
+    #[serde(default = "default_drain_timeout_ms")]
+    pub drain_timeout_ms: u32,
+    /// Which halves of a hedged proof must verify: "both", "either",
+    /// "bn254" or "bls12-381"
+    #[serde(default = "default_hedge_verify")]
+    pub hedge_verify: String,
+    /// Trusted verifying key files (hex) that dual proofs are checked
+    /// against; the keys bundled in a proof file are never used
+    #[serde(default)]
+    pub hedge_bn254_vk: Option<String>,
+    #[serde(default)]
+    pub hedge_bls12_381_vk: Option<String>,
+    /// Freeze automatic morphing when decisions oscillate or readings hug
+    /// a threshold
+    #[serde(default)]
+    pub flap_detection: FlapConfig,
+    /// Set up a backend's keys in the background when the threat trend
+    /// projects a morph to it
+    #[serde(default)]
+    pub prewarm: PrewarmConfig,
+}
+
+fn default_threat_threshold() -> u32 {
+    70
+}
+
+fn default_recovery_threshold() -> u32 {
+    30
+}
+
+fn default_rollback_grace_seconds() -> u32 {
+    900
+}
+
+fn default_canary_latency_budget_ms() -> u32 {
+    2000
+}
+
+fn default_drain_timeout_ms() -> u32 {
+    5000
+}
+
+fn default_hedge_verify() -> String {
+    "both".to_string()
+}
+
+impl MorphConfig {
+    /// Check the thresholds leave a hysteresis band between them
+    pub fn validate(&self) -> Result<(), String> {
+        if self.threat_threshold > 100 {
+            return Err(format!(
+                "threat_threshold must be 0-100, got {}",
+                self.threat_threshold
+            ));
+        }
+        if self.recovery_threshold >= self.threat_threshold {
+            return Err(format!(
+                "recovery_threshold ({}) must be below threat_threshold ({})",
+                self.recovery_threshold, self.threat_threshold
+            ));
+        }
+        self.hedge_requirement()?;
+        Ok(())
+    }
+
+    pub fn hedge_requirement(&self) -> Result<HedgeRequirement, String> {
+        HedgeRequirement::parse(&self.hedge_verify).ok_or_else(|| {
+            format!(
+                "hedge_verify must be both, either, bn254 or bls12-381, got '{}'",
+                self.hedge_verify
+            )
+        })
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_seconds as u64)
+    }
+
+    pub fn rollback_grace(&self) -> Duration {
+        Duration::from_secs(self.rollback_grace_seconds as u64)
+    }
+
+    pub fn canary_latency_budget(&self) -> Duration {
+        Duration::from_millis(self.canary_latency_budget_ms as u64)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_millis(self.drain_timeout_ms as u64)
+    }
+}
+
+/// Simulator settings from simulator/threat_config.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreatConfig {
+    pub morph_threshold: u32,
+    pub recovery_threshold: u32,
+    pub check_interval_seconds: u32,
+    pub total_cycles: u32,
+    pub threat_pattern: String,
+    /// Category name -> weight as a fraction; must add up to 1.0
+    pub weights: BTreeMap<String, f64>,
+    /// Optional per-category elevated/emergency thresholds
+    #[serde(default)]
+    pub thresholds: BTreeMap<String, CategoryThresholds>,
+    /// Optional per-category decay half-life and staleness limit
+    #[serde(default)]
+    pub freshness: BTreeMap<String, CategoryFreshness>,
+}
+
+impl ThreatConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read threat config: {}", e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse threat config: {}", e))
+    }
+
+    /// Load from default location; `None` if there is no threat_config.json
+    pub fn load_default() -> Result<Option<Self>, String> {
+        let paths = vec![
+            "simulator/threat_config.json",
+            "../simulator/threat_config.json",
+            "threat_config.json",
+        ];
+
+        for path in paths {
+            if Path::new(path).exists() {
+                return Self::load(path).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Morph settings with the simulator's upgrade/recovery thresholds applied
+    pub fn apply_to(&self, morph: &MorphConfig) -> MorphConfig {
+        MorphConfig {
+            threat_threshold: self.morph_threshold,
+            recovery_threshold: self.recovery_threshold,
+            ..morph.clone()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +218,66 @@ pub struct OutputConfig {
     pub proof_directory: String,
     pub benchmark_directory: String,
     pub report_directory: String,
+    #[serde(default = "default_audit_log")]
+    pub audit_log: String,
+    #[serde(default = "default_morph_journal")]
+    pub morph_journal: String,
+    /// Threat reports turned away by the feed, one JSON object per line
+    #[serde(default = "default_rejected_reports")]
+    pub rejected_reports: String,
+}
+
+fn default_audit_log() -> String {
+    "output/audit/morph_audit.jsonl".to_string()
+}
+
+fn default_morph_journal() -> String {
+    "output/audit/morph_journal.jsonl".to_string()
+}
+
+fn default_rejected_reports() -> String {
+    "output/audit/rejected_reports.jsonl".to_string()
+}
+
+/// Latency SLOs, e.g. "p95 prove < 200ms" or "bls12-381 p95 prove < 200ms"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyConfig {
+    /// Samples kept per backend and operation
+    #[serde(default = "default_latency_window")]
+    pub window: usize,
+    #[serde(default)]
+    pub slos: Vec<String>,
+    /// How often `serve` scores latency against the SLOs and decides on it
+    #[serde(default = "default_latency_check_interval_seconds")]
+    pub check_interval_seconds: u64,
+}
+
+fn default_latency_window() -> usize {
+    100
+}
+
+fn default_latency_check_interval_seconds() -> u64 {
+    30
+}
+
+impl LatencyConfig {
+    pub fn slos(&self) -> Result<Vec<Slo>, String> {
+        self.slos.iter().map(|s| Slo::parse(s)).collect()
+    }
+
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_seconds.max(1))
+    }
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            window: default_latency_window(),
+            slos: Vec::new(),
+            check_interval_seconds: default_latency_check_interval_seconds(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,8 +292,13 @@ impl Config {
         let content = fs::read_to_string(path)
             .map_err(|e| format!("Failed to read config: {}", e))?;
         
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse config: {}", e))
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse config: {}", e))?;
+        config
+            .morph
+            .validate()
+            .map_err(|e| format!("Invalid morph config: {}", e))?;
+        Ok(config)
     }
     
     /// Load from default location
@@ -95,29 +338,62 @@ impl Default for Config {
             backends: BackendsConfig {
                 bn254: BackendInfo {
                     enabled: true,
-                    security_bits: 100,
                     description: "Ethereum-optimized".to_string(),
                 },
                 bls12_381: BackendInfo {
                     enabled: true,
-                    security_bits: 128,
                     description: "Higher security".to_string(),
                 },
             },
             morph: MorphConfig {
                 auto_morph_enabled: true,
                 threat_threshold: 70,
+                recovery_threshold: 30,
                 cooldown_seconds: 300,
+                rollback_grace_seconds: default_rollback_grace_seconds(),
+                canary_enabled: false,
+                canary_latency_budget_ms: default_canary_latency_budget_ms(),
+                drain_timeout_ms: default_drain_timeout_ms(),
+                hedge_verify: default_hedge_verify(),
+                hedge_bn254_vk: None,
+                hedge_bls12_381_vk: None,
+                flap_detection: FlapConfig::default(),
+                prewarm: PrewarmConfig::default(),
             },
             output: OutputConfig {
                 proof_directory: "output/proofs".to_string(),
                 benchmark_directory: "output/benchmarks".to_string(),
                 report_directory: "output/reports".to_string(),
+                audit_log: default_audit_log(),
+                morph_journal: default_morph_journal(),
+                rejected_reports: default_rejected_reports(),
             },
             benchmark: BenchmarkConfig {
                 iterations: 5,
                 warmup_iterations: 1,
             },
+            latency: LatencyConfig::default(),
+            metrics: MetricsConfig::default(),
+            jobs: JobsConfig::default(),
+            server: ServerConfig::default(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_rejects_inverted_thresholds() {
+        let mut config = Config::default();
+        config.morph.recovery_threshold = 80;
+        let path = std::env::temp_dir().join(format!("config-{}.json", std::process::id()));
+        fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+        let loaded = Config::load(path.to_str().unwrap());
+        fs::remove_file(&path).ok();
+
+        let err = loaded.unwrap_err();
+        assert!(err.contains("recovery_threshold (80)"), "{}", err);
+    }
+}
@@ -47,6 +47,20 @@ pub enum ChameleonError {
     #[error("State commitment mismatch during morph")]
     StateCommitmentMismatch,
     
+    #[error("{backend} prove+verify took {took_ms}ms, budget is {budget_ms}ms")]
+    CanaryOverBudget {
+        backend: String,
+        took_ms: u128,
+        budget_ms: u128,
+    },
+    
+    #[error("{backend} is not approved in {jurisdiction}; approved: {approved}")]
+    BackendNotApproved {
+        backend: String,
+        jurisdiction: String,
+        approved: String,
+    },
+    
     // Serialization errors
     #[error("Serialization failed: {reason}")]
     SerializationFailed { reason: String },
@@ -63,6 +77,13 @@ pub enum ChameleonError {
     ConfigError { field: String, reason: String },
 }
 
+// Most of the crate still reports errors as strings
+impl From<ChameleonError> for String {
+    fn from(e: ChameleonError) -> Self {
+        e.to_string()
+    }
+}
+
 /// Result type alias for Chameleon operations
 pub type ChameleonResult<T> = Result<T, ChameleonError>;
 
@@ -0,0 +1,343 @@
+// Flapping and threshold-manipulation detection
+//
+// Whoever can move the threat feed could walk it back and forth across a
+// threshold and make us morph over and over, burning proving capacity on
+// state proofs. The detector watches for two things:
+//
+// - oscillation: automatic morph requests that keep changing direction
+// - threshold hugging: readings sitting within a few points of a
+//   threshold, on both sides of it, far more often than a real trend would
+//
+// Either one freezes automatic morphing and raises an alert. Automatic
+// morphs stay frozen, emergencies included, until an operator confirms;
+// manual morphs are not affected.
+
+use crate::simulator::ThreatLevel;
+use crate::threat::ThreatModel;
+use crate::types::BackendType;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlapConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// How far back readings and morph requests are considered
+    #[serde(default = "default_window_seconds")]
+    pub window_seconds: u64,
+    /// Direction changes among automatic morph requests that freeze
+    #[serde(default = "default_max_reversals")]
+    pub max_reversals: usize,
+    /// Points either side of a threshold that count as hugging it
+    #[serde(default = "default_hug_margin")]
+    pub hug_margin: u32,
+    /// Share of readings near one threshold that counts as hugging
+    #[serde(default = "default_hug_fraction")]
+    pub hug_fraction: f64,
+    /// Readings needed in the window before hugging is judged
+    #[serde(default = "default_min_readings")]
+    pub min_readings: usize,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_window_seconds() -> u64 {
+    3600
+}
+
+fn default_max_reversals() -> usize {
+    3
+}
+
+fn default_hug_margin() -> u32 {
+    3
+}
+
+fn default_hug_fraction() -> f64 {
+    0.6
+}
+
+fn default_min_readings() -> usize {
+    6
+}
+
+impl Default for FlapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            window_seconds: default_window_seconds(),
+            max_reversals: default_max_reversals(),
+            hug_margin: default_hug_margin(),
+            hug_fraction: default_hug_fraction(),
+            min_readings: default_min_readings(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlapKind {
+    Oscillation {
+        reversals: usize,
+    },
+    ThresholdHugging {
+        category: String,
+        threshold: u32,
+        near: usize,
+        readings: usize,
+    },
+    /// Frozen before a restart, as the audit log recorded it
+    Restored {
+        reason: String,
+    },
+}
+
+impl fmt::Display for FlapKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlapKind::Oscillation { reversals } => {
+                write!(f, "morph requests reversed {} times", reversals)
+            }
+            FlapKind::ThresholdHugging {
+                category,
+                threshold,
+                near,
+                readings,
+            } => write!(
+                f,
+                "{} hugging threshold {} ({} of {} readings)",
+                category, threshold, near, readings
+            ),
+            FlapKind::Restored { reason } => write!(f, "{} (before restart)", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FlapAlert {
+    /// Clock reading of whoever owns the detector
+    pub at: Duration,
+    pub timestamp: String,
+    pub kind: FlapKind,
+}
+
+pub struct FlapDetector {
+    config: FlapConfig,
+    /// (category or "overall", threshold) pairs to watch
+    thresholds: Vec<(String, u32)>,
+    readings: VecDeque<(Duration, ThreatLevel)>,
+    /// Targets of automatic morph requests
+    requests: VecDeque<(Duration, BackendType)>,
+    frozen: Option<FlapAlert>,
+    alerts: Vec<FlapAlert>,
+}
+
+impl FlapDetector {
+    /// Watch every category threshold of `model` and the overall
+    /// upgrade/recovery thresholds
+    pub fn new(
+        config: FlapConfig,
+        model: &ThreatModel,
+        threat_threshold: u32,
+        recovery_threshold: u32,
+    ) -> Self {
+        let mut thresholds = Vec::new();
+        for category in &model.categories {
+            if let Some(limits) = category.thresholds {
+                thresholds.push((category.name.clone(), limits.elevated));
+                thresholds.push((category.name.clone(), limits.emergency));
+            }
+        }
+        thresholds.push(("overall".to_string(), threat_threshold));
+        thresholds.push(("overall".to_string(), recovery_threshold));
+
+        Self {
+            config,
+            thresholds,
+            readings: VecDeque::new(),
+            requests: VecDeque::new(),
+            frozen: None,
+            alerts: Vec::new(),
+        }
+    }
+
+    /// The alert automatic morphing is frozen on, if any
+    pub fn frozen(&self) -> Option<&FlapAlert> {
+        self.frozen.as_ref()
+    }
+
+    /// Every alert raised so far
+    pub fn alerts(&self) -> &[FlapAlert] {
+        &self.alerts
+    }
+
+    /// Record a threat reading; returns the alert if it froze auto-morph
+    pub fn observe(&mut self, at: Duration, threat: &ThreatLevel) -> Option<FlapAlert> {
+        self.readings.push_back((at, threat.clone()));
+        self.prune(at);
+        if self.frozen.is_some() || self.readings.len() < self.config.min_readings {
+            return None;
+        }
+
+        let kind = self.thresholds.iter().find_map(|(category, threshold)| {
+            let scores: Vec<u32> = self
+                .readings
+                .iter()
+                .map(|(_, t)| match category.as_str() {
+                    "overall" => t.overall,
+                    name => t.score(name),
+                })
+                .filter(|s| s.abs_diff(*threshold) <= self.config.hug_margin)
+                .collect();
+            let both_sides =
+                scores.iter().any(|s| s >= threshold) && scores.iter().any(|s| s < threshold);
+            let share = scores.len() as f64 / self.readings.len() as f64;
+
+            (both_sides && share >= self.config.hug_fraction).then(|| FlapKind::ThresholdHugging {
+                category: category.clone(),
+                threshold: *threshold,
+                near: scores.len(),
+                readings: self.readings.len(),
+            })
+        })?;
+        Some(self.freeze(at, kind))
+    }
+
+    /// Record an automatic morph request to `target`; returns the alert if
+    /// it froze auto-morph
+    pub fn record_request(&mut self, at: Duration, target: BackendType) -> Option<FlapAlert> {
+        self.requests.push_back((at, target));
+        self.prune(at);
+        if self.frozen.is_some() {
+            return None;
+        }
+
+        let reversals = self
+            .requests
+            .iter()
+            .zip(self.requests.iter().skip(1))
+            .filter(|(a, b)| a.1 != b.1)
+            .count();
+        if reversals < self.config.max_reversals {
+            return None;
+        }
+        Some(self.freeze(at, FlapKind::Oscillation { reversals }))
+    }
+
+    /// Come back frozen on a freeze recorded before a restart
+    pub fn restore_freeze(&mut self, at: Duration, reason: &str) -> FlapAlert {
+        self.freeze(
+            at,
+            FlapKind::Restored {
+                reason: reason.to_string(),
+            },
+        )
+    }
+
+    /// Operator confirmation: unfreeze and forget the evidence, so the same
+    /// readings do not freeze us again straight away
+    pub fn confirm(&mut self) -> Option<FlapAlert> {
+        self.readings.clear();
+        self.requests.clear();
+        self.frozen.take()
+    }
+
+    fn prune(&mut self, now: Duration) {
+        let window = Duration::from_secs(self.config.window_seconds);
+        let expired = |at: &Duration| now.saturating_sub(*at) > window;
+        while self.readings.front().is_some_and(|(at, _)| expired(at)) {
+            self.readings.pop_front();
+        }
+        while self.requests.front().is_some_and(|(at, _)| expired(at)) {
+            self.requests.pop_front();
+        }
+    }
+
+    fn freeze(&mut self, at: Duration, kind: FlapKind) -> FlapAlert {
+        let alert = FlapAlert {
+            at,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            kind,
+        };
+        eprintln!(
+            "[ALERT] {} auto-morph frozen: {}; manual confirmation required",
+            alert.timestamp, alert.kind
+        );
+        self.frozen = Some(alert.clone());
+        self.alerts.push(alert.clone());
+        alert
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> FlapDetector {
+        FlapDetector::new(FlapConfig::default(), &ThreatModel::default(), 50, 20)
+    }
+
+    fn minutes(n: u64) -> Duration {
+        Duration::from_secs(n * 60)
+    }
+
+    #[test]
+    fn test_oscillation_freezes_until_confirmed() {
+        let mut detector = detector();
+        let targets = [BackendType::BLS12_381, BackendType::BN254];
+        for i in 0..3 {
+            assert!(detector
+                .record_request(minutes(i), targets[i as usize % 2])
+                .is_none());
+        }
+        let alert = detector
+            .record_request(minutes(3), BackendType::BN254)
+            .unwrap();
+        assert_eq!(alert.kind, FlapKind::Oscillation { reversals: 3 });
+        assert!(detector.frozen().is_some());
+
+        assert!(detector.confirm().is_some());
+        assert!(detector.frozen().is_none());
+        // Slow, genuine back-and-forth stays under the limit
+        for i in 0..4 {
+            let at = minutes(100 + i * 90);
+            assert!(detector
+                .record_request(at, targets[i as usize % 2])
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_threshold_hugging_detected() {
+        let model = ThreatModel::default();
+        let mut hugged = detector();
+        for (i, quantum) in [58, 61, 59, 62, 57, 60].into_iter().enumerate() {
+            let reading = model.level(&[("quantum", quantum)]);
+            let alert = hugged.observe(minutes(i as u64), &reading);
+            assert_eq!(alert.is_some(), i == 5);
+        }
+        let Some(FlapAlert {
+            kind:
+                FlapKind::ThresholdHugging {
+                    category,
+                    threshold,
+                    ..
+                },
+            ..
+        }) = hugged.frozen()
+        else {
+            panic!("expected hugging");
+        };
+        assert_eq!((category.as_str(), *threshold), ("quantum", 60));
+
+        // A real rise passes through the threshold without lingering
+        let mut rising = detector();
+        for (i, quantum) in (0..=100).step_by(10).enumerate() {
+            let reading = model.level(&[("quantum", quantum)]);
+            assert!(rising.observe(minutes(i as u64), &reading).is_none());
+        }
+    }
+}
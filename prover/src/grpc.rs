@@ -0,0 +1,543 @@
+// gRPC front end of the prover daemon (`chameleon serve --grpc ADDR`)
+//
+// Serves the same ProverService as the HTTP API, behind the schema in
+// client/proto/chameleon.proto; the chameleon-client crate holds the
+// generated code and a client for other services. Proving and verifying
+// block, so each call runs on tokio's blocking pool, and the runtime has
+// a thread of its own so the rest of the prover stays synchronous.
+//
+// Errors map onto status codes the way the HTTP API maps them onto 400,
+// 401 and 409: bad input is INVALID_ARGUMENT, a missing or unknown
+// operator token UNAUTHENTICATED, a refusal FAILED_PRECONDITION. Morph
+// takes its token from `authorization: Bearer` metadata, as over HTTP.
+
+use crate::server::{
+    MorphEvent, MorphRequest, MorphResponse, ProveRequest, ProverService, ServiceError,
+    StatusResponse,
+};
+use crate::simulator::{MorphDecision, ThreatLevel};
+use crate::types::{BackendType, UniversalProof};
+use chameleon_client::pb::prover_server::{Prover, ProverServer};
+use chameleon_client::pb::{self, Backend};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+
+/// Largest BatchVerify accepted
+pub const MAX_BATCH: usize = 1024;
+
+/// Open WatchMorphEvents streams; each holds a thread, so more are refused
+pub const MAX_WATCHERS: usize = 64;
+
+/// How often morph watchers check whether they should stop
+const WATCH_POLL: Duration = Duration::from_millis(200);
+
+fn backend_to_pb(backend: BackendType) -> i32 {
+    match backend {
+        BackendType::BN254 => Backend::Bn254.into(),
+        BackendType::BLS12_381 => Backend::Bls12381.into(),
+    }
+}
+
+fn backend_from_pb(value: i32) -> Result<BackendType, String> {
+    match Backend::try_from(value) {
+        Ok(Backend::Bn254) => Ok(BackendType::BN254),
+        Ok(Backend::Bls12381) => Ok(BackendType::BLS12_381),
+        _ => Err(format!("Unknown backend {}", value)),
+    }
+}
+
+impl From<UniversalProof> for pb::UniversalProof {
+    fn from(proof: UniversalProof) -> Self {
+        Self {
+            backend: backend_to_pb(proof.backend),
+            proof_bytes: proof.proof_bytes,
+            public_inputs: proof.public_inputs,
+        }
+    }
+}
+
+impl TryFrom<pb::UniversalProof> for UniversalProof {
+    type Error = String;
+
+    fn try_from(proof: pb::UniversalProof) -> Result<Self, String> {
+        Ok(Self {
+            backend: backend_from_pb(proof.backend)?,
+            proof_bytes: proof.proof_bytes,
+            public_inputs: proof.public_inputs,
+        })
+    }
+}
+
+impl From<ThreatLevel> for pb::ThreatLevel {
+    fn from(threat: ThreatLevel) -> Self {
+        Self {
+            quantum: threat.quantum,
+            regulatory: threat.regulatory,
+            overall: threat.overall,
+            other: threat.other.into_iter().collect(),
+            stale: threat.stale.into_iter().collect(),
+        }
+    }
+}
+
+impl From<MorphDecision> for pb::MorphDecision {
+    fn from(decision: MorphDecision) -> Self {
+        Self {
+            should_morph: decision.should_morph,
+            recommended_backend: backend_to_pb(decision.recommended_backend),
+            reason: decision.reason,
+            emergency: decision.emergency,
+            rule: decision.rule,
+        }
+    }
+}
+
+impl From<MorphResponse> for pb::MorphResponse {
+    fn from(morph: MorphResponse) -> Self {
+        Self {
+            result: Some(pb::MorphResult {
+                success: true,
+                old_backend: backend_to_pb(morph.old_backend),
+                new_backend: backend_to_pb(morph.new_backend),
+                duration_ms: morph.duration_ms as u64,
+            }),
+            in_flight: morph.in_flight as u32,
+            stragglers: morph.stragglers as u32,
+            drain_ms: morph.drain_ms as u64,
+        }
+    }
+}
+
+impl From<MorphEvent> for pb::MorphEvent {
+    fn from(event: MorphEvent) -> Self {
+        Self {
+            timestamp: event.timestamp,
+            trigger: event.trigger.name().to_string(),
+            operator: event.operator,
+            reason: event.reason,
+            morph: Some(event.morph.into()),
+            threat: event.threat.map(Into::into),
+            decision: event.decision.map(Into::into),
+        }
+    }
+}
+
+impl From<StatusResponse> for pb::StatusResponse {
+    fn from(status: StatusResponse) -> Self {
+        Self {
+            current_backend: backend_to_pb(status.current_backend),
+            backends: status
+                .backends
+                .into_iter()
+                .map(|backend| pb::BackendStatus {
+                    backend: backend_to_pb(backend.backend),
+                    keys_loaded: backend.keys_loaded,
+                    warming: backend.warming,
+                    security_bits: backend.security_bits,
+                })
+                .collect(),
+            threat: Some(status.threat.into()),
+            proofs_in_flight: status.proofs_in_flight as u32,
+            cooldown_remaining_secs: status.cooldown_remaining_secs,
+            auto_morph: status.auto_morph,
+            auto_morph_frozen: status.auto_morph_frozen,
+        }
+    }
+}
+
+impl From<ServiceError> for Status {
+    fn from(error: ServiceError) -> Self {
+        match error {
+            ServiceError::Invalid(message) => Status::invalid_argument(message),
+            ServiceError::Refused(message) => Status::failed_precondition(message),
+            ServiceError::Unauthorized(message) => Status::unauthenticated(message),
+        }
+    }
+}
+
+/// Run `work` on the blocking pool
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, ServiceError> + Send + 'static,
+) -> Result<T, Status> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| Status::internal(format!("Request failed: {}", e)))?
+        .map_err(Status::from)
+}
+
+struct GrpcProver {
+    service: Arc<ProverService>,
+    /// Set on shutdown, so open WatchMorphEvents streams end
+    stopping: Arc<AtomicBool>,
+    /// Open WatchMorphEvents streams
+    watchers: Arc<AtomicUsize>,
+}
+
+impl GrpcProver {
+    /// The operator whose token is in the `authorization` metadata
+    fn operator<T>(&self, request: &Request<T>) -> Result<String, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        Ok(self.service.authenticate(token)?)
+    }
+}
+
+#[tonic::async_trait]
+impl Prover for GrpcProver {
+    async fn prove(
+        &self,
+        request: Request<pb::ProveRequest>,
+    ) -> Result<Response<pb::UniversalProof>, Status> {
+        let request = request.into_inner();
+        let request = ProveRequest {
+            a: request.a,
+            b: request.b,
+            jurisdiction: request.jurisdiction,
+        };
+        let service = Arc::clone(&self.service);
+        let proof = blocking(move || service.prove(&request)).await?;
+        Ok(Response::new(proof.into()))
+    }
+
+    async fn verify(
+        &self,
+        request: Request<pb::UniversalProof>,
+    ) -> Result<Response<pb::VerifyResponse>, Status> {
+        let proof =
+            UniversalProof::try_from(request.into_inner()).map_err(Status::invalid_argument)?;
+        let service = Arc::clone(&self.service);
+        let valid = blocking(move || service.verify(&proof)).await?;
+        Ok(Response::new(pb::VerifyResponse { valid }))
+    }
+
+    async fn batch_verify(
+        &self,
+        request: Request<pb::BatchVerifyRequest>,
+    ) -> Result<Response<pb::BatchVerifyResponse>, Status> {
+        let proofs = request.into_inner().proofs;
+        if proofs.len() > MAX_BATCH {
+            return Err(Status::invalid_argument(format!(
+                "Batch of {} proofs exceeds {}",
+                proofs.len(),
+                MAX_BATCH
+            )));
+        }
+        let service = Arc::clone(&self.service);
+        let results = blocking(move || {
+            Ok(proofs
+                .into_iter()
+                .map(|proof| {
+                    let checked = UniversalProof::try_from(proof).and_then(|proof| {
+                        service.verify(&proof).map_err(|e| e.message().to_string())
+                    });
+                    match checked {
+                        Ok(valid) => pb::BatchVerifyResult { valid, error: None },
+                        Err(e) => pb::BatchVerifyResult {
+                            valid: false,
+                            error: Some(e),
+                        },
+                    }
+                })
+                .collect::<Vec<_>>())
+        })
+        .await?;
+        Ok(Response::new(pb::BatchVerifyResponse {
+            valid_count: results.iter().filter(|r| r.valid).count() as u32,
+            results,
+        }))
+    }
+
+    async fn morph(
+        &self,
+        request: Request<pb::MorphRequest>,
+    ) -> Result<Response<pb::MorphResponse>, Status> {
+        let operator = self.operator(&request)?;
+        let request = request.into_inner();
+        let to = backend_from_pb(request.to).map_err(Status::invalid_argument)?;
+        let request = MorphRequest {
+            to: to.name().to_string(),
+            reason: request.reason,
+            emergency: request.emergency,
+        };
+        let service = Arc::clone(&self.service);
+        let morph = blocking(move || service.morph(&request, &operator)).await?;
+        Ok(Response::new(morph.into()))
+    }
+
+    async fn resume_auto_morph(
+        &self,
+        request: Request<pb::ResumeAutoMorphRequest>,
+    ) -> Result<Response<pb::ResumeAutoMorphResponse>, Status> {
+        let operator = self.operator(&request)?;
+        let service = Arc::clone(&self.service);
+        let resumed = blocking(move || service.resume(&operator)).await?;
+        Ok(Response::new(pb::ResumeAutoMorphResponse {
+            operator: resumed.operator,
+            frozen_on: resumed.frozen_on,
+        }))
+    }
+
+    async fn get_status(
+        &self,
+        _request: Request<pb::StatusRequest>,
+    ) -> Result<Response<pb::StatusResponse>, Status> {
+        let service = Arc::clone(&self.service);
+        let status = blocking(move || Ok(service.status())).await?;
+        Ok(Response::new(status.into()))
+    }
+
+    type WatchMorphEventsStream = ReceiverStream<Result<pb::MorphEvent, Status>>;
+
+    async fn watch_morph_events(
+        &self,
+        _request: Request<pb::WatchMorphEventsRequest>,
+    ) -> Result<Response<Self::WatchMorphEventsStream>, Status> {
+        if self.watchers.fetch_add(1, Ordering::SeqCst) >= MAX_WATCHERS {
+            self.watchers.fetch_sub(1, Ordering::SeqCst);
+            return Err(Status::resource_exhausted(format!(
+                "Already {} morph watchers",
+                MAX_WATCHERS
+            )));
+        }
+        let watchers = Arc::clone(&self.watchers);
+        let events = self.service.watch_morphs();
+        let stopping = Arc::clone(&self.stopping);
+        let (tx, rx) = mpsc::channel(16);
+        // Forward from the service's channel until the client goes away
+        // or the server stops
+        thread::spawn(move || {
+            while !stopping.load(Ordering::SeqCst) && !tx.is_closed() {
+                match events.recv_timeout(WATCH_POLL) {
+                    Ok(event) => {
+                        if tx.blocking_send(Ok(event.into())).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            watchers.fetch_sub(1, Ordering::SeqCst);
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+pub struct GrpcServer {
+    addr: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    shutdown: oneshot::Sender<()>,
+    thread: JoinHandle<Result<(), String>>,
+}
+
+impl GrpcServer {
+    /// Bind `addr` and serve `service` on a runtime of its own
+    pub fn start(addr: &str, service: Arc<ProverService>) -> Result<Self, String> {
+        let listener =
+            TcpListener::bind(addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+        let local = listener
+            .local_addr()
+            .and_then(|local| listener.set_nonblocking(true).map(|_| local))
+            .map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to start gRPC runtime: {}", e))?;
+
+        let stopping = Arc::new(AtomicBool::new(false));
+        let prover = GrpcProver {
+            service,
+            stopping: Arc::clone(&stopping),
+            watchers: Arc::new(AtomicUsize::new(0)),
+        };
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let thread = thread::spawn(move || {
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener)
+                    .map_err(|e| format!("Failed to listen: {}", e))?;
+                tonic::transport::Server::builder()
+                    .add_service(ProverServer::new(prover))
+                    .serve_with_incoming_shutdown(TcpIncoming::from(listener), async {
+                        stopped.await.ok();
+                    })
+                    .await
+                    .map_err(|e| format!("gRPC server failed: {}", e))
+            })
+        });
+
+        Ok(Self {
+            addr: local,
+            stopping,
+            shutdown,
+            thread,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop accepting, end morph watches, and wait for calls in progress
+    pub fn shutdown(self) -> Result<(), String> {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.shutdown.send(()).ok();
+        self.thread
+            .join()
+            .map_err(|_| "gRPC server panicked".to_string())?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::morph::MorphController;
+    use crate::simulator::ThreatSimulator;
+    use crate::threat::ThreatModel;
+    use chameleon_client::Client;
+    use sha2::{Digest, Sha256};
+    use std::collections::BTreeMap;
+    use tonic::Code;
+
+    const TOKEN: &str = "ops-secret";
+
+    fn start() -> (GrpcServer, tokio::runtime::Runtime) {
+        let mut config = Config::default();
+        config.morph.cooldown_seconds = 0;
+        let controller = MorphController::from_config(
+            BackendType::BN254,
+            &config.morph,
+            &ThreatModel::default(),
+        );
+        controller.setup(BackendType::BN254).unwrap();
+        let simulator = ThreatSimulator::from_config(BackendType::BN254, &config.morph);
+        let hash = hex::encode(Sha256::digest(TOKEN.as_bytes()));
+        let service = ProverService::new(controller, simulator, &config.morph)
+            .with_operators(BTreeMap::from([("ops".to_string(), hash)]));
+        let server = GrpcServer::start("127.0.0.1:0", Arc::new(service)).unwrap();
+        (server, tokio::runtime::Runtime::new().unwrap())
+    }
+
+    #[test]
+    fn test_prove_and_batch_verify() {
+        let (server, runtime) = start();
+        let endpoint = format!("http://{}", server.addr());
+        runtime.block_on(async {
+            let mut client = Client::connect(endpoint).await.unwrap();
+            let proof = client.prove(6, 7).await.unwrap();
+            assert_eq!(proof.backend, i32::from(Backend::Bn254));
+            assert_eq!(proof.public_inputs, vec!["42"]);
+            assert!(client.verify(proof.clone()).await.unwrap());
+
+            let wrong_statement = pb::UniversalProof {
+                public_inputs: vec!["43".to_string()],
+                ..proof.clone()
+            };
+            let no_backend = pb::UniversalProof {
+                backend: Backend::Unspecified.into(),
+                ..proof.clone()
+            };
+            let results = client
+                .batch_verify(vec![proof, wrong_statement, no_backend])
+                .await
+                .unwrap();
+            assert!(results[0].valid && results[0].error.is_none());
+            assert!(!results[1].valid && results[1].error.is_none());
+            assert!(!results[2].valid && results[2].error.is_some());
+
+            let overflow = client.prove(u64::MAX, 2).await.unwrap_err();
+            assert_eq!(overflow.code(), Code::InvalidArgument);
+        });
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_watch_morph_events() {
+        let (server, runtime) = start();
+        let endpoint = format!("http://{}", server.addr());
+        let mut events = runtime.block_on(async {
+            let mut anonymous = Client::connect(endpoint.clone()).await.unwrap();
+            let refused = anonymous.morph(Backend::Bls12381, "no token").await;
+            assert_eq!(refused.unwrap_err().code(), Code::Unauthenticated);
+            let mut forged = anonymous.clone().with_token("guess").unwrap();
+            let refused = forged.morph(Backend::Bls12381, "wrong token").await;
+            assert_eq!(refused.unwrap_err().code(), Code::Unauthenticated);
+            let refused = anonymous.resume_auto_morph().await;
+            assert_eq!(refused.unwrap_err().code(), Code::Unauthenticated);
+
+            let client = Client::connect(endpoint).await.unwrap();
+            let mut client = client.with_token(TOKEN).unwrap();
+            // Nothing to resume from
+            let refused = client.resume_auto_morph().await;
+            assert_eq!(refused.unwrap_err().code(), Code::FailedPrecondition);
+            let mut events = client.watch_morph_events().await.unwrap();
+
+            let morph = client
+                .morph(Backend::Bls12381, "key rotation")
+                .await
+                .unwrap();
+            assert_eq!(
+                morph.result.unwrap().new_backend,
+                i32::from(Backend::Bls12381)
+            );
+            let refused = client.morph(Backend::Bls12381, "again").await;
+            assert_eq!(refused.unwrap_err().code(), Code::FailedPrecondition);
+
+            let event = events.message().await.unwrap().unwrap();
+            assert_eq!(event.trigger, "manual");
+            assert_eq!(event.operator, "ops");
+            assert_eq!(
+                event.morph.unwrap().result.unwrap().old_backend,
+                i32::from(Backend::Bn254)
+            );
+
+            let status = client.status().await.unwrap();
+            assert_eq!(status.current_backend, i32::from(Backend::Bls12381));
+            assert_eq!(status.backends.len(), 2);
+            events
+        });
+        // Shutdown ends the open watch instead of waiting on it
+        server.shutdown().unwrap();
+        assert!(runtime.block_on(events.message()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_watchers_capped() {
+        let (server, runtime) = start();
+        let endpoint = format!("http://{}", server.addr());
+        runtime.block_on(async {
+            let mut client = Client::connect(endpoint).await.unwrap();
+            let mut streams = Vec::new();
+            for _ in 0..MAX_WATCHERS {
+                streams.push(client.watch_morph_events().await.unwrap());
+            }
+            let refused = client.watch_morph_events().await.unwrap_err();
+            assert_eq!(refused.code(), Code::ResourceExhausted);
+
+            // A closed stream frees its slot once its thread notices
+            streams.pop();
+            let mut reopened = Err(refused);
+            for _ in 0..20 {
+                tokio::time::sleep(WATCH_POLL).await;
+                reopened = client.watch_morph_events().await;
+                if reopened.is_ok() {
+                    break;
+                }
+            }
+            assert!(reopened.is_ok());
+        });
+        server.shutdown().unwrap();
+    }
+}
@@ -0,0 +1,386 @@
+// Hedged proofs: one statement proven on both curves
+//
+// When the threat picture is unclear, a high-value statement can be proven
+// on BN254 and BLS12-381 at once instead of betting on one. The DualProof
+// keeps both proofs under a single public-input set; a verifier accepts it
+// when the proofs its HedgeRequirement asks for hold: both, either, or one
+// named curve (say BN254 for cheap on-chain checks while BLS12-381 is kept
+// for the record).
+//
+// Backend keys come from a fresh setup, so an exported dual proof carries
+// both verifying keys for reference. Anyone can rewrite those along with the
+// proofs, so `chameleon verify` checks it against verifying keys the
+// verifier trusts, given by flag or config, and refuses without them.
+
+use crate::bls12_381_backend::BLS12_381Backend;
+use crate::bn254_backend::BN254Backend;
+use crate::calldata::Calldata;
+use crate::types::{BackendType, UniversalProof};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Which proofs of a DualProof must verify
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HedgeRequirement {
+    Both,
+    Either,
+    Only(BackendType),
+}
+
+impl HedgeRequirement {
+    /// "both", "either", or a backend name such as "bn254"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "both" => Some(HedgeRequirement::Both),
+            "either" | "any" => Some(HedgeRequirement::Either),
+            other => BackendType::parse(other).map(HedgeRequirement::Only),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            HedgeRequirement::Both => "both".to_string(),
+            HedgeRequirement::Either => "either".to_string(),
+            HedgeRequirement::Only(backend) => backend.name().to_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DualProof {
+    pub public_inputs: Vec<String>,
+    pub bn254: UniversalProof,
+    pub bls12_381: UniversalProof,
+}
+
+impl DualProof {
+    /// Pair two proofs of the same statement
+    pub fn new(bn254: UniversalProof, bls12_381: UniversalProof) -> Result<Self, String> {
+        if bn254.backend != BackendType::BN254 || bls12_381.backend != BackendType::BLS12_381 {
+            return Err("Dual proof needs a BN254 and a BLS12-381 proof".to_string());
+        }
+        if bn254.public_inputs != bls12_381.public_inputs {
+            return Err(format!(
+                "Dual proof public inputs differ: BN254 {:?}, BLS12-381 {:?}",
+                bn254.public_inputs, bls12_381.public_inputs
+            ));
+        }
+        Ok(Self {
+            public_inputs: bn254.public_inputs.clone(),
+            bn254,
+            bls12_381,
+        })
+    }
+
+    /// The proof on `backend`, carrying the shared public inputs
+    pub fn proof(&self, backend: BackendType) -> UniversalProof {
+        let proof = match backend {
+            BackendType::BN254 => &self.bn254,
+            BackendType::BLS12_381 => &self.bls12_381,
+        };
+        UniversalProof {
+            public_inputs: self.public_inputs.clone(),
+            ..proof.clone()
+        }
+    }
+
+    /// "dual:" plus the first 16 hex chars of SHA-256 over both proofs
+    pub fn id(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.bn254.proof_bytes);
+        hasher.update(&self.bls12_381.proof_bytes);
+        format!("dual:{}", &hex::encode(hasher.finalize())[..16])
+    }
+
+    pub fn calldata(&self) -> Result<[Calldata; 2], String> {
+        Ok([
+            Calldata::for_proof(&self.proof(BackendType::BN254))?,
+            Calldata::for_proof(&self.proof(BackendType::BLS12_381))?,
+        ])
+    }
+
+    /// Check the proofs `requirement` asks for with `verify`. Under
+    /// `Either` a proof that cannot be checked counts as failed, and the
+    /// error is returned only if neither can be.
+    pub fn verify_with(
+        &self,
+        requirement: HedgeRequirement,
+        verify: impl Fn(&UniversalProof) -> Result<bool, String>,
+    ) -> Result<DualVerification, String> {
+        let check = |backend| verify(&self.proof(backend));
+        let (bn254, bls12_381) = match requirement {
+            HedgeRequirement::Both => (
+                Some(check(BackendType::BN254)?),
+                Some(check(BackendType::BLS12_381)?),
+            ),
+            HedgeRequirement::Only(BackendType::BN254) => (Some(check(BackendType::BN254)?), None),
+            HedgeRequirement::Only(BackendType::BLS12_381) => {
+                (None, Some(check(BackendType::BLS12_381)?))
+            }
+            HedgeRequirement::Either => {
+                match (check(BackendType::BN254), check(BackendType::BLS12_381)) {
+                    (Err(e), Err(_)) => return Err(e),
+                    (bn254, bls12_381) => (bn254.ok(), bls12_381.ok()),
+                }
+            }
+        };
+        let accepted = match requirement {
+            HedgeRequirement::Either => bn254 == Some(true) || bls12_381 == Some(true),
+            _ => bn254 != Some(false) && bls12_381 != Some(false),
+        };
+        Ok(DualVerification {
+            requirement,
+            bn254,
+            bls12_381,
+            accepted,
+        })
+    }
+}
+
+/// Outcome of verifying a DualProof; `None` where a proof was not checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DualVerification {
+    pub requirement: HedgeRequirement,
+    pub bn254: Option<bool>,
+    pub bls12_381: Option<bool>,
+    pub accepted: bool,
+}
+
+/// Verifying keys obtained from the prover through a trusted channel, not
+/// from the proof file
+#[derive(Debug, Clone, Default)]
+pub struct TrustedKeys {
+    pub bn254: Option<Vec<u8>>,
+    pub bls12_381: Option<Vec<u8>>,
+}
+
+impl TrustedKeys {
+    /// Read hex-encoded compressed keys from the given files
+    pub fn load(bn254: Option<&str>, bls12_381: Option<&str>) -> Result<Self, String> {
+        let read = |path: Option<&str>| -> Result<Option<Vec<u8>>, String> {
+            let Some(path) = path else {
+                return Ok(None);
+            };
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read verifying key {}: {}", path, e))?;
+            hex::decode(content.trim())
+                .map(Some)
+                .map_err(|e| format!("Invalid key hex in {}: {}", path, e))
+        };
+        Ok(Self {
+            bn254: read(bn254)?,
+            bls12_381: read(bls12_381)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bn254.is_none() && self.bls12_381.is_none()
+    }
+}
+
+/// A dual proof with the verifying keys needed to check it elsewhere
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedDualProof {
+    pub version: String,
+    pub timestamp: String,
+    pub id: String,
+    pub proof: DualProof,
+    /// Compressed verifying keys, hex
+    pub bn254_vk: String,
+    pub bls12_381_vk: String,
+    pub calldata: [Calldata; 2],
+}
+
+impl ExportedDualProof {
+    pub fn new(proof: DualProof, bn254_vk: &[u8], bls12_381_vk: &[u8]) -> Result<Self, String> {
+        Ok(Self {
+            version: "1.0".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            id: proof.id(),
+            calldata: proof.calldata()?,
+            proof,
+            bn254_vk: hex::encode(bn254_vk),
+            bls12_381_vk: hex::encode(bls12_381_vk),
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create directory: {}", e))?;
+            }
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write file: {}", e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse dual proof: {}", e))
+    }
+
+    /// Verify against `trusted` keys, never the bundled ones. A half the
+    /// requirement needs but that has no trusted key is an error.
+    pub fn verify(
+        &self,
+        requirement: HedgeRequirement,
+        trusted: &TrustedKeys,
+    ) -> Result<DualVerification, String> {
+        if trusted.is_empty() {
+            return Err(
+                "No trusted verifying keys given; the keys bundled in the proof file are not used"
+                    .to_string(),
+            );
+        }
+        let bn254 = trusted
+            .bn254
+            .as_deref()
+            .map(BN254Backend::from_verifying_key)
+            .transpose()?;
+        let bls12_381 = trusted
+            .bls12_381
+            .as_deref()
+            .map(BLS12_381Backend::from_verifying_key)
+            .transpose()?;
+        let missing = |backend: BackendType| format!("No trusted {} verifying key", backend.name());
+        self.proof
+            .verify_with(requirement, |proof| match proof.backend {
+                BackendType::BN254 => bn254
+                    .as_ref()
+                    .ok_or_else(|| missing(BackendType::BN254))?
+                    .verify(proof),
+                BackendType::BLS12_381 => bls12_381
+                    .as_ref()
+                    .ok_or_else(|| missing(BackendType::BLS12_381))?
+                    .verify(proof),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::BackendPool;
+
+    fn dual(pool: &BackendPool, a: u64, b: u64) -> DualProof {
+        DualProof::new(
+            pool.prove(BackendType::BN254, a, b).unwrap(),
+            pool.prove(BackendType::BLS12_381, a, b).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_requirements() {
+        let pool = BackendPool::new();
+        let mut proof = dual(&pool, 3, 7);
+        assert_eq!(proof.public_inputs, vec!["21"]);
+        let verify = |p: &UniversalProof| pool.verify(p);
+
+        let both = proof.verify_with(HedgeRequirement::Both, verify).unwrap();
+        assert!(both.accepted);
+        assert_eq!((both.bn254, both.bls12_381), (Some(true), Some(true)));
+
+        // Break the BLS12-381 half by swapping in a proof of another statement
+        proof.bls12_381 = pool.prove(BackendType::BLS12_381, 2, 2).unwrap();
+        assert!(
+            !proof
+                .verify_with(HedgeRequirement::Both, verify)
+                .unwrap()
+                .accepted
+        );
+        assert!(
+            proof
+                .verify_with(HedgeRequirement::Either, verify)
+                .unwrap()
+                .accepted
+        );
+        let bn254_only = HedgeRequirement::parse("bn254").unwrap();
+        let only = proof.verify_with(bn254_only, verify).unwrap();
+        assert!(only.accepted);
+        assert_eq!(only.bls12_381, None);
+        let bls_only = HedgeRequirement::Only(BackendType::BLS12_381);
+        assert!(!proof.verify_with(bls_only, verify).unwrap().accepted);
+    }
+
+    #[test]
+    fn test_mismatched_statements_rejected() {
+        let pool = BackendPool::new();
+        let bn254 = pool.prove(BackendType::BN254, 3, 7).unwrap();
+        let bls = pool.prove(BackendType::BLS12_381, 3, 8).unwrap();
+        assert!(DualProof::new(bn254.clone(), bls.clone()).is_err());
+        assert!(DualProof::new(bls, bn254).is_err());
+    }
+
+    #[test]
+    fn test_export_verifies_with_trusted_keys_only() {
+        let pool = BackendPool::new();
+        let proof = dual(&pool, 4, 5);
+        let exported = ExportedDualProof::new(
+            proof,
+            &pool.verifying_key(BackendType::BN254).unwrap(),
+            &pool.verifying_key(BackendType::BLS12_381).unwrap(),
+        )
+        .unwrap();
+
+        let path = std::env::temp_dir().join(format!("dual-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        exported.save(path).unwrap();
+        let loaded = ExportedDualProof::load(path).unwrap();
+        fs::remove_file(path).ok();
+
+        assert_eq!(loaded.id, loaded.proof.id());
+        assert_eq!(loaded.calldata[0].backend, BackendType::BN254);
+        let trusted = TrustedKeys {
+            bn254: Some(pool.verifying_key(BackendType::BN254).unwrap()),
+            bls12_381: Some(pool.verifying_key(BackendType::BLS12_381).unwrap()),
+        };
+        assert!(
+            loaded
+                .verify(HedgeRequirement::Both, &trusted)
+                .unwrap()
+                .accepted
+        );
+
+        // Without trusted keys the bundled ones are not enough
+        let none = TrustedKeys::default();
+        assert!(loaded.verify(HedgeRequirement::Both, &none).is_err());
+        let bn254_only = TrustedKeys {
+            bls12_381: None,
+            ..trusted.clone()
+        };
+        assert!(loaded.verify(HedgeRequirement::Both, &bn254_only).is_err());
+        let either = loaded
+            .verify(HedgeRequirement::Either, &bn254_only)
+            .unwrap();
+        assert!(either.accepted);
+        assert_eq!(either.bls12_381, None);
+
+        // Proofs and bundled keys from another setup do not verify
+        let other = BackendPool::new();
+        let mut forged = ExportedDualProof::new(
+            dual(&other, 4, 5),
+            &other.verifying_key(BackendType::BN254).unwrap(),
+            &other.verifying_key(BackendType::BLS12_381).unwrap(),
+        )
+        .unwrap();
+        assert!(
+            !forged
+                .verify(HedgeRequirement::Both, &trusted)
+                .unwrap()
+                .accepted
+        );
+        forged.proof.bn254 = loaded.proof.bn254.clone();
+        assert!(
+            forged
+                .verify(HedgeRequirement::Either, &trusted)
+                .unwrap()
+                .accepted
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Just enough HTTP/1.1 for the metrics endpoint and the prover daemon
+//
+// One request per connection, answered with `Connection: close`. Bodies
+// need a Content-Length; chunked uploads are refused. Anything fancier
+// belongs behind a reverse proxy.
+
+use serde::Serialize;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Largest request body accepted
+pub const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// Longest request or header line accepted, and most header lines
+pub const MAX_LINE_BYTES: usize = 8 << 10;
+pub const MAX_HEADERS: usize = 100;
+
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Names lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Token from `Authorization: Bearer <token>`
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.header("authorization")?
+            .strip_prefix("Bearer ")
+            .map(str::trim)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+impl Response {
+    pub fn text(status: u16, content_type: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self::text(status, "application/json", body),
+            Err(e) => Self::error(500, &format!("Failed to serialize: {}", e)),
+        }
+    }
+
+    /// `{"error": message}`
+    pub fn error(status: u16, message: &str) -> Self {
+        let body = serde_json::to_string(&ErrorBody { error: message })
+            .unwrap_or_else(|_| "{}".to_string());
+        Self::text(status, "application/json", body)
+    }
+
+    pub fn not_found() -> Self {
+        Self::error(404, "Not found")
+    }
+}
+
+/// Read one request, headers and body
+pub fn read_request(stream: &TcpStream) -> Result<Request, String> {
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream);
+
+    let request_line =
+        read_line(&mut reader).map_err(|e| format!("Failed to read request: {}", e))?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err("Malformed request line".to_string());
+    };
+
+    let mut content_length = 0;
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader).map_err(|e| format!("Failed to read headers: {}", e))?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(format!("More than {} headers", MAX_HEADERS));
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(format!("Malformed header '{}'", line));
+        };
+        let name = name.trim().to_lowercase();
+        headers.push((name.clone(), value.trim().to_string()));
+        match name.as_str() {
+            "content-length" => {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| "Invalid Content-Length".to_string())?;
+            }
+            "transfer-encoding" => return Err("Chunked bodies are not supported".to_string()),
+            _ => {}
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(format!(
+            "Body of {} bytes exceeds {} byte limit",
+            content_length, MAX_BODY_BYTES
+        ));
+    }
+
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("Failed to read body: {}", e))?;
+
+    Ok(Request {
+        method: method.to_string(),
+        // Query strings are not used by any route
+        path: path.split('?').next().unwrap_or(path).to_string(),
+        headers,
+        body,
+    })
+}
+
+/// One line, refusing to buffer more than MAX_LINE_BYTES of it
+fn read_line(reader: &mut impl BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE_BYTES as u64 + 1)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    if line.len() > MAX_LINE_BYTES {
+        return Err(format!("Line exceeds {} byte limit", MAX_LINE_BYTES));
+    }
+    Ok(line)
+}
+
+pub fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,854 @@
+// Disk-backed queue of proving jobs
+//
+// For batches too large to prove inline, e.g. re-proving every account's
+// state commitment after a morph. A submitted job gets an id at once and is
+// proven later by a JobRunner: highest priority first, then in submission
+// order, no faster than `max_per_second`. Failed attempts are retried with
+// exponential backoff up to `max_attempts`.
+//
+// Every change to a job is appended to a JSONL file and synced, one full
+// snapshot per line; on open the last line per id wins, so the queue picks
+// up where it left off after a restart. Jobs that were running when the
+// process died are queued again. Submitting with an idempotency key that is
+// already known returns the original job instead of a new one. Finished
+// jobs are kept for `retention_seconds`, then dropped along with their
+// idempotency keys.
+//
+// A job is pinned to the backend that was current when it was submitted and
+// is proven there even after a morph, unless it is marked re-targetable, in
+// which case it is proven on whatever backend is current when it runs.
+
+use crate::types::{BackendType, UniversalProof};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    #[serde(default = "default_jobs_path")]
+    pub path: String,
+    /// Jobs proven at once
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    /// Jobs started per second across all workers; 0 for no limit
+    #[serde(default)]
+    pub max_per_second: u32,
+    /// Attempts before a job is marked failed
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each one after
+    #[serde(default = "default_backoff_base_ms")]
+    pub backoff_base_ms: u64,
+    #[serde(default = "default_backoff_max_ms")]
+    pub backoff_max_ms: u64,
+    /// How long finished jobs stay queryable; 0 keeps them forever
+    #[serde(default = "default_retention_seconds")]
+    pub retention_seconds: u64,
+}
+
+fn default_jobs_path() -> String {
+    "output/jobs/jobs.jsonl".to_string()
+}
+
+fn default_workers() -> usize {
+    2
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_backoff_base_ms() -> u64 {
+    1000
+}
+
+fn default_backoff_max_ms() -> u64 {
+    60_000
+}
+
+fn default_retention_seconds() -> u64 {
+    7 * 24 * 3600
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            path: default_jobs_path(),
+            workers: default_workers(),
+            max_per_second: 0,
+            max_attempts: default_max_attempts(),
+            backoff_base_ms: default_backoff_base_ms(),
+            backoff_max_ms: default_backoff_max_ms(),
+            retention_seconds: default_retention_seconds(),
+        }
+    }
+}
+
+impl JobsConfig {
+    /// Wait before retrying after `attempts` failed attempts
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 1u64 << attempts.saturating_sub(1).min(32);
+        Duration::from_millis(
+            self.backoff_base_ms
+                .saturating_mul(factor)
+                .min(self.backoff_max_ms),
+        )
+    }
+}
+
+/// Higher runs first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    /// E.g. re-proving state after an emergency morph
+    Emergency,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobRequest {
+    pub a: u64,
+    pub b: u64,
+    #[serde(default)]
+    pub jurisdiction: Option<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Prove on the backend current at run time rather than at submission
+    #[serde(default)]
+    pub retargetable: bool,
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Succeeded | JobState::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub request: JobRequest,
+    /// Pinned at submission; for re-targetable jobs, the backend proven on
+    pub backend: BackendType,
+    pub state: JobState,
+    pub attempts: u32,
+    /// Milliseconds since the Unix epoch
+    pub submitted_ms: u64,
+    /// Not run again before this, while backing off
+    pub not_before_ms: u64,
+    pub finished_ms: Option<u64>,
+    pub proof: Option<UniversalProof>,
+    /// Error of the last failed attempt
+    pub error: Option<String>,
+}
+
+/// Outcome of `submit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Submitted {
+    pub id: u64,
+    pub backend: BackendType,
+    /// The idempotency key was known; `id` is the original job
+    pub duplicate: bool,
+}
+
+#[derive(Default)]
+struct State {
+    jobs: BTreeMap<u64, Job>,
+    keys: BTreeMap<String, u64>,
+    next_id: u64,
+    /// Lines in the file, to know when compacting pays off
+    lines: usize,
+    subscribers: BTreeMap<u64, Vec<Sender<Job>>>,
+}
+
+pub struct JobQueue {
+    path: PathBuf,
+    config: JobsConfig,
+    state: Mutex<State>,
+    /// Signalled when a job is queued or re-queued
+    changed: Condvar,
+}
+
+impl JobQueue {
+    /// Open the queue at `config.path`, re-queuing jobs a crash left running
+    pub fn open(config: &JobsConfig) -> Result<Self, String> {
+        Self::open_at(config, now_ms())
+    }
+
+    fn open_at(config: &JobsConfig, now: u64) -> Result<Self, String> {
+        let path = PathBuf::from(&config.path);
+        let mut state = State::default();
+        if path.exists() {
+            let snapshots = read_snapshots(&path)?;
+            state.lines = snapshots.len();
+            for mut job in snapshots {
+                if job.state == JobState::Running {
+                    job.state = JobState::Queued;
+                }
+                if let Some(key) = &job.request.idempotency_key {
+                    state.keys.insert(key.clone(), job.id);
+                }
+                state.next_id = state.next_id.max(job.id + 1);
+                state.jobs.insert(job.id, job);
+            }
+        }
+        if state.next_id == 0 {
+            state.next_id = 1;
+        }
+
+        let queue = Self {
+            path,
+            config: config.clone(),
+            state: Mutex::new(state),
+            changed: Condvar::new(),
+        };
+        // Also drops a torn tail, expired jobs and records the re-queued jobs
+        let mut state = queue.state();
+        queue.evict(&mut state, now);
+        if state.lines > 0 {
+            queue.compact(&mut state)?;
+        }
+        drop(state);
+        Ok(queue)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn config(&self) -> &JobsConfig {
+        &self.config
+    }
+
+    /// Queue a job pinned to `current`, or return the one submitted earlier
+    /// under the same idempotency key
+    pub fn submit(&self, request: JobRequest, current: BackendType) -> Result<Submitted, String> {
+        self.submit_at(request, current, now_ms())
+    }
+
+    fn submit_at(
+        &self,
+        request: JobRequest,
+        current: BackendType,
+        now: u64,
+    ) -> Result<Submitted, String> {
+        if request.a.checked_mul(request.b).is_none() {
+            return Err("a * b overflows 64 bits".to_string());
+        }
+        let mut state = self.state();
+        self.evict(&mut state, now);
+        if let Some(key) = &request.idempotency_key {
+            if let Some(job) = state.keys.get(key).and_then(|id| state.jobs.get(id)) {
+                if job.request != request {
+                    return Err(format!(
+                        "Idempotency key '{}' was used for job {} with other parameters",
+                        key, job.id
+                    ));
+                }
+                return Ok(Submitted {
+                    id: job.id,
+                    backend: job.backend,
+                    duplicate: true,
+                });
+            }
+        }
+
+        let job = Job {
+            id: state.next_id,
+            request,
+            backend: current,
+            state: JobState::Queued,
+            attempts: 0,
+            submitted_ms: now,
+            not_before_ms: now,
+            finished_ms: None,
+            proof: None,
+            error: None,
+        };
+        self.store(&mut state, job.clone())?;
+        state.next_id += 1;
+        if let Some(key) = &job.request.idempotency_key {
+            state.keys.insert(key.clone(), job.id);
+        }
+        self.changed.notify_all();
+        Ok(Submitted {
+            id: job.id,
+            backend: job.backend,
+            duplicate: false,
+        })
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.state().jobs.get(&id).cloned()
+    }
+
+    /// Jobs per state
+    pub fn counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for job in self.state().jobs.values() {
+            let name = match job.state {
+                JobState::Queued => "queued",
+                JobState::Running => "running",
+                JobState::Succeeded => "succeeded",
+                JobState::Failed => "failed",
+            };
+            *counts.entry(name).or_default() += 1;
+        }
+        counts
+    }
+
+    /// The job once it has finished; sent at once if it already has.
+    /// None if there is no such job.
+    pub fn subscribe(&self, id: u64) -> Option<Receiver<Job>> {
+        let mut state = self.state();
+        let job = state.jobs.get(&id)?.clone();
+        let (tx, rx) = mpsc::channel();
+        if job.state.is_finished() {
+            tx.send(job).ok();
+        } else {
+            state.subscribers.entry(id).or_default().push(tx);
+        }
+        Some(rx)
+    }
+
+    /// Wait up to `timeout` for a job to finish; returns it as it stands
+    pub fn wait(&self, id: u64, timeout: Duration) -> Option<Job> {
+        let finished = self.subscribe(id)?.recv_timeout(timeout).ok();
+        finished.or_else(|| self.get(id))
+    }
+
+    /// Start the next job that is due, waiting up to `timeout` for one
+    pub fn next(&self, timeout: Duration) -> Result<Option<Job>, String> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state();
+        loop {
+            let now = now_ms();
+            if let Some(job) = self.start_due(&mut state, now)? {
+                return Ok(Some(job));
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Ok(None);
+            }
+            // Wake for the earliest backoff to expire, if sooner
+            let until_due = state
+                .jobs
+                .values()
+                .filter(|job| job.state == JobState::Queued)
+                .map(|job| Duration::from_millis(job.not_before_ms.saturating_sub(now)))
+                .min()
+                .unwrap_or(left);
+            state = self
+                .changed
+                .wait_timeout(state, left.min(until_due.max(Duration::from_millis(1))))
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    fn start_due(&self, state: &mut State, now: u64) -> Result<Option<Job>, String> {
+        let due = state
+            .jobs
+            .values()
+            .filter(|job| job.state == JobState::Queued && job.not_before_ms <= now)
+            .max_by_key(|job| (job.request.priority, std::cmp::Reverse(job.id)))
+            .map(|job| job.id);
+        let Some(id) = due else {
+            return Ok(None);
+        };
+        let mut job = state.jobs[&id].clone();
+        job.state = JobState::Running;
+        job.attempts += 1;
+        self.store(state, job.clone())?;
+        Ok(Some(job))
+    }
+
+    /// Record the outcome of a run started by `next`
+    pub fn finish(&self, id: u64, result: Result<UniversalProof, String>) -> Result<Job, String> {
+        self.finish_at(id, result, now_ms())
+    }
+
+    fn finish_at(
+        &self,
+        id: u64,
+        result: Result<UniversalProof, String>,
+        now: u64,
+    ) -> Result<Job, String> {
+        let mut state = self.state();
+        let mut job = state
+            .jobs
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| format!("No job {}", id))?;
+        match result {
+            Ok(proof) => {
+                job.state = JobState::Succeeded;
+                job.backend = proof.backend;
+                job.proof = Some(proof);
+                job.error = None;
+                job.finished_ms = Some(now);
+            }
+            Err(e) if job.attempts < self.config.max_attempts => {
+                job.state = JobState::Queued;
+                job.not_before_ms = now + self.config.backoff(job.attempts).as_millis() as u64;
+                job.error = Some(e);
+            }
+            Err(e) => {
+                job.state = JobState::Failed;
+                job.error = Some(e);
+                job.finished_ms = Some(now);
+            }
+        }
+        if let Err(e) = self.store(&mut state, job.clone()) {
+            // The file still says running, so queue it again here as a
+            // restart would rather than leave it running forever
+            let mut retry = state.jobs[&id].clone();
+            retry.state = JobState::Queued;
+            retry.not_before_ms = now + self.config.backoff(retry.attempts).as_millis() as u64;
+            state.jobs.insert(id, retry);
+            self.changed.notify_all();
+            return Err(e);
+        }
+
+        if job.state.is_finished() {
+            for subscriber in state.subscribers.remove(&id).unwrap_or_default() {
+                subscriber.send(job.clone()).ok();
+            }
+        } else {
+            self.changed.notify_all();
+        }
+        Ok(job)
+    }
+
+    /// Append `job`'s new state, then apply it; on error nothing changed
+    fn store(&self, state: &mut State, job: Job) -> Result<(), String> {
+        append_snapshot(&self.path, &job)?;
+        state.lines += 1;
+        state.jobs.insert(job.id, job);
+        if state.lines > 4 * state.jobs.len() + 64 {
+            // The change is on disk either way; the old file stays intact
+            if let Err(e) = self.compact(state) {
+                eprintln!("[WARN] Job queue: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget jobs finished more than `retention_seconds` ago; the file
+    /// loses them at the next compaction
+    fn evict(&self, state: &mut State, now: u64) {
+        if self.config.retention_seconds == 0 {
+            return;
+        }
+        let cutoff = now.saturating_sub(self.config.retention_seconds.saturating_mul(1000));
+        let expired: Vec<u64> = state
+            .jobs
+            .values()
+            .filter(|job| job.finished_ms.is_some_and(|finished| finished < cutoff))
+            .map(|job| job.id)
+            .collect();
+        for id in expired {
+            if let Some(key) = state
+                .jobs
+                .remove(&id)
+                .and_then(|job| job.request.idempotency_key)
+            {
+                state.keys.remove(&key);
+            }
+            state.subscribers.remove(&id);
+        }
+    }
+
+    /// Rewrite the file with one line per job
+    fn compact(&self, state: &mut State) -> Result<(), String> {
+        let mut content = String::new();
+        for job in state.jobs.values() {
+            let line = serde_json::to_string(job)
+                .map_err(|e| format!("Failed to serialize job: {}", e))?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+        // Synced before the rename so it never replaces the file with a
+        // partly written one, and the directory after so the rename lasts
+        let tmp_path = self.path.with_extension("tmp");
+        let error = |e: std::io::Error| format!("Failed to compact job queue: {}", e);
+        let mut file = File::create(&tmp_path).map_err(error)?;
+        file.write_all(content.as_bytes()).map_err(error)?;
+        file.sync_all().map_err(error)?;
+        fs::rename(&tmp_path, &self.path).map_err(error)?;
+        sync_dir(&self.path).map_err(error)?;
+        state.lines = state.jobs.len();
+        Ok(())
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis().max(0) as u64
+}
+
+fn append_snapshot(path: &Path, job: &Job) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create job queue directory: {}", e))?;
+        }
+    }
+    let line = serde_json::to_string(job).map_err(|e| format!("Failed to serialize job: {}", e))?;
+    let created = !path.exists();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open job queue: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write job queue: {}", e))?;
+    file.sync_all()
+        .map_err(|e| format!("Failed to sync job queue: {}", e))?;
+    if created {
+        sync_dir(path).map_err(|e| format!("Failed to sync job queue: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Sync the directory holding `path`, so a new or renamed entry survives
+/// a crash
+fn sync_dir(path: &Path) -> std::io::Result<()> {
+    match path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        Some(parent) => File::open(parent)?.sync_all(),
+        None => File::open(".")?.sync_all(),
+    }
+}
+
+/// Read all snapshots in order, less a torn final line
+fn read_snapshots(path: &Path) -> Result<Vec<Job>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read job queue {}: {}", path.display(), e))?;
+
+    let mut jobs = Vec::new();
+    let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+    for (n, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(job) => jobs.push(job),
+            // The change being written when the process died never happened
+            Err(_) if n + 1 == lines.len() => break,
+            Err(e) => return Err(format!("Failed to parse job queue line {}: {}", n + 1, e)),
+        }
+    }
+    Ok(jobs)
+}
+
+/// Worker threads proving the queue's jobs with `prove`
+pub struct JobRunner {
+    stopping: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl JobRunner {
+    pub fn start<F>(queue: Arc<JobQueue>, prove: F) -> Self
+    where
+        F: Fn(&Job) -> Result<UniversalProof, String> + Send + Sync + 'static,
+    {
+        let stopping = Arc::new(AtomicBool::new(false));
+        let prove = Arc::new(prove);
+        let interval = match queue.config().max_per_second {
+            0 => Duration::ZERO,
+            rate => Duration::from_secs(1) / rate,
+        };
+        let next_slot = Arc::new(Mutex::new(Instant::now()));
+
+        let workers = (0..queue.config().workers.max(1))
+            .map(|_| {
+                let (queue, prove) = (Arc::clone(&queue), Arc::clone(&prove));
+                let (stopping, next_slot) = (Arc::clone(&stopping), Arc::clone(&next_slot));
+                thread::spawn(move || {
+                    while !stopping.load(Ordering::SeqCst) {
+                        let job = match queue.next(Duration::from_millis(200)) {
+                            Ok(Some(job)) => job,
+                            Ok(None) => continue,
+                            Err(e) => {
+                                eprintln!("[WARN] Job queue: {}", e);
+                                thread::sleep(Duration::from_secs(1));
+                                continue;
+                            }
+                        };
+                        pace(&next_slot, interval);
+                        if let Err(e) = queue.finish(job.id, prove(&job)) {
+                            eprintln!("[WARN] Job {}: {}", job.id, e);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        Self { stopping, workers }
+    }
+
+    /// Stop taking jobs and wait for the ones running to finish
+    pub fn stop(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        for worker in self.workers {
+            worker.join().ok();
+        }
+    }
+}
+
+/// Wait for the next start slot under the rate limit
+fn pace(next_slot: &Mutex<Instant>, interval: Duration) {
+    if interval.is_zero() {
+        return;
+    }
+    let start = {
+        let mut next = next_slot.lock().unwrap_or_else(|e| e.into_inner());
+        let start = (*next).max(Instant::now());
+        *next = start + interval;
+        start
+    };
+    thread::sleep(start.saturating_duration_since(Instant::now()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(name: &str, config: JobsConfig) -> JobQueue {
+        let path = std::env::temp_dir().join(format!("jobs-{}-{}.jsonl", name, std::process::id()));
+        fs::remove_file(&path).ok();
+        JobQueue::open(&JobsConfig {
+            path: path.to_str().unwrap().to_string(),
+            ..config
+        })
+        .unwrap()
+    }
+
+    fn request(a: u64, priority: Priority) -> JobRequest {
+        JobRequest {
+            a,
+            b: 2,
+            jurisdiction: None,
+            priority,
+            retargetable: false,
+            idempotency_key: None,
+        }
+    }
+
+    fn proof(backend: BackendType) -> UniversalProof {
+        UniversalProof {
+            backend,
+            proof_bytes: vec![1],
+            public_inputs: vec!["2".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_priority_order_and_idempotency() {
+        let queue = queue("priority", JobsConfig::default());
+        let bn254 = BackendType::BN254;
+        let low = queue
+            .submit_at(request(1, Priority::Low), bn254, 0)
+            .unwrap();
+        let normal = queue
+            .submit_at(request(2, Priority::Normal), bn254, 0)
+            .unwrap();
+        let emergency = queue
+            .submit_at(request(3, Priority::Emergency), bn254, 0)
+            .unwrap();
+        let normal2 = queue
+            .submit_at(request(4, Priority::Normal), bn254, 0)
+            .unwrap();
+
+        let mut state = queue.state();
+        let order: Vec<u64> = (0..4)
+            .map(|_| queue.start_due(&mut state, 0).unwrap().unwrap().id)
+            .collect();
+        drop(state);
+        assert_eq!(order, vec![emergency.id, normal.id, normal2.id, low.id]);
+
+        let keyed = JobRequest {
+            idempotency_key: Some("account-7".to_string()),
+            ..request(5, Priority::High)
+        };
+        let first = queue.submit(keyed.clone(), bn254).unwrap();
+        let again = queue.submit(keyed.clone(), BackendType::BLS12_381).unwrap();
+        assert!(!first.duplicate && again.duplicate);
+        assert_eq!((again.id, again.backend), (first.id, bn254));
+        let changed = JobRequest { b: 3, ..keyed };
+        assert!(queue.submit(changed, bn254).is_err());
+        fs::remove_file(queue.path()).ok();
+    }
+
+    #[test]
+    fn test_retries_with_backoff() {
+        let config = JobsConfig {
+            max_attempts: 3,
+            backoff_base_ms: 100,
+            ..JobsConfig::default()
+        };
+        let queue = queue("retry", config);
+        let id = queue
+            .submit_at(request(1, Priority::Normal), BackendType::BN254, 0)
+            .unwrap()
+            .id;
+        let finished = queue.subscribe(id).unwrap();
+        let start = |now| queue.start_due(&mut queue.state(), now).unwrap();
+
+        assert_eq!(start(0).unwrap().attempts, 1);
+        let job = queue.finish_at(id, Err("boom".to_string()), 0).unwrap();
+        assert_eq!((job.state, job.not_before_ms), (JobState::Queued, 100));
+        assert!(start(99).is_none());
+        assert_eq!(start(100).unwrap().attempts, 2);
+        let job = queue.finish_at(id, Err("boom".to_string()), 100).unwrap();
+        assert_eq!(job.not_before_ms, 300);
+        assert!(finished.try_recv().is_err());
+
+        start(300).unwrap();
+        let job = queue.finish_at(id, Err("boom".to_string()), 300).unwrap();
+        assert_eq!(job.state, JobState::Failed);
+        let notified = finished.try_recv().unwrap();
+        assert_eq!(
+            (notified.attempts, notified.error),
+            (3, Some("boom".to_string()))
+        );
+        fs::remove_file(queue.path()).ok();
+    }
+
+    #[test]
+    fn test_survives_restart() {
+        let queue = queue("restart", JobsConfig::default());
+        let config = queue.config().clone();
+        let keyed = JobRequest {
+            idempotency_key: Some("k".to_string()),
+            ..request(1, Priority::Normal)
+        };
+        let first = queue.submit(keyed.clone(), BackendType::BN254).unwrap();
+        queue
+            .submit(request(2, Priority::Normal), BackendType::BN254)
+            .unwrap();
+        queue.next(Duration::ZERO).unwrap().unwrap();
+        queue
+            .finish(first.id, Ok(proof(BackendType::BN254)))
+            .unwrap();
+        let running = queue.next(Duration::ZERO).unwrap().unwrap();
+        drop(queue);
+
+        // A crash mid-write leaves a torn line behind
+        let mut file = OpenOptions::new().append(true).open(&config.path).unwrap();
+        write!(file, "{{\"id\": 9, \"requ").unwrap();
+
+        let queue = JobQueue::open(&config).unwrap();
+        assert_eq!(queue.get(first.id).unwrap().state, JobState::Succeeded);
+        let requeued = queue.get(running.id).unwrap();
+        assert_eq!((requeued.state, requeued.attempts), (JobState::Queued, 1));
+        assert!(queue.submit(keyed, BackendType::BN254).unwrap().duplicate);
+        let next = queue
+            .submit(request(3, Priority::Low), BackendType::BN254)
+            .unwrap();
+        assert_eq!(next.id, running.id + 1);
+
+        // The runner picks the re-queued job up
+        let queue = Arc::new(queue);
+        let runner = JobRunner::start(Arc::clone(&queue), |job| Ok(proof(job.backend)));
+        for id in [running.id, next.id] {
+            let job = queue.wait(id, Duration::from_secs(5)).unwrap();
+            assert_eq!(job.state, JobState::Succeeded);
+        }
+        runner.stop();
+        assert_eq!(queue.counts().get("succeeded"), Some(&3));
+        fs::remove_file(queue.path()).ok();
+    }
+    #[test]
+    fn test_unrecorded_finish_requeues() {
+        let queue = queue("unrecorded", JobsConfig::default());
+        let id = queue
+            .submit_at(request(1, Priority::Normal), BackendType::BN254, 0)
+            .unwrap()
+            .id;
+        queue.start_due(&mut queue.state(), 0).unwrap().unwrap();
+
+        // The file can no longer be appended to
+        fs::remove_file(queue.path()).unwrap();
+        fs::create_dir(queue.path()).unwrap();
+        assert!(queue
+            .finish_at(id, Ok(proof(BackendType::BN254)), 0)
+            .is_err());
+        let job = queue.get(id).unwrap();
+        assert_eq!((job.state, job.not_before_ms), (JobState::Queued, 1000));
+        assert!(job.proof.is_none());
+
+        fs::remove_dir(queue.path()).unwrap();
+        let job = queue.start_due(&mut queue.state(), 1000).unwrap().unwrap();
+        assert_eq!((job.id, job.attempts), (id, 2));
+        let job = queue
+            .finish_at(id, Ok(proof(BackendType::BN254)), 1000)
+            .unwrap();
+        assert_eq!(job.state, JobState::Succeeded);
+        fs::remove_file(queue.path()).ok();
+    }
+
+    #[test]
+    fn test_finished_jobs_expire() {
+        let config = JobsConfig {
+            retention_seconds: 60,
+            ..JobsConfig::default()
+        };
+        let queue = queue("retention", config);
+        let keyed = JobRequest {
+            idempotency_key: Some("k".to_string()),
+            ..request(1, Priority::Normal)
+        };
+        let done = queue
+            .submit_at(keyed.clone(), BackendType::BN254, 0)
+            .unwrap();
+        let waiting = queue
+            .submit_at(request(2, Priority::Low), BackendType::BN254, 0)
+            .unwrap();
+        queue.start_due(&mut queue.state(), 0).unwrap().unwrap();
+        queue
+            .finish_at(done.id, Ok(proof(BackendType::BN254)), 1000)
+            .unwrap();
+
+        let again = queue
+            .submit_at(keyed.clone(), BackendType::BN254, 61_000)
+            .unwrap();
+        assert!(again.duplicate);
+        let fresh = queue.submit_at(keyed, BackendType::BN254, 61_001).unwrap();
+        assert!(!fresh.duplicate && fresh.id != done.id);
+        assert!(queue.get(done.id).is_none());
+        // Unfinished jobs stay however old they are
+        assert!(queue.get(waiting.id).is_some());
+
+        let config = queue.config().clone();
+        drop(queue);
+        let queue = JobQueue::open_at(&config, 61_001).unwrap();
+        assert!(queue.get(done.id).is_none());
+        assert_eq!(queue.counts().get("queued"), Some(&2));
+        fs::remove_file(queue.path()).ok();
+    }
+}
@@ -0,0 +1,197 @@
+// Write-ahead journal for in-flight morphs
+//
+// Every phase change of a morph is appended (and synced) before the
+// controller acts on it. If the process dies half way, the last record of
+// the unfinished morph tells the next start whether to roll back or to
+// finish the switch.
+
+use crate::morph::MorphContext;
+use crate::types::BackendType;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MorphPhase {
+    Idle,
+    Preparing,
+    ProvingOldState,
+    Switching,
+    ProvingNewState,
+    Committed,
+    /// An operator confirmed the new backend's proofs as committed
+    /// downstream; the morph can no longer be rolled back
+    Confirmed,
+    RolledBack,
+}
+
+impl MorphPhase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MorphPhase::Idle => "Idle",
+            MorphPhase::Preparing => "Preparing",
+            MorphPhase::ProvingOldState => "ProvingOldState",
+            MorphPhase::Switching => "Switching",
+            MorphPhase::ProvingNewState => "ProvingNewState",
+            MorphPhase::Committed => "Committed",
+            MorphPhase::Confirmed => "Confirmed",
+            MorphPhase::RolledBack => "RolledBack",
+        }
+    }
+
+    /// Idle, Committed, Confirmed and RolledBack mean no morph is in flight
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            MorphPhase::Idle
+                | MorphPhase::Committed
+                | MorphPhase::Confirmed
+                | MorphPhase::RolledBack
+        )
+    }
+
+    /// Idle → Preparing → ProvingOldState → Switching → ProvingNewState →
+    /// Committed → Confirmed, with RolledBack reachable from any in-flight
+    /// phase
+    pub fn can_transition_to(&self, next: MorphPhase) -> bool {
+        use MorphPhase::*;
+        matches!(
+            (self, next),
+            (Idle | Committed | Confirmed | RolledBack, Preparing)
+                | (Committed, Confirmed)
+                | (Preparing, ProvingOldState)
+                | (ProvingOldState, Switching)
+                | (Switching, ProvingNewState)
+                | (ProvingNewState, Committed)
+                | (
+                    Preparing | ProvingOldState | Switching | ProvingNewState,
+                    RolledBack
+                )
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub morph_id: u64,
+    pub phase: MorphPhase,
+    pub old_backend: BackendType,
+    pub new_backend: BackendType,
+    pub timestamp: String,
+    pub detail: String,
+    /// Present on the Preparing record so recovery can finish the audit entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<MorphContext>,
+}
+
+pub struct MorphJournal {
+    path: PathBuf,
+    records: Vec<JournalRecord>,
+}
+
+impl MorphJournal {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let path = PathBuf::from(path);
+        if !path.exists() {
+            return Ok(Self {
+                path,
+                records: Vec::new(),
+            });
+        }
+
+        let (records, torn) = read_records(&path)?;
+        let journal = Self { path, records };
+        if torn {
+            journal.rewrite()?;
+        }
+        Ok(journal)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn records(&self) -> &[JournalRecord] {
+        &self.records
+    }
+
+    pub fn next_morph_id(&self) -> u64 {
+        self.records.last().map(|r| r.morph_id + 1).unwrap_or(0)
+    }
+
+    /// Append a record and sync it to disk before returning
+    pub fn record(&mut self, record: JournalRecord) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create journal directory: {}", e))?;
+            }
+        }
+
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize journal record: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open morph journal: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write morph journal: {}", e))?;
+        file.sync_all()
+            .map_err(|e| format!("Failed to sync morph journal: {}", e))?;
+
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Replace the file with the records we could read, dropping a torn tail
+    fn rewrite(&self) -> Result<(), String> {
+        let mut content = String::new();
+        for record in &self.records {
+            let line = serde_json::to_string(record)
+                .map_err(|e| format!("Failed to serialize journal record: {}", e))?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, content)
+            .map_err(|e| format!("Failed to rewrite morph journal: {}", e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to rewrite morph journal: {}", e))
+    }
+
+    /// Records of the last morph, if it never reached a terminal phase
+    pub fn pending(&self) -> Option<Vec<&JournalRecord>> {
+        let last = self.records.last()?;
+        if last.phase.is_terminal() {
+            return None;
+        }
+
+        Some(
+            self.records
+                .iter()
+                .filter(|r| r.morph_id == last.morph_id)
+                .collect(),
+        )
+    }
+}
+
+/// Read all records; the flag is set if a torn final line was dropped
+fn read_records(path: &Path) -> Result<(Vec<JournalRecord>, bool), String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read morph journal {}: {}", path.display(), e))?;
+
+    let mut records = Vec::new();
+    let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+    for (n, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            // A crash mid-write can leave a torn final line; the record it
+            // was writing never took effect, so drop it.
+            Err(_) if n + 1 == lines.len() => return Ok((records, true)),
+            Err(e) => return Err(format!("Failed to parse journal line {}: {}", n + 1, e)),
+        }
+    }
+    Ok((records, false))
+}
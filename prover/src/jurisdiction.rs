@@ -0,0 +1,315 @@
+// Jurisdiction registry, mirroring contracts/src/RegulatoryRegistry.sol
+//
+// Same data model as the contract: curves by backend id, and per region
+// code the approved backends, the preferred one, the regulator and the
+// compliance standard, with one jurisdiction active at a time. Loaded from
+// config/jurisdictions.json; without that file we start from what the
+// contract's constructor registers.
+//
+// Region codes are matched case-insensitively here. The contract hashes
+// them with abi.encodePacked when adding but abi.encode in
+// setActiveJurisdiction/getJurisdictionDetails, so those two never find a
+// default entry on-chain; that is not mirrored.
+
+use crate::error::ChameleonError;
+use crate::types::BackendType;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// A curve the registry knows about, by the contract's backend id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurveInfo {
+    pub backend_id: u8,
+    pub curve_name: String,
+    pub standard_ref: String,
+    pub is_approved: bool,
+    pub security_level: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JurisdictionConfig {
+    pub name: String,
+    pub region_code: String,
+    pub is_active: bool,
+    pub approved_backends: Vec<u8>,
+    pub preferred_backend: u8,
+    pub regulatory_body: String,
+    pub compliance_standard: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JurisdictionRegistry {
+    pub curves: Vec<CurveInfo>,
+    pub jurisdictions: Vec<JurisdictionConfig>,
+    /// Region code of the jurisdiction proofs are produced for by default
+    pub active_jurisdiction: String,
+}
+
+impl JurisdictionRegistry {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read jurisdiction registry: {}", e))?;
+        let registry: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse jurisdiction registry: {}", e))?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Load from default location, falling back to the contract's defaults
+    pub fn load_default() -> Result<Self, String> {
+        let paths = vec![
+            "config/jurisdictions.json",
+            "../config/jurisdictions.json",
+            "jurisdictions.json",
+        ];
+
+        for path in paths {
+            if Path::new(path).exists() {
+                return Self::load(path);
+            }
+        }
+
+        Ok(Self::default())
+    }
+
+    /// Check every id refers to a registered curve, each jurisdiction
+    /// prefers one of its approved backends, and the active one exists
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, j) in self.jurisdictions.iter().enumerate() {
+            if self.jurisdictions[..i]
+                .iter()
+                .any(|other| other.region_code.eq_ignore_ascii_case(&j.region_code))
+            {
+                return Err(format!("Jurisdiction '{}' is listed twice", j.region_code));
+            }
+            if let Some(id) = j
+                .approved_backends
+                .iter()
+                .find(|id| self.curve(**id).is_none())
+            {
+                return Err(format!(
+                    "Jurisdiction '{}' approves unknown backend id {}",
+                    j.region_code, id
+                ));
+            }
+            if !j.approved_backends.contains(&j.preferred_backend) {
+                return Err(format!(
+                    "Jurisdiction '{}' prefers backend id {}, which it does not approve",
+                    j.region_code, j.preferred_backend
+                ));
+            }
+        }
+        match self.jurisdiction(&self.active_jurisdiction) {
+            Some(j) if j.is_active => Ok(()),
+            _ => Err(format!(
+                "Active jurisdiction '{}' not found",
+                self.active_jurisdiction
+            )),
+        }
+    }
+
+    pub fn curve(&self, backend_id: u8) -> Option<&CurveInfo> {
+        self.curves.iter().find(|c| c.backend_id == backend_id)
+    }
+
+    pub fn curve_name(&self, backend_id: u8) -> &str {
+        self.curve(backend_id).map_or("", |c| c.curve_name.as_str())
+    }
+
+    pub fn jurisdiction(&self, region_code: &str) -> Option<&JurisdictionConfig> {
+        self.jurisdictions
+            .iter()
+            .find(|j| j.region_code.eq_ignore_ascii_case(region_code))
+    }
+
+    pub fn active(&self) -> &JurisdictionConfig {
+        self.jurisdiction(&self.active_jurisdiction)
+            .expect("active jurisdiction is validated")
+    }
+
+    pub fn set_active(&mut self, region_code: &str) -> Result<(), String> {
+        match self.jurisdiction(region_code) {
+            Some(j) if j.is_active => {
+                self.active_jurisdiction = j.region_code.clone();
+                Ok(())
+            }
+            _ => Err(format!("Jurisdiction '{}' not found", region_code)),
+        }
+    }
+
+    pub fn add_jurisdiction(&mut self, jurisdiction: JurisdictionConfig) -> Result<(), String> {
+        if self.jurisdiction(&jurisdiction.region_code).is_some() {
+            return Err(format!(
+                "Jurisdiction '{}' already registered",
+                jurisdiction.region_code
+            ));
+        }
+        self.jurisdictions.push(jurisdiction);
+        if let Err(e) = self.validate() {
+            self.jurisdictions.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Whether the active jurisdiction approves `backend_id`
+    pub fn is_backend_approved(&self, backend_id: u8) -> bool {
+        self.active().approved_backends.contains(&backend_id)
+    }
+
+    pub fn preferred_backend(&self) -> u8 {
+        self.active().preferred_backend
+    }
+
+    pub fn approved_backends(&self) -> &[u8] {
+        &self.active().approved_backends
+    }
+
+    /// Jurisdiction `region_code`, or the active one for `None`
+    pub fn resolve(&self, region_code: Option<&str>) -> Result<&JurisdictionConfig, String> {
+        match region_code {
+            None => Ok(self.active()),
+            Some(code) => match self.jurisdiction(code) {
+                Some(j) if j.is_active => Ok(j),
+                _ => Err(format!("Jurisdiction '{}' not found", code)),
+            },
+        }
+    }
+
+    /// Which of this prover's backends `region_code` (or the active
+    /// jurisdiction) approves, preferred first
+    pub fn prover_backends(&self, region_code: Option<&str>) -> Result<Vec<BackendType>, String> {
+        let j = self.resolve(region_code)?;
+        let mut ids = vec![j.preferred_backend];
+        ids.extend(
+            j.approved_backends
+                .iter()
+                .filter(|id| **id != j.preferred_backend),
+        );
+        Ok(ids.into_iter().filter_map(BackendType::from_id).collect())
+    }
+
+    /// Refuse `backend` unless `region_code` (or the active jurisdiction)
+    /// approves it
+    pub fn check(&self, backend: BackendType, region_code: Option<&str>) -> Result<(), String> {
+        let j = self.resolve(region_code)?;
+        if j.approved_backends.contains(&backend.id()) {
+            return Ok(());
+        }
+        let approved: Vec<&str> = j
+            .approved_backends
+            .iter()
+            .map(|id| self.curve_name(*id))
+            .collect();
+        Err(ChameleonError::BackendNotApproved {
+            backend: backend.name().to_string(),
+            jurisdiction: format!(
+                "{} ({}, {})",
+                j.region_code, j.regulatory_body, j.compliance_standard
+            ),
+            approved: approved.join(", "),
+        }
+        .into())
+    }
+}
+
+impl Default for JurisdictionRegistry {
+    /// What the contract's constructor registers
+    fn default() -> Self {
+        let curve = |id, name: &str, standard: &str, security| CurveInfo {
+            backend_id: id,
+            curve_name: name.to_string(),
+            standard_ref: standard.to_string(),
+            is_approved: true,
+            security_level: security,
+        };
+        let jurisdiction =
+            |code: &str, name: &str, approved: &[u8], preferred, body: &str, standard: &str| {
+                JurisdictionConfig {
+                    name: name.to_string(),
+                    region_code: code.to_string(),
+                    is_active: true,
+                    approved_backends: approved.to_vec(),
+                    preferred_backend: preferred,
+                    regulatory_body: body.to_string(),
+                    compliance_standard: standard.to_string(),
+                }
+            };
+
+        Self {
+            curves: vec![
+                curve(0, "BN254", "alt_bn128", 100),
+                curve(1, "BLS12-381", "BLS12-381", 128),
+                curve(2, "P-256", "NIST P-256 / secp256r1", 128),
+                curve(3, "SM2", "GM/T 0003-2012", 128),
+                curve(4, "Curve25519", "RFC 7748", 128),
+            ],
+            jurisdictions: vec![
+                jurisdiction("US", "United States", &[0, 1, 2], 2, "NIST", "FIPS 140-3"),
+                jurisdiction("EU", "European Union", &[0, 1, 4], 1, "ENISA", "eIDAS"),
+                jurisdiction("CN", "China", &[3], 3, "OSCCA", "GM/T 0003"),
+                jurisdiction("SG", "Singapore", &[0, 1, 2, 4], 0, "MAS", "MAS TRM"),
+                jurisdiction(
+                    "GLOBAL",
+                    "Global (No Restriction)",
+                    &[0, 1, 2, 3, 4],
+                    0,
+                    "None",
+                    "None",
+                ),
+            ],
+            active_jurisdiction: "GLOBAL".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_match_contract() {
+        let mut registry = JurisdictionRegistry::default();
+        registry.validate().unwrap();
+        assert_eq!(registry.jurisdictions.len(), 5);
+        assert_eq!(registry.active().name, "Global (No Restriction)");
+        assert!(registry.is_backend_approved(3));
+
+        registry.set_active("eu").unwrap();
+        assert_eq!(registry.preferred_backend(), 1);
+        assert_eq!(registry.approved_backends(), &[0, 1, 4]);
+        assert!(!registry.is_backend_approved(2));
+        assert_eq!(registry.curve_name(4), "Curve25519");
+        assert!(registry.set_active("MARS").is_err());
+    }
+
+    #[test]
+    fn test_check_names_regulator_and_alternatives() {
+        let registry = JurisdictionRegistry::default();
+        assert!(registry.check(BackendType::BN254, None).is_ok());
+        assert!(registry.check(BackendType::BLS12_381, Some("US")).is_ok());
+
+        let err = registry.check(BackendType::BN254, Some("CN")).unwrap_err();
+        assert!(err.contains("OSCCA"));
+        assert!(err.contains("SM2"));
+        assert!(registry.prover_backends(Some("CN")).unwrap().is_empty());
+        assert_eq!(
+            registry.prover_backends(Some("EU")).unwrap(),
+            vec![BackendType::BLS12_381, BackendType::BN254]
+        );
+    }
+
+    #[test]
+    fn test_invalid_jurisdiction_rejected() {
+        let mut registry = JurisdictionRegistry::default();
+        let mut bad = registry.jurisdictions[0].clone();
+        bad.region_code = "UK".to_string();
+        bad.preferred_backend = 3;
+        assert!(registry.add_jurisdiction(bad).is_err());
+        assert_eq!(registry.jurisdictions.len(), 5);
+
+        let duplicate = registry.jurisdictions[0].clone();
+        assert!(registry.add_jurisdiction(duplicate).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+// Observed proving latency and the SLOs it is held to
+//
+// The controller records how long each setup, prove and verify took on
+// each backend into a rolling window. SLOs such as "p95 prove < 200ms" are
+// checked against those windows, and the result is turned into a score for
+// the "performance" threat category: 50 at the SLO limit, 100 at twice the
+// limit, so the policy can trade security headroom for latency.
+
+use crate::simulator::ThreatSimulator;
+use crate::types::BackendType;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::time::Duration;
+
+/// Performance score of a latency exactly at its SLO limit
+pub const BREACH_SCORE: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Setup,
+    Prove,
+    Verify,
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Setup => "setup",
+            Operation::Prove => "prove",
+            Operation::Verify => "verify",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "setup" => Some(Operation::Setup),
+            "prove" => Some(Operation::Prove),
+            "verify" => Some(Operation::Verify),
+            _ => None,
+        }
+    }
+}
+
+/// Latest `window` samples per backend and operation
+#[derive(Debug, Clone)]
+pub struct LatencyTracker {
+    window: usize,
+    samples: BTreeMap<(u8, Operation), VecDeque<Duration>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+}
+
+impl LatencyTracker {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            samples: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, backend: BackendType, operation: Operation, took: Duration) {
+        let samples = self.samples.entry((backend.id(), operation)).or_default();
+        if samples.len() == self.window {
+            samples.pop_front();
+        }
+        samples.push_back(took);
+    }
+
+    /// Nearest-rank percentile of the current window
+    pub fn percentile(
+        &self,
+        backend: BackendType,
+        operation: Operation,
+        percentile: u8,
+    ) -> Option<Duration> {
+        let samples = self.samples.get(&(backend.id(), operation))?;
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort();
+        let rank = (percentile.clamp(1, 100) as usize * sorted.len()).div_ceil(100);
+        Some(sorted[rank - 1])
+    }
+
+    pub fn summary(&self, backend: BackendType, operation: Operation) -> Option<LatencySummary> {
+        Some(LatencySummary {
+            samples: self.samples.get(&(backend.id(), operation))?.len(),
+            p50: self.percentile(backend, operation, 50)?,
+            p95: self.percentile(backend, operation, 95)?,
+        })
+    }
+
+    /// Each SLO that applies to `backend` and has samples, with what was
+    /// observed
+    pub fn check(&self, slos: &[Slo], backend: BackendType) -> Vec<SloStatus> {
+        slos.iter()
+            .filter(|slo| slo.backend.is_none_or(|b| b == backend))
+            .filter_map(|slo| {
+                let observed = self.percentile(backend, slo.operation, slo.percentile)?;
+                Some(SloStatus {
+                    slo: slo.clone(),
+                    backend,
+                    observed,
+                })
+            })
+            .collect()
+    }
+
+    /// Performance category score for `backend`: the worst SLO's observed
+    /// latency as a share of its limit, with the limit at `BREACH_SCORE`.
+    /// `None` until some SLO has samples.
+    pub fn performance_score(&self, slos: &[Slo], backend: BackendType) -> Option<u32> {
+        self.check(slos, backend).iter().map(SloStatus::score).max()
+    }
+
+    /// Ingest the current backend's performance score into `simulator`.
+    /// Returns the score, or `None` if there was nothing to go on.
+    pub fn feed(
+        &self,
+        slos: &[Slo],
+        simulator: &mut ThreatSimulator,
+    ) -> Result<Option<u32>, String> {
+        let Some(score) = self.performance_score(slos, simulator.current_backend) else {
+            return Ok(None);
+        };
+        simulator.ingest("performance", score)?;
+        Ok(Some(score))
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+/// "p95 prove < 200ms", optionally for one backend: "bls12-381 p95 prove < 200ms"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slo {
+    pub backend: Option<BackendType>,
+    pub percentile: u8,
+    pub operation: Operation,
+    pub max: Duration,
+}
+
+impl Slo {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let bad = |why: &str| format!("Bad SLO '{}': {}", text, why);
+        let mut words: Vec<&str> = text.split_whitespace().collect();
+
+        let backend = match words.first().and_then(|w| BackendType::parse(w)) {
+            Some(backend) => {
+                words.remove(0);
+                Some(backend)
+            }
+            None => None,
+        };
+        let [percentile, operation, "<", limit] = words[..] else {
+            return Err(bad("expected '[backend] p<N> <operation> < <limit>'"));
+        };
+
+        let percentile = percentile
+            .strip_prefix('p')
+            .and_then(|p| p.parse::<u8>().ok())
+            .filter(|p| (1..=100).contains(p))
+            .ok_or_else(|| bad("percentile must be p1-p100"))?;
+        let operation = Operation::parse(operation)
+            .ok_or_else(|| bad("operation must be setup, prove or verify"))?;
+        let max = if let Some(ms) = limit.strip_suffix("ms") {
+            ms.parse::<u64>().map(Duration::from_millis)
+        } else if let Some(s) = limit.strip_suffix('s') {
+            s.parse::<u64>().map(Duration::from_secs)
+        } else {
+            return Err(bad("limit needs a unit, e.g. 200ms or 2s"));
+        }
+        .map_err(|_| bad("bad limit"))?;
+        if max.is_zero() {
+            return Err(bad("limit must be above zero"));
+        }
+
+        Ok(Self {
+            backend,
+            percentile,
+            operation,
+            max,
+        })
+    }
+}
+
+impl fmt::Display for Slo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(backend) = self.backend {
+            write!(f, "{} ", backend.name())?;
+        }
+        write!(
+            f,
+            "p{} {} < {}ms",
+            self.percentile,
+            self.operation.name(),
+            self.max.as_millis()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SloStatus {
+    pub slo: Slo,
+    pub backend: BackendType,
+    pub observed: Duration,
+}
+
+impl SloStatus {
+    pub fn breached(&self) -> bool {
+        self.observed >= self.slo.max
+    }
+
+    pub fn score(&self) -> u32 {
+        let share = self.observed.as_secs_f64() / self.slo.max.as_secs_f64();
+        ((share * BREACH_SCORE as f64).round() as u32).min(100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn test_rolling_percentiles() {
+        let mut tracker = LatencyTracker::new(20);
+        for n in 1..=40 {
+            tracker.record(BackendType::BN254, Operation::Prove, ms(n));
+        }
+        // Only 21..=40 are left in the window
+        let summary = tracker
+            .summary(BackendType::BN254, Operation::Prove)
+            .unwrap();
+        assert_eq!(summary.samples, 20);
+        assert_eq!(summary.p50, ms(30));
+        assert_eq!(summary.p95, ms(39));
+        assert!(tracker
+            .summary(BackendType::BLS12_381, Operation::Prove)
+            .is_none());
+    }
+
+    #[test]
+    fn test_slo_parse() {
+        let slo = Slo::parse("p95 prove < 200ms").unwrap();
+        assert_eq!(slo.backend, None);
+        assert_eq!(slo.percentile, 95);
+        assert_eq!(slo.max, ms(200));
+
+        let slo = Slo::parse("bls12-381 p50 verify < 2s").unwrap();
+        assert_eq!(slo.backend, Some(BackendType::BLS12_381));
+        assert_eq!(slo.to_string(), "BLS12-381 p50 verify < 2000ms");
+
+        assert!(Slo::parse("p95 prove 200ms").is_err());
+        assert!(Slo::parse("p0 prove < 200ms").is_err());
+        assert!(Slo::parse("p95 mine < 200ms").is_err());
+        assert!(Slo::parse("p95 prove < 200").is_err());
+    }
+
+    #[test]
+    fn test_breach_feeds_performance_score() {
+        let slos = vec![
+            Slo::parse("p95 prove < 200ms").unwrap(),
+            Slo::parse("bn254 p95 verify < 10ms").unwrap(),
+        ];
+        let mut tracker = LatencyTracker::default();
+        assert_eq!(
+            tracker.performance_score(&slos, BackendType::BLS12_381),
+            None
+        );
+
+        for _ in 0..10 {
+            tracker.record(BackendType::BLS12_381, Operation::Prove, ms(300));
+            tracker.record(BackendType::BLS12_381, Operation::Verify, ms(50));
+            tracker.record(BackendType::BN254, Operation::Prove, ms(100));
+        }
+        let status = tracker.check(&slos, BackendType::BLS12_381);
+        assert_eq!(status.len(), 1);
+        assert!(status[0].breached());
+        assert_eq!(
+            tracker.performance_score(&slos, BackendType::BLS12_381),
+            Some(75)
+        );
+        assert_eq!(
+            tracker.performance_score(&slos, BackendType::BN254),
+            Some(25)
+        );
+
+        let mut simulator = ThreatSimulator::new(BackendType::BLS12_381, 50);
+        assert_eq!(tracker.feed(&slos, &mut simulator).unwrap(), Some(75));
+        assert_eq!(simulator.current_threat().score("performance"), 75);
+    }
+}
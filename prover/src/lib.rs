@@ -1,12 +1,50 @@
+pub mod audit;
+pub mod backtest;
 pub mod bls12_381_backend;
 pub mod bn254_backend;
+pub mod calldata;
 pub mod circuit;
+pub mod concurrent;
+pub mod config;
+pub mod error;
+pub mod flapping;
+pub mod grpc;
+pub mod hedge;
+pub mod http;
+pub mod jobs;
+pub mod journal;
+pub mod jurisdiction;
+pub mod latency;
+pub mod metrics;
 pub mod morph;
+pub mod optimizer;
+pub mod oracle;
+pub mod policy;
+pub mod pool;
+pub mod prewarm;
+pub mod quantum;
+pub mod reports;
+pub mod scenario;
+pub mod security;
+pub mod server;
 pub mod simulator;
+pub mod threat;
 pub mod types;
 
+pub use audit::AuditLog;
 pub use bls12_381_backend::BLS12_381Backend;
 pub use bn254_backend::BN254Backend;
-pub use morph::MorphController;
-pub use simulator::ThreatSimulator;
-pub use types::{BackendType, MorphResult, UniversalProof};
+pub use concurrent::ConcurrentController;
+pub use error::{ChameleonError, ChameleonResult};
+pub use journal::{MorphJournal, MorphPhase};
+pub use jurisdiction::JurisdictionRegistry;
+pub use morph::{MorphContext, MorphController};
+pub use optimizer::Optimizer;
+pub use oracle::ThreatAggregator;
+pub use policy::MorphPolicy;
+pub use quantum::QuantumForecast;
+pub use reports::{SignedThreatReport, ThreatFeed};
+pub use scenario::{Assertion, Scenario, ThreatPattern};
+pub use simulator::{ThreatLevel, ThreatSimulator};
+pub use threat::ThreatModel;
+pub use types::{BackendType, MorphResult, MorphTrigger, UniversalProof};
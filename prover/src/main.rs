@@ -1,18 +1,42 @@
-mod bls12_381_backend;
-mod bn254_backend;
-mod circuit;
+// The original CLI output prints blank lines as println!("")
+#![allow(clippy::println_empty_string, clippy::needless_return)]
+
 mod cli;
-mod morph;
-mod types;
 
-use bls12_381_backend::BLS12_381Backend;
-use bn254_backend::BN254Backend;
+use chameleon_prover::audit;
+use chameleon_prover::backtest;
+use chameleon_prover::calldata::Calldata;
+use chameleon_prover::config::{Config, MorphConfig, ThreatConfig};
+use chameleon_prover::grpc::GrpcServer;
+use chameleon_prover::hedge::{DualVerification, ExportedDualProof, HedgeRequirement, TrustedKeys};
+use chameleon_prover::jobs::{JobQueue, JobRunner};
+use chameleon_prover::latency::{LatencyTracker, Operation};
+use chameleon_prover::metrics;
+use chameleon_prover::optimizer::{self, Optimizer, SimulatedCosts};
+use chameleon_prover::prewarm::TrendProjector;
+use chameleon_prover::quantum::{self, QuantumForecast};
+use chameleon_prover::reports::{self, ReporterConfig};
+use chameleon_prover::scenario;
+use chameleon_prover::security::{self, SecurityEstimate};
+use chameleon_prover::server::{LatencyChecker, ProverService, Server};
+use chameleon_prover::simulator::capitalize;
+use chameleon_prover::types;
+use chameleon_prover::{Assertion, Scenario, ThreatPattern};
+use chameleon_prover::{AuditLog, BackendType, MorphContext, MorphController, MorphJournal};
+use chameleon_prover::{BLS12_381Backend, BN254Backend, JurisdictionRegistry, MorphPolicy};
+use chameleon_prover::{SignedThreatReport, ThreatFeed, ThreatLevel};
+use chameleon_prover::{ThreatModel, ThreatSimulator};
 use clap::Parser;
+use cli::AuditAction;
 use cli::Cli;
 use cli::Commands;
+use cli::ReportAction;
+use ed25519_dalek::SigningKey;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 #[allow(dead_code)]
 fn main() {
     let cli = Cli::parse();
@@ -26,32 +50,163 @@ fn main() {
             a,
             b,
             output,
+            jurisdiction,
+            hedged,
+        } => {
+            if hedged {
+                do_prove_hedged(a, b, output, jurisdiction);
+            } else {
+                do_prove(backend, a, b, output, jurisdiction);
+            }
+        }
+        Commands::Verify {
+            proof,
+            require,
+            bn254_vk,
+            bls12_381_vk,
+            calldata,
         } => {
-            do_prove(backend, a, b, output);
+            do_verify(proof, require, (bn254_vk, bls12_381_vk), calldata);
+        }
+        Commands::Morph {
+            to,
+            operator,
+            reason,
+            emergency,
+            commitment_proofs,
+            canary,
+        } => {
+            do_morph(to, operator, reason, emergency, commitment_proofs, canary);
+        }
+        Commands::Rollback { operator, reason } => {
+            do_rollback(operator, reason);
         }
-        Commands::Verify { proof } => {
-            do_verify(proof);
+        Commands::Confirm { operator } => {
+            do_confirm(operator);
         }
-        Commands::Morph { to } => {
-            do_morph(to);
+        Commands::Audit { action } => {
+            do_audit(action);
         }
         Commands::Benchmark { iterations } => {
             do_benchmark(iterations);
         }
-        Commands::Simulate { threat, level } => {
-            do_simulate(threat, level);
+        Commands::Simulate {
+            threat,
+            level,
+            policy,
+            jurisdiction,
+            explain,
+            reports,
+            reporters,
+            quantum_forecast,
+        } => {
+            let reports = reports.map(|path| (path, reporters));
+            do_simulate(
+                threat,
+                level,
+                policy,
+                jurisdiction,
+                explain,
+                reports,
+                quantum_forecast,
+            );
+        }
+        Commands::Quantum { year, forecast } => {
+            do_quantum(year, forecast);
+        }
+        Commands::Report { action } => {
+            do_report(action);
+        }
+        Commands::Scenario {
+            pattern,
+            cycles,
+            interval,
+            seed,
+            policy,
+            assertions,
+            prove,
+            optimize,
+        } => {
+            do_scenario(
+                pattern, cycles, interval, seed, policy, assertions, prove, optimize,
+            );
+        }
+        Commands::Backtest {
+            log,
+            policies,
+            proofs_per_hour,
+        } => {
+            do_backtest(log, policies, proofs_per_hour);
+        }
+        Commands::Serve {
+            listen,
+            grpc,
+            policy,
+            metrics,
+        } => {
+            do_serve(listen, grpc, policy, metrics);
         }
     }
 }
 
+// Helper: Serve /metrics for as long as the daemon runs; one-shot commands
+// exit before anything could scrape them
+fn start_metrics(listen: Option<String>, config: &Config) {
+    let registry = metrics::global();
+    registry.set_gas_price_gwei(config.metrics.gas_price_gwei);
+    let Some(addr) = listen.or_else(|| config.metrics.listen.clone()) else {
+        return;
+    };
+    match metrics::serve(&addr, registry) {
+        Ok((local, _)) => eprintln!("[INFO] Serving metrics on http://{}/metrics", local),
+        Err(e) => eprintln!("[WARN] Metrics disabled: {}", e),
+    }
+}
+
 fn do_status() {
     println!("                    CHAMELEON-ZK STATUS");
     println!("Version: 0.1.0");
     println!("");
     println!("Available Backends:");
-    println!("  - BN254     (100-bit security, Ethereum-optimized)");
-    println!("  - BLS12-381 (128-bit security, Higher security)");
+    for (backend, use_case) in [
+        (BackendType::BN254, "Ethereum-optimized"),
+        (BackendType::BLS12_381, "Higher security"),
+    ] {
+        let estimate = SecurityEstimate::for_backend(backend);
+        println!(
+            "  - {:<9} ({}-bit security, {})",
+            backend.name(),
+            estimate.bits,
+            use_case
+        );
+        println!("      {}", estimate);
+    }
+    println!("  Security estimates after:");
+    for citation in [security::POLLARD_RHO, security::EXTNFS, security::KEY_SIZES] {
+        println!("    {}", citation);
+    }
     println!("");
+    match JurisdictionRegistry::load_default() {
+        Ok(registry) => {
+            let active = registry.active();
+            let approved: Vec<&str> = active
+                .approved_backends
+                .iter()
+                .map(|id| registry.curve_name(*id))
+                .collect();
+            println!(
+                "Jurisdiction: {} ({}, {})",
+                active.region_code, active.regulatory_body, active.compliance_standard
+            );
+            println!("  Approved:  {}", approved.join(", "));
+            println!(
+                "  Preferred: {}",
+                registry.curve_name(active.preferred_backend)
+            );
+        }
+        Err(e) => println!("Jurisdiction: ERROR: {}", e),
+    }
+    println!();
     println!("Commands:");
     println!("  status    - Show this status");
     println!("  prove     - Generate a ZK proof");
@@ -61,11 +216,33 @@ fn do_status() {
     println!("  morph     - Switch backend");
 }
 
-fn do_prove(backend: String, a: u64, b: u64, output: String) {
+fn do_prove(backend: String, a: u64, b: u64, output: String, jurisdiction: Option<String>) {
     println!("                    GENERATING PROOF");
     println!("Backend: {}", backend);
     println!("Inputs:  {} x {}", a, b);
+
+    let registry = match JurisdictionRegistry::load_default() {
+        Ok(registry) => registry,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let region = match registry.resolve(jurisdiction.as_deref()) {
+        Ok(j) => j.region_code.clone(),
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    println!("Jurisdiction: {}", region);
     println!("");
+    if let Some(requested) = BackendType::parse(&backend) {
+        if let Err(e) = registry.check(requested, Some(&region)) {
+            println!("ERROR: {}", e);
+            return;
+        }
+    }
 
     let start = Instant::now();
 
@@ -156,8 +333,176 @@ fn save_proof(output: &str, backend: &str, a: u64, b: u64, proof: &types::Univer
     fs::write(output, &json_content).ok();
 }
 
-#[allow(dead_code)]
-fn do_verify(proof_path: String) {
+fn do_prove_hedged(a: u64, b: u64, output: String, jurisdiction: Option<String>) {
+    println!("                    GENERATING HEDGED PROOF");
+    println!("Backends: BN254 + BLS12-381");
+    println!("Inputs:   {} x {}", a, b);
+
+    let config = load_config();
+    let mut controller =
+        MorphController::from_config(BackendType::BN254, &config.morph, &load_threat_model());
+    match JurisdictionRegistry::load_default() {
+        Ok(registry) => controller.set_registry(registry),
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    }
+    println!();
+
+    let start = Instant::now();
+    println!("[1/3] Proving on both curves...");
+    let dual = match controller.prove_hedged(a, b, jurisdiction.as_deref()) {
+        Ok(dual) => dual,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    println!(
+        "[2/3] Verifying ({})...",
+        controller.hedge_requirement().name()
+    );
+    let verification = match controller.verify_hedged(&dual) {
+        Ok(verification) => verification,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let elapsed = start.elapsed();
+
+    println!("[3/3] Saving proof...");
+    let pool = controller.pool();
+    let exported = pool
+        .verifying_key(BackendType::BN254)
+        .and_then(|bn254| {
+            let bls12_381 = pool.verifying_key(BackendType::BLS12_381)?;
+            ExportedDualProof::new(dual, &bn254, &bls12_381)
+        })
+        .and_then(|exported| exported.save(&output).map(|_| exported))
+        .and_then(|exported| {
+            // Verifiers check against these, so hand them over out of band
+            // rather than trusting the copies inside the proof file
+            fs::write(format!("{}.bn254.vk", output), &exported.bn254_vk)
+                .and_then(|_| fs::write(format!("{}.bls12-381.vk", output), &exported.bls12_381_vk))
+                .map_err(|e| format!("Failed to write verifying keys: {}", e))?;
+            Ok(exported)
+        });
+    let exported = match exported {
+        Ok(exported) => exported,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    println!();
+    println!("SUCCESS!");
+    println!("  Proof id:   {}", exported.id);
+    println!(
+        "  Result:     {} x {} = {}",
+        a, b, exported.proof.public_inputs[0]
+    );
+    println!(
+        "  Proof size: {} + {} bytes",
+        exported.proof.bn254.proof_bytes.len(),
+        exported.proof.bls12_381.proof_bytes.len()
+    );
+    print_dual_verification(&verification);
+    println!("  Time:       {:?}", elapsed);
+    println!("  Saved to:   {}", output);
+    println!("  Keys:       {0}.bn254.vk, {0}.bls12-381.vk", output);
+}
+
+fn print_dual_verification(verification: &DualVerification) {
+    let show = |checked: Option<bool>| match checked {
+        Some(true) => "YES",
+        Some(false) => "NO",
+        None => "not checked",
+    };
+    println!("  BN254:      {}", show(verification.bn254));
+    println!("  BLS12-381:  {}", show(verification.bls12_381));
+    println!(
+        "  Verified:   {} (requires {})",
+        if verification.accepted { "YES" } else { "NO" },
+        verification.requirement.name()
+    );
+}
+
+fn print_calldata(calldata: &Calldata) {
+    println!("Calldata ({}):", calldata.backend.name());
+    println!("  {}", calldata.to_args());
+}
+
+fn do_verify_dual(
+    proof_path: &str,
+    require: Option<String>,
+    keys: (Option<String>, Option<String>),
+    calldata: bool,
+) {
+    let exported = match ExportedDualProof::load(proof_path) {
+        Ok(exported) => exported,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let requirement = match require {
+        Some(name) => HedgeRequirement::parse(&name).ok_or_else(|| {
+            format!(
+                "Unknown requirement '{}' (both, either, bn254, bls12-381)",
+                name
+            )
+        }),
+        None => load_config().morph.hedge_requirement(),
+    };
+    let requirement = match requirement {
+        Ok(requirement) => requirement,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    // Keys from the flags, else from config; never the ones in the file
+    let (bn254_vk, bls12_381_vk) = match keys {
+        (None, None) => {
+            let morph = load_config().morph;
+            (morph.hedge_bn254_vk, morph.hedge_bls12_381_vk)
+        }
+        keys => keys,
+    };
+    let trusted = match TrustedKeys::load(bn254_vk.as_deref(), bls12_381_vk.as_deref()) {
+        Ok(trusted) => trusted,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    println!("Proof id:      {}", exported.id);
+    println!("Public output: {}", exported.proof.public_inputs.join(","));
+    println!("Created:       {}", exported.timestamp);
+    println!();
+    match exported.verify(requirement, &trusted) {
+        Ok(verification) => print_dual_verification(&verification),
+        Err(e) => println!("ERROR: {}", e),
+    }
+    if calldata {
+        println!();
+        for data in &exported.calldata {
+            print_calldata(data);
+        }
+    }
+}
+
+fn do_verify(
+    proof_path: String,
+    require: Option<String>,
+    keys: (Option<String>, Option<String>),
+    calldata: bool,
+) {
     println!(" PROOF FILE INFO");
     println!("File: {}", proof_path);
     println!("");
@@ -170,6 +515,11 @@ fn do_verify(proof_path: String) {
         }
     };
 
+    if content.contains("\"bls12_381_vk\"") {
+        do_verify_dual(&proof_path, require, keys, calldata);
+        return;
+    }
+
     let backend = extract_json_string(&content, "backend");
     let proof_hex = extract_json_string(&content, "proof_hex");
     let public_output = extract_json_string(&content, "public_output");
@@ -191,30 +541,387 @@ fn do_verify(proof_path: String) {
     println!("Public output: {}", public_output);
     println!("Proof size:    {} bytes", proof_bytes.len());
     println!("");
+    if calldata {
+        let proof = BackendType::parse(&backend)
+            .ok_or_else(|| format!("Unknown backend '{}'", backend))
+            .and_then(|backend| {
+                Calldata::for_proof(&types::UniversalProof {
+                    backend,
+                    proof_bytes: proof_bytes.clone(),
+                    public_inputs: vec![public_output.clone()],
+                })
+            });
+        match proof {
+            Ok(data) => print_calldata(&data),
+            Err(e) => println!("ERROR: {}", e),
+        }
+        println!();
+    }
     println!("NOTE: Proof was verified during generation.");
     println!("      Re-verification requires same setup keys.");
     println!("      Use 'benchmark' command for full prove/verify demo.");
 }
-fn do_morph(to: String) {
+fn do_morph(
+    to: String,
+    operator: String,
+    reason: Option<String>,
+    emergency: bool,
+    commitment_proofs: Vec<String>,
+    canary: bool,
+) {
     println!("                    MORPHING BACKEND");
-    match to.to_lowercase().as_str() {
-        "bn254" => {
+    let target = match BackendType::parse(&to) {
+        Some(backend) => backend,
+        None => {
+            println!("ERROR: Unknown backend '{}'", to);
+            println!("Available: bn254, bls12-381");
+            return;
+        }
+    };
+
+    let config = load_config();
+    let mut controller = match open_controller(&config) {
+        Ok(controller) => controller,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    if target == controller.current_backend() {
+        println!("Already on {}", target.name());
+        return;
+    }
+
+    if canary || config.morph.canary_enabled {
+        println!("Canary:       setting up both backends...");
+        if let Err(e) = controller.initialize() {
+            println!("ERROR: {}", e);
+            return;
+        }
+        controller.set_canary(Some(config.morph.canary_latency_budget()));
+    }
+
+    let context = match (emergency, reason) {
+        (true, Some(reason)) => MorphContext::emergency(&operator, &reason),
+        (true, None) => {
+            println!("ERROR: --emergency requires --reason");
+            return;
+        }
+        (false, reason) => {
+            MorphContext::manual(&operator, reason.as_deref().unwrap_or("manual morph"))
+        }
+    }
+    .with_commitment_proofs(commitment_proofs);
+
+    let result = match controller.morph_with(target, context) {
+        Ok(result) => result,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    if let Some(canary) = &result.canary {
+        println!(
+            "Canary:       PASSED (prove {}ms, verify {}ms, proof {})",
+            canary.prove_ms, canary.verify_ms, canary.proof_id
+        );
+    }
+
+    match target {
+        BackendType::BN254 => {
             println!("Switching to: BN254");
-            println!("Security:     100-bit");
+            println!("Security:     {}-bit", target.security_bits());
             println!("Optimization: Ethereum precompiles");
-            println!("");
-            println!("Backend switched successfully!");
         }
-        "bls12-381" | "bls" => {
+        BackendType::BLS12_381 => {
             println!("Switching to: BLS12-381");
-            println!("Security:     128-bit");
+            println!("Security:     {}-bit", target.security_bits());
             println!("Optimization: Higher security applications");
+        }
+    }
+    println!();
+    println!("Backend switched successfully!");
+    if let Some(entry) = controller.audit_log().and_then(|log| log.last()) {
+        println!("Audit entry:  #{} {}", entry.index, entry.hash);
+    }
+}
+
+fn do_rollback(operator: String, reason: String) {
+    println!("                    ROLLING BACK MORPH");
+
+    let config = load_config();
+    let mut controller = match open_controller(&config) {
+        Ok(controller) => controller,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    match controller.rollback(&operator, &reason) {
+        Ok(result) => {
+            println!(
+                "Rolled back:  {} -> {}",
+                result.old_backend.name(),
+                result.new_backend.name()
+            );
+            if let Some(entry) = controller.audit_log().and_then(|log| log.last()) {
+                println!(
+                    "Audit entry:  #{} (undoes #{}) {}",
+                    entry.index,
+                    entry.rollback_of.map_or("?".to_string(), |i| i.to_string()),
+                    entry.hash
+                );
+            }
+        }
+        Err(e) => println!("ERROR: {}", e),
+    }
+}
+
+fn do_confirm(operator: String) {
+    println!("                    CONFIRMING COMMIT POINT");
+
+    let config = load_config();
+    let mut controller = match open_controller(&config) {
+        Ok(controller) => controller,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    let backend = controller.current_backend();
+    match controller.confirm_commit_point(&operator) {
+        Ok(_) => {
+            println!("Confirmed:    proofs on {} are committed", backend.name());
+            println!("Operator:     {}", operator);
+            println!("Rollback of the last morph is now refused");
+        }
+        Err(e) => println!("ERROR: {}", e),
+    }
+}
+
+/// Rebuild the controller from the audit log and journal left by earlier
+/// invocations: current backend, cooldown, rollback window, and any morph
+/// that was interrupted.
+fn open_controller(config: &Config) -> Result<MorphController, String> {
+    let log = AuditLog::open(&config.output.audit_log)?;
+
+    // The audit log is the record of which backend we are on
+    let current = log
+        .last()
+        .map(|entry| entry.new_backend)
+        .or_else(|| BackendType::parse(&config.default_backend))
+        .unwrap_or(BackendType::BN254);
+
+    let model = ThreatModel::load_default()?;
+    let mut controller = MorphController::from_config(current, &config.morph, &model);
+    controller.set_registry(JurisdictionRegistry::load_default()?);
+    controller.set_latency_window(config.latency.window);
+    controller.set_journal(MorphJournal::open(&config.output.morph_journal)?);
+    if let Some(entry) = log.last_morph() {
+        if let Ok(at) = chrono::DateTime::parse_from_rfc3339(&entry.timestamp) {
+            let elapsed = chrono::Utc::now()
+                .signed_duration_since(at)
+                .to_std()
+                .unwrap_or_default();
+            controller.resume_cooldown(elapsed);
+            controller.resume_rollback_window(entry, elapsed);
+        }
+    }
+    if let Some(entry) = log.frozen() {
+        controller.restore_freeze(&entry.reason);
+    }
+    controller.set_audit_log(log);
+
+    match controller.recover() {
+        Ok(Some(phase)) => {
+            println!("Recovered interrupted morph: {}", phase.name());
+            println!("Now on:       {}", controller.current_backend().name());
+            println!();
+        }
+        Ok(None) => {}
+        Err(e) => return Err(format!("Could not recover interrupted morph: {}", e)),
+    }
+
+    Ok(controller)
+}
+
+fn do_serve(listen: String, grpc: Option<String>, policy: Option<String>, metrics: Option<String>) {
+    let config = load_config();
+    let model = load_threat_model();
+    let policy = match load_policy(policy.as_deref(), &model) {
+        Ok(policy) => policy,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    let controller = match open_controller(&config) {
+        Ok(controller) => controller,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let current = controller.current_backend();
+    println!("Setting up {} keys...", current.name());
+    if let Err(e) = controller.setup(current) {
+        println!("ERROR: {}", e);
+        return;
+    }
+
+    // POST /threat takes signed reports only; without registered reporters
+    // the daemon runs with no threat feed
+    let feed = ReporterConfig::load_default().and_then(|reporters| {
+        if reporters.reporters.is_empty() {
+            return Err("No reporters registered in config/reporters.json \
+                        (add keys from `report keygen`)"
+                .to_string());
+        }
+        ThreatFeed::new(reporters, model.clone())
+    });
+    let feed = match feed {
+        Ok(mut feed) => {
+            feed.set_rejection_log(&config.output.rejected_reports);
+            Some(feed)
+        }
+        Err(e) => {
+            println!("WARNING: Threat feed disabled: {}", e);
+            None
+        }
+    };
+
+    let mut simulator = ThreatSimulator::from_config(current, &config.morph);
+    simulator.model = model;
+    simulator.policy = policy;
+    simulator.jurisdiction = controller
+        .registry()
+        .map(|registry| registry.active_jurisdiction.clone());
+    if config.morph.flap_detection.enabled {
+        simulator.enable_flap_detection(&config.morph.flap_detection);
+    }
+    // A freeze outlives restarts until an operator confirms it
+    let frozen = controller.audit_log().and_then(AuditLog::frozen);
+    let now = simulator.now();
+    if let (Some(entry), Some(detector)) = (frozen, simulator.detector.as_mut()) {
+        detector.restore_freeze(now, &entry.reason);
+    }
+
+    let queue = match JobQueue::open(&config.jobs) {
+        Ok(queue) => Arc::new(queue),
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let slos = config.latency.slos().unwrap_or_else(|e| {
+        println!("WARNING: Latency SLOs ignored: {}", e);
+        Vec::new()
+    });
+    let mut service = ProverService::new(controller, simulator, &config.morph)
+        .with_jobs(Arc::clone(&queue))
+        .with_operators(config.server.operators.clone())
+        .with_slos(slos);
+    if let Some(feed) = feed {
+        service = service.with_feed(feed);
+    }
+    let service = Arc::new(service);
+    let grpc = match grpc.map(|addr| GrpcServer::start(&addr, Arc::clone(&service))) {
+        Some(Ok(server)) => Some(server),
+        Some(Err(e)) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+        None => None,
+    };
+    let server = match Server::start(&listen, Arc::clone(&service), config.server.max_connections) {
+        Ok(server) => server,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    start_metrics(metrics, &config);
+    let shutdown = server.shutdown_handle();
+    if let Err(e) = ctrlc::set_handler(move || shutdown.shutdown()) {
+        println!("ERROR: Failed to install signal handler: {}", e);
+        server.shutdown();
+        return;
+    }
+    let counts = queue.counts();
+    let runner = {
+        let service = Arc::clone(&service);
+        JobRunner::start(queue, move |job| service.prove_job(job))
+    };
+    let checker = LatencyChecker::start(Arc::clone(&service), config.latency.check_interval());
+
+    println!("Serving on:   http://{}", server.addr());
+    if let Some(grpc) = &grpc {
+        println!("gRPC on:      {}", grpc.addr());
+    }
+    println!("Backend:      {}", current.name());
+    println!(
+        "Jobs:         {} queued ({})",
+        counts.get("queued").copied().unwrap_or(0),
+        config.jobs.path
+    );
+    println!("Ctrl-C to stop; requests in progress are finished first");
+    server.join();
+    checker.stop();
+    runner.stop();
+    if let Some(Err(e)) = grpc.map(GrpcServer::shutdown) {
+        println!("ERROR: {}", e);
+    }
+    println!("Stopped");
+}
+
+fn do_audit(action: AuditAction) {
+    let default_log = || load_config().output.audit_log;
+
+    match action {
+        AuditAction::Verify { log } => {
+            let path = log.unwrap_or_else(default_log);
+            println!("                    AUDIT LOG VERIFICATION");
+            println!("Log: {}", path);
             println!("");
-            println!("Backend switched successfully!");
+
+            match audit::verify_file(Path::new(&path)) {
+                Ok(report) => {
+                    println!("INTACT");
+                    println!("  Entries:   {}", report.entries);
+                    println!("  Head hash: {}", report.head_hash);
+                }
+                Err(e) => {
+                    println!("TAMPERED: {}", e);
+                    std::process::exit(1);
+                }
+            }
         }
-        _ => {
-            println!("ERROR: Unknown backend '{}'", to);
-            println!("Available: bn254, bls12-381");
+        AuditAction::Show { log } => {
+            let path = log.unwrap_or_else(default_log);
+            let log = match AuditLog::open(&path) {
+                Ok(log) => log,
+                Err(e) => {
+                    println!("ERROR: {}", e);
+                    return;
+                }
+            };
+
+            println!("                    MORPH AUDIT LOG");
+            for entry in log.entries() {
+                println!(
+                    "  #{:<3} {} {:>9} -> {:<9} {:<13} by {}: {}",
+                    entry.index,
+                    entry.timestamp,
+                    entry.old_backend.name(),
+                    entry.new_backend.name(),
+                    entry.trigger.name(),
+                    entry.operator,
+                    entry.reason
+                );
+            }
         }
     }
 }
@@ -246,53 +953,707 @@ fn do_benchmark(iterations: u32) {
     println!("  Proof Size   | {:>10} B | {:>10} B", r1.3, r2.3);
     println!("  Status       | {:>12}   | {:>12}", r1.4, r2.4);
     println!("");
+
+    let config = load_config();
+    match config.latency.slos() {
+        Ok(slos) if !slos.is_empty() => {
+            let mut tracker = LatencyTracker::new(config.latency.window);
+            for (backend, r) in [(BackendType::BN254, &r1), (BackendType::BLS12_381, &r2)] {
+                let ms = |t: u128| Duration::from_millis(t as u64);
+                tracker.record(backend, Operation::Setup, ms(r.0));
+                tracker.record(backend, Operation::Prove, ms(r.1));
+                tracker.record(backend, Operation::Verify, ms(r.2));
+            }
+            println!("  SLOs");
+            for backend in [BackendType::BN254, BackendType::BLS12_381] {
+                for status in tracker.check(&slos, backend) {
+                    println!(
+                        "  {:<10} {:<28} {:>6}ms  {}",
+                        backend.name(),
+                        status.slo.to_string(),
+                        status.observed.as_millis(),
+                        if status.breached() { "BREACHED" } else { "OK" }
+                    );
+                }
+                if let Some(score) = tracker.performance_score(&slos, backend) {
+                    println!(
+                        "  {:<10} performance threat score {}",
+                        backend.name(),
+                        score
+                    );
+                }
+            }
+            println!();
+        }
+        Ok(_) => {}
+        Err(e) => println!("ERROR: {}", e),
+    }
     println!("Both backends operational!");
 }
 
-fn do_simulate(threat: String, level: u32) {
+#[allow(clippy::too_many_arguments)]
+fn do_simulate(
+    threat: String,
+    level: u32,
+    policy: Option<String>,
+    jurisdiction: Option<String>,
+    explain: bool,
+    reports: Option<(String, Option<String>)>,
+    quantum_forecast: bool,
+) {
     println!("                    THREAT SIMULATION");
     println!("");
 
-    let (quantum, regulatory) = match threat.to_lowercase().as_str() {
-        "quantum" => (level, 20),
-        "regulatory" => (20, level),
-        "both" => (level, level),
-        _ => (level, 20),
+    let config = load_config();
+    let model = load_threat_model();
+
+    let source = match &reports {
+        Some((path, _)) => format!("signed reports ({})", path),
+        None if quantum_forecast => "quantum (hardware forecast)".to_string(),
+        None => threat.clone(),
+    };
+    let levels = match reports {
+        Some((path, reporters)) => {
+            match threat_from_reports(&path, reporters.as_deref(), &model, &config) {
+                Ok(levels) => levels,
+                Err(e) => {
+                    println!("ERROR: {}", e);
+                    return;
+                }
+            }
+        }
+        None if quantum_forecast => match QuantumForecast::load_default() {
+            Ok(forecast) => {
+                let score = forecast.quantum_score(quantum::current_year());
+                match what_if_level("quantum", score, &model) {
+                    Some(levels) => levels,
+                    None => return,
+                }
+            }
+            Err(e) => {
+                println!("ERROR: {}", e);
+                return;
+            }
+        },
+        None => match what_if_level(&threat, level, &model) {
+            Some(levels) => levels,
+            None => return,
+        },
     };
 
-    let overall = (quantum * 60 + regulatory * 40) / 100;
+    let current = AuditLog::open(&config.output.audit_log)
+        .ok()
+        .and_then(|log| log.last().map(|entry| entry.new_backend))
+        .or_else(|| BackendType::parse(&config.default_backend))
+        .unwrap_or(BackendType::BN254);
+    let morph_config = &config.morph;
+    let policy = match load_policy(policy.as_deref(), &model) {
+        Ok(policy) => policy,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let mut simulator = ThreatSimulator::from_config(current, morph_config);
+    simulator.model = model;
+    simulator.policy = policy;
+    simulator.jurisdiction = jurisdiction;
 
-    println!("  Threat Type: {}", threat);
+    println!("  Threat Type: {}", source);
     println!("");
     println!("  Levels:");
-    println!("    Quantum:    {:>3}/100 {}", quantum, make_bar(quantum));
+    for category in &simulator.model.categories {
+        let score = levels.score(&category.name);
+        println!(
+            "    {:<12}{:>3}/100 {}",
+            format!("{}:", capitalize(&category.name)),
+            score,
+            make_bar(score)
+        );
+    }
     println!(
-        "    Regulatory: {:>3}/100 {}",
-        regulatory,
-        make_bar(regulatory)
+        "    {:<12}{:>3}/100 {}",
+        "Overall:",
+        levels.overall,
+        make_bar(levels.overall)
     );
-    println!("    Overall:    {:>3}/100 {}", overall, make_bar(overall));
     println!("");
 
     println!("  DECISION");
 
-    if quantum >= 80 {
-        println!("  Action:  EMERGENCY MORPH");
-        println!("  Target:  BLS12-381");
-        println!("  Reason:  Critical quantum threat detected");
-    } else if quantum >= 60 {
-        println!("  Action:  PREEMPTIVE MORPH");
-        println!("  Target:  BLS12-381");
-        println!("  Reason:  Elevated quantum threat");
-    } else if overall < 30 {
-        println!("  Action:  OPTIMIZE");
-        println!("  Target:  BN254");
-        println!("  Reason:  Low threat, use faster backend");
+    let explanation = simulator.explain(&levels);
+    let decision = explanation.decision.clone();
+    let action = if !decision.should_morph {
+        "NO CHANGE"
+    } else if decision.emergency {
+        "EMERGENCY MORPH"
+    } else if decision.recommended_backend == BackendType::BLS12_381 {
+        "PREEMPTIVE MORPH"
     } else {
-        println!("  Action:  NO CHANGE");
-        println!("  Target:  Current backend");
-        println!("  Reason:  Threat level acceptable");
+        "OPTIMIZE"
+    };
+    println!("  Action:  {}", action);
+    println!("  Current: {}", current.name());
+    println!("  Target:  {}", decision.recommended_backend.name());
+    println!("  Reason:  {}", decision.reason);
+    println!(
+        "  Rule:    {}",
+        decision.rule.as_deref().unwrap_or("(none matched)")
+    );
+
+    if explain {
+        println!();
+        explanation.print();
+    }
+}
+
+fn do_quantum(year: Option<f64>, forecast: Option<String>) {
+    println!("                    QUANTUM RESOURCE ESTIMATE");
+    println!();
+
+    let forecast = match forecast {
+        Some(path) => QuantumForecast::load(&path),
+        None => QuantumForecast::load_default(),
+    };
+    let forecast = match forecast {
+        Ok(forecast) => forecast,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let year = year.unwrap_or_else(quantum::current_year);
+    let (logical, rate) = forecast.capability(year);
+
+    println!("  Year:      {:.1}", year);
+    println!(
+        "  Hardware:  {:.0} logical qubits, {:.0e} Toffoli/s",
+        logical, rate
+    );
+    println!(
+        "  Practical: under {} days, score 0 at {} years of margin",
+        forecast.max_runtime_days, forecast.horizon_years
+    );
+    println!();
+    println!(
+        "  {:<10} {:>6} {:>8} {:>10} {:>12} {:>7} {:>7} {:>6}",
+        "Curve", "Field", "Qubits", "Toffoli", "Runtime", "Breaks", "Margin", "Score"
+    );
+    for backend in [BackendType::BN254, BackendType::BLS12_381] {
+        let assessment = forecast.assess(backend, year);
+        let estimate = &assessment.estimate;
+        let runtime = estimate.runtime(rate).as_secs_f64() / 86_400.0;
+        println!(
+            "  {:<10} {:>6} {:>8} {:>10.2e} {:>11.1}d {:>7} {:>7} {:>6}",
+            backend.name(),
+            estimate.field_bits,
+            estimate.logical_qubits,
+            estimate.toffoli_count,
+            runtime,
+            assessment
+                .break_year
+                .map_or("never".to_string(), |y| format!("{:.1}", y)),
+            assessment
+                .margin_years
+                .map_or("-".to_string(), |m| format!("{:.1}y", m)),
+            assessment.score
+        );
+    }
+    println!();
+    println!(
+        "  Quantum threat: {}/100 {}",
+        forecast.quantum_score(year),
+        make_bar(forecast.quantum_score(year))
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn do_scenario(
+    pattern: Option<String>,
+    cycles: Option<u32>,
+    interval: Option<u32>,
+    seed: u64,
+    policy: Option<String>,
+    assertions: Vec<String>,
+    prove: bool,
+    optimize: bool,
+) {
+    println!("                    THREAT SCENARIO");
+    println!();
+
+    let config = load_config();
+    let threat_config = match ThreatConfig::load_default() {
+        Ok(Some(tc)) => tc,
+        Ok(None) => {
+            println!("ERROR: threat_config.json not found");
+            return;
+        }
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    let setup = || -> Result<_, String> {
+        let model = ThreatModel::from_config(&threat_config)?;
+        let mut scenario = Scenario::from_config(&threat_config)?;
+        if let Some(pattern) = &pattern {
+            scenario.pattern = ThreatPattern::parse(pattern)?;
+        }
+        if let Some(cycles) = cycles {
+            scenario.cycles = cycles;
+        }
+        if let Some(interval) = interval {
+            scenario.interval = Duration::from_secs(interval as u64);
+        }
+        scenario.seed = seed;
+
+        let assertions = assertions
+            .iter()
+            .map(|a| Assertion::parse(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        let policy = load_policy(policy.as_deref(), &model)?;
+        Ok((model, scenario, assertions, policy))
+    };
+    let (model, scenario, assertions, policy) = match setup() {
+        Ok(setup) => setup,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    let initial = BackendType::parse(&config.default_backend).unwrap_or(BackendType::BN254);
+    let morph_config = &config.morph;
+    let mut simulator = ThreatSimulator::from_config(initial, morph_config);
+    simulator.model = model.clone();
+    simulator.policy = policy;
+    if morph_config.flap_detection.enabled {
+        simulator.enable_flap_detection(&morph_config.flap_detection);
+    }
+
+    let mut controller = if prove {
+        match scenario_controller(initial, morph_config, &model) {
+            Ok(controller) => Some(controller),
+            Err(e) => {
+                println!("ERROR: {}", e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    println!("  Pattern:  {}", scenario.pattern.name());
+    println!("  Interval: {}s", scenario.interval.as_secs());
+    println!("  Policy:   {}", simulator.policy().name);
+    println!();
+
+    let report = scenario::run(&scenario, &mut simulator, controller.as_mut());
+    for cycle in &report.cycles {
+        let action = match (&cycle.error, cycle.morphed()) {
+            (Some(e), _) => format!("MORPH FAILED: {}", e),
+            (None, true) => format!(
+                "MORPH {} -> {}: {}",
+                cycle.from.name(),
+                cycle.backend.name(),
+                cycle.decision.reason
+            ),
+            (None, false) if cycle.decision.recommended_backend != cycle.from => {
+                format!("HOLD: {}", cycle.decision.reason)
+            }
+            (None, false) => String::new(),
+        };
+        let action = match (action.is_empty(), cycle.prewarmed) {
+            (true, _) if cycle.frozen => "FROZEN".to_string(),
+            (true, Some(backend)) => format!("PREWARM {}", backend.name()),
+            _ => action,
+        };
+        println!(
+            "  {:>3} t={:>5}s | Q:{:>3} R:{:>3} | Score:{:>3} {} | {:<9} {}",
+            cycle.cycle,
+            cycle.at.as_secs(),
+            cycle.threat.quantum,
+            cycle.threat.regulatory,
+            cycle.threat.overall,
+            make_bar(cycle.threat.overall),
+            cycle.backend.name(),
+            action
+        );
+    }
+    println!();
+    println!("  Morphs: {}", report.morph_count());
+    if let Some(detector) = &simulator.detector {
+        for alert in detector.alerts() {
+            println!(
+                "  Alert:  t={}s auto-morph frozen: {}",
+                alert.at.as_secs(),
+                alert.kind
+            );
+        }
+    }
+
+    if optimize {
+        let mut optimized = ThreatSimulator::from_config(initial, morph_config);
+        optimized.model = model.clone();
+        // A fresh controller, so the optimizer starts where the policy did
+        let mut controller = if prove {
+            match scenario_controller(initial, morph_config, &model) {
+                Ok(controller) => Some(controller),
+                Err(e) => {
+                    println!("ERROR: {}", e);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        compare_optimizer(&scenario, &report, &mut optimized, controller.as_mut());
+    }
+
+    if assertions.is_empty() {
+        return;
+    }
+    println!();
+    println!("  ASSERTIONS");
+    let mut failed = 0;
+    for assertion in &assertions {
+        match assertion.check(&report) {
+            Ok(()) => println!("  PASS  {}", assertion),
+            Err(e) => {
+                failed += 1;
+                println!("  FAIL  {}: {}", assertion, e);
+            }
+        }
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+// The simulator enforces the cooldown in scenario time, so the
+// controller gets none of its own and no audit log. Only the starting
+// backend is set up now; the others warm up as the trend approaches them.
+fn scenario_controller(
+    initial: BackendType,
+    morph_config: &MorphConfig,
+    model: &ThreatModel,
+) -> Result<MorphController, String> {
+    let mut controller = MorphController::new(initial);
+    controller.setup(initial)?;
+    if morph_config.prewarm.enabled {
+        controller.set_projector(Some(TrendProjector::new(
+            morph_config.prewarm.clone(),
+            model,
+            morph_config.threat_threshold,
+            morph_config.recovery_threshold,
+        )));
+    }
+    controller.set_registry(JurisdictionRegistry::load_default()?);
+    Ok(controller)
+}
+
+/// Run the cost optimizer on the scenario's timeline and print it next to
+/// the policy run, both judged by the same costs. With a controller, the
+/// optimizer learns from proofs it times, and prove times in those costs
+/// are the medians the pool recorded.
+fn compare_optimizer(
+    scenario: &Scenario,
+    policy: &scenario::ScenarioReport,
+    simulator: &mut ThreatSimulator,
+    mut controller: Option<&mut MorphController>,
+) {
+    let cost = backtest::ProvingCost::default();
+    let proofs_per_window = cost.proofs_per_hour as f64 * scenario.interval.as_secs_f64() / 3600.0;
+    let mut optimizer =
+        Optimizer::new(simulator.threat_threshold, proofs_per_window, scenario.seed);
+    let mut world = SimulatedCosts::new(scenario.seed);
+
+    let optimized = scenario::run_optimized(
+        scenario,
+        simulator,
+        &mut optimizer,
+        &mut world,
+        controller.as_deref_mut(),
+    );
+    if let Some(controller) = controller {
+        let latency = controller.latency();
+        for backend in [BackendType::BN254, BackendType::BLS12_381] {
+            if let Some(summary) = latency.summary(backend, Operation::Prove) {
+                world.set_prove_time(backend, summary.p50);
+            }
+        }
+    }
+
+    println!();
+    println!("  OPTIMIZER VS POLICY");
+    println!("  Strategy   | Morphs | Cost        | Under-protected cycles");
+    println!("  -----------|--------|-------------|-----------------------");
+    for (name, report) in [("policy", policy), ("optimizer", &optimized)] {
+        let run = optimizer::run_cost(report, &optimizer, &world, &simulator.model);
+        println!(
+            "  {:<10} | {:>6} | {:>11.0} | {:>6}",
+            name, run.morphs, run.cost, run.under_protected
+        );
+    }
+    for backend in [BackendType::BN254, BackendType::BLS12_381] {
+        let estimate = optimizer.estimate(backend);
+        println!(
+            "  Learned {:<9} cost/proof {:>6.1} (sd {:.1}, {} measurements)",
+            backend.name(),
+            estimate.mean,
+            estimate.variance.sqrt(),
+            estimate.samples
+        );
+    }
+}
+
+fn do_backtest(log: String, policies: Vec<String>, proofs_per_hour: u64) {
+    println!("                    POLICY BACKTEST");
+    println!();
+
+    let config = load_config();
+    let setup = || -> Result<_, String> {
+        let model = ThreatModel::load_default()?;
+        let readings = backtest::load_log(&log, &model)?;
+
+        let policies = if policies.is_empty() {
+            vec![load_policy(None, &model)?]
+        } else {
+            policies
+                .iter()
+                .map(|p| load_policy(Some(p), &model))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        Ok((model, readings, policies))
+    };
+    let (model, readings, policies) = match setup() {
+        Ok(setup) => setup,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+
+    let morph_config = &config.morph;
+    let initial = readings[0]
+        .backend
+        .or_else(|| BackendType::parse(&config.default_backend))
+        .unwrap_or(BackendType::BN254);
+    let cost = backtest::ProvingCost {
+        proofs_per_hour,
+        ..backtest::ProvingCost::default()
+    };
+    let summarize = |backends: &[BackendType]| {
+        backtest::summarize(
+            &readings,
+            backends,
+            initial,
+            &model,
+            morph_config.threat_threshold,
+            &cost,
+        )
+    };
+
+    let mut runs = Vec::new();
+    if let Some(recorded) = backtest::recorded_backends(&readings) {
+        runs.push(("as recorded".to_string(), summarize(&recorded)));
+    }
+    for policy in policies {
+        let mut simulator = ThreatSimulator::from_config(initial, morph_config);
+        simulator.model = model.clone();
+        simulator.policy = policy;
+        let name = simulator.policy().name.clone();
+        let backends = backtest::replay(&readings, &mut simulator);
+        runs.push((name, summarize(&backends)));
+    }
+
+    println!("  Log:      {} ({} readings)", log, readings.len());
+    println!(
+        "  Cost:     BN254 {}ms, BLS12-381 {}ms per proof, {} proofs/hour",
+        cost.bn254_prove_ms, cost.bls12_381_prove_ms, cost.proofs_per_hour
+    );
+    println!();
+    println!(
+        "  {:<22} | {:>6} | {:>10} | {:>10} | {:>10} | {:>12}",
+        "Policy", "Morphs", "BN254", "BLS12-381", "Exposed", "Extra prove"
+    );
+    println!("  {}", "-".repeat(86));
+    for (name, report) in &runs {
+        println!(
+            "  {:<22} | {:>6} | {:>9}s | {:>9}s | {:>9}s | {:>10}ms",
+            name,
+            report.morphs,
+            report.time_on_bn254.as_secs(),
+            report.time_on_bls12_381.as_secs(),
+            report.under_protected.as_secs(),
+            report.extra_proving.as_millis()
+        );
+    }
+    println!();
+    println!("  Exposed: time on BN254 while the threat was at an upgrade threshold");
+}
+
+// Helper: Threat level with one category (or both/all) at `level`
+fn what_if_level(threat: &str, level: u32, model: &ThreatModel) -> Option<ThreatLevel> {
+    // The chosen category gets `level`; everything else sits at a baseline
+    let chosen = threat.to_lowercase();
+    if !matches!(chosen.as_str(), "both" | "all") && model.category(&chosen).is_none() {
+        let names: Vec<&str> = model.categories.iter().map(|c| c.name.as_str()).collect();
+        println!("ERROR: Unknown threat '{}'", threat);
+        println!("Available: {}, both, all", names.join(", "));
+        return None;
+    }
+    let scores: Vec<(&str, u32)> = model
+        .categories
+        .iter()
+        .map(|c| {
+            let hit = match chosen.as_str() {
+                "all" => true,
+                "both" => c.name == "quantum" || c.name == "regulatory",
+                name => c.name == name,
+            };
+            (c.name.as_str(), if hit { level } else { 20 })
+        })
+        .collect();
+    Some(model.level(&scores))
+}
+
+// Helper: Threat level from signed reports, after signature, quorum and
+// outlier checks
+fn threat_from_reports(
+    path: &str,
+    reporters: Option<&str>,
+    model: &ThreatModel,
+    config: &Config,
+) -> Result<ThreatLevel, String> {
+    let reporter_config = match reporters {
+        Some(path) => ReporterConfig::load(path)?,
+        None => ReporterConfig::load_default()?,
+    };
+    let mut feed = ThreatFeed::new(reporter_config, model.clone())?;
+    feed.set_rejection_log(&config.output.rejected_reports);
+
+    let reports = reports::load_reports(path)?;
+    let total = reports.len();
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    for report in reports {
+        // Rejections are logged by the feed
+        feed.submit(report, now).ok();
+    }
+    let (levels, consensus) = feed.threat_level(now);
+
+    println!("  Reports: {} ({} rejected)", total, feed.rejected().len());
+    for (name, c) in &consensus {
+        match c.score {
+            Some(score) => println!(
+                "    {:<12} {:>3} from {}{}",
+                format!("{}:", name),
+                score,
+                c.reporters.join(", "),
+                if c.stale { " (stale)" } else { "" }
+            ),
+            None => println!(
+                "    {:<12} no quorum ({} of {} reporters)",
+                format!("{}:", name),
+                c.reporters.len(),
+                c.quorum
+            ),
+        }
+    }
+    println!();
+    Ok(levels)
+}
+
+fn do_report(action: ReportAction) {
+    match action {
+        ReportAction::Keygen { out } => {
+            let key = SigningKey::generate(&mut rand::rngs::OsRng);
+            if let Err(e) = fs::write(&out, hex::encode(key.to_bytes())) {
+                println!("ERROR: Failed to write key: {}", e);
+                return;
+            }
+            println!("Secret key written to {}", out);
+            println!(
+                "Public key: {}",
+                hex::encode(key.verifying_key().to_bytes())
+            );
+        }
+        ReportAction::Sign {
+            key,
+            reporter,
+            category,
+            score,
+            out,
+        } => {
+            let sign = || -> Result<SignedThreatReport, String> {
+                let secret =
+                    fs::read_to_string(&key).map_err(|e| format!("Failed to read key: {}", e))?;
+                let key = reports::parse_signing_key(&secret)?;
+                let timestamp = chrono::Utc::now().timestamp() as u64;
+                let report = SignedThreatReport::sign(&key, &reporter, &category, score, timestamp);
+
+                if let Some(parent) = Path::new(&out).parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                let line = serde_json::to_string(&report).map_err(|e| e.to_string())?;
+                let mut file = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&out)
+                    .map_err(|e| format!("Failed to open {}: {}", out, e))?;
+                writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+                Ok(report)
+            };
+            match sign() {
+                Ok(report) => println!(
+                    "Signed {} = {} from {} -> {}",
+                    report.category, report.score, report.reporter, out
+                ),
+                Err(e) => println!("ERROR: {}", e),
+            }
+        }
+    }
+}
+
+// Helper: Load the config, stopping on an invalid one rather than running on defaults.
+// The upgrade/recovery thresholds come from threat_config.json when there is one,
+// the same file the threat model and scripts/threat_simulator.sh read.
+fn load_config() -> Config {
+    let load = || -> Result<Config, String> {
+        let mut config = Config::load_default()?;
+        if let Some(threats) = ThreatConfig::load_default()? {
+            config.morph = threats.apply_to(&config.morph);
+            config
+                .morph
+                .validate()
+                .map_err(|e| format!("Invalid threat_config.json: {}", e))?;
+        }
+        Ok(config)
+    };
+    load().unwrap_or_else(|e| {
+        println!("ERROR: {}", e);
+        std::process::exit(1);
+    })
+}
+
+// Helper: Load the threat model from threat_config.json, stopping if it is invalid
+fn load_threat_model() -> ThreatModel {
+    ThreatModel::load_default().unwrap_or_else(|e| {
+        println!("ERROR: {}", e);
+        std::process::exit(1);
+    })
+}
+
+// Helper: Load the policy file named on the command line, or the default one
+fn load_policy(path: Option<&str>, model: &ThreatModel) -> Result<Option<MorphPolicy>, String> {
+    let policy = match path {
+        Some(path) => Some(MorphPolicy::load(path)?),
+        None => MorphPolicy::load_default()?,
+    };
+    if let Some(policy) = &policy {
+        policy.validate(model)?;
     }
+    Ok(policy)
 }
 
 // Helper: Make progress bar
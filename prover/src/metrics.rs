@@ -0,0 +1,451 @@
+// Prometheus metrics for the prover and morph events
+//
+// Everything that proves or morphs records into one process-wide registry,
+// and `serve` exposes it in the Prometheus text format on /metrics for the
+// scrape job in monitoring/prometheus.yml. Names are unprefixed to match
+// monitoring/alerts.yml, which alerts on `threat_score`,
+// `morph_failures_total` and `gas_cost_wei`.
+//
+// `gas_cost_wei` is what verifying one proof on the current backend costs:
+// the backend's typical verification gas times the configured gas price.
+
+use crate::http;
+use crate::latency::Operation;
+use crate::optimizer::Measurement;
+use crate::simulator::ThreatLevel;
+use crate::types::{BackendType, MorphTrigger};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+const BACKENDS: [BackendType; 2] = [BackendType::BN254, BackendType::BLS12_381];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Address `serve` exposes /metrics on, the scrape target in
+    /// monitoring/prometheus.yml by default; off when null
+    #[serde(default = "default_metrics_listen")]
+    pub listen: Option<String>,
+    /// Gas price `gas_cost_wei` is computed at
+    #[serde(default = "default_gas_price_gwei")]
+    pub gas_price_gwei: f64,
+}
+
+fn default_metrics_listen() -> Option<String> {
+    Some("0.0.0.0:9090".to_string())
+}
+
+fn default_gas_price_gwei() -> f64 {
+    1.0
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_metrics_listen(),
+            gas_price_gwei: default_gas_price_gwei(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Per bucket, not cumulative; the last is +Inf
+    counts: [u64; BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    operations: BTreeMap<(&'static str, Operation), Histogram>,
+    proofs_generated: BTreeMap<&'static str, u64>,
+    proofs_failed: BTreeMap<&'static str, u64>,
+    current_backend: Option<BackendType>,
+    morphs: BTreeMap<&'static str, u64>,
+    morph_failures: BTreeMap<&'static str, u64>,
+    threat: Option<ThreatLevel>,
+    /// Last setup time per backend, and whether it was a warm-up
+    key_load: BTreeMap<&'static str, (Duration, bool)>,
+    gas_price_wei: f64,
+}
+
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            registry: Mutex::new(Registry {
+                gas_price_wei: default_gas_price_gwei() * 1e9,
+                ..Registry::default()
+            }),
+        }
+    }
+
+    pub fn observe_operation(&self, backend: BackendType, operation: Operation, took: Duration) {
+        let mut registry = self.registry();
+        registry
+            .operations
+            .entry((backend.name(), operation))
+            .or_default()
+            .observe(took.as_secs_f64());
+    }
+
+    pub fn record_proof(&self, backend: BackendType, ok: bool) {
+        let mut registry = self.registry();
+        let counter = if ok {
+            &mut registry.proofs_generated
+        } else {
+            &mut registry.proofs_failed
+        };
+        *counter.entry(backend.name()).or_default() += 1;
+    }
+
+    pub fn set_current_backend(&self, backend: BackendType) {
+        self.registry().current_backend = Some(backend);
+    }
+
+    /// Count a morph attempt; `backend` is where it left us
+    pub fn record_morph(&self, trigger: MorphTrigger, ok: bool, backend: BackendType) {
+        let mut registry = self.registry();
+        let counter = if ok {
+            &mut registry.morphs
+        } else {
+            &mut registry.morph_failures
+        };
+        *counter.entry(trigger.name()).or_default() += 1;
+        registry.current_backend = Some(backend);
+    }
+
+    pub fn set_threat(&self, threat: &ThreatLevel) {
+        self.registry().threat = Some(threat.clone());
+    }
+
+    pub fn record_key_load(&self, backend: BackendType, took: Duration, prewarmed: bool) {
+        self.registry()
+            .key_load
+            .insert(backend.name(), (took, prewarmed));
+    }
+
+    pub fn set_gas_price_gwei(&self, gwei: f64) {
+        self.registry().gas_price_wei = gwei * 1e9;
+    }
+
+    /// Prometheus text exposition format, version 0.0.4
+    pub fn render(&self) -> String {
+        let registry = self.registry();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "prover_operation_seconds",
+            "histogram",
+            "Setup, prove and verify time per backend",
+        );
+        for ((backend, operation), histogram) in &registry.operations {
+            let labels = format!("backend=\"{}\",operation=\"{}\"", backend, operation.name());
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(&histogram.counts) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "prover_operation_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "prover_operation_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "prover_operation_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "prover_operation_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+
+        for (name, help, counter) in [
+            (
+                "proofs_generated_total",
+                "Proofs generated per backend",
+                &registry.proofs_generated,
+            ),
+            (
+                "proofs_failed_total",
+                "Proofs that failed per backend",
+                &registry.proofs_failed,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            for backend in BACKENDS {
+                let count = counter.get(backend.name()).copied().unwrap_or(0);
+                let _ = writeln!(out, "{}{{backend=\"{}\"}} {}", name, backend.name(), count);
+            }
+        }
+
+        if let Some(current) = registry.current_backend {
+            header(
+                &mut out,
+                "current_backend",
+                "gauge",
+                "1 for the backend proofs are made on",
+            );
+            for backend in BACKENDS {
+                let _ = writeln!(
+                    out,
+                    "current_backend{{backend=\"{}\"}} {}",
+                    backend.name(),
+                    u8::from(backend == current)
+                );
+            }
+        }
+
+        for (name, help, counter) in [
+            (
+                "morphs_total",
+                "Committed morphs by trigger",
+                &registry.morphs,
+            ),
+            (
+                "morph_failures_total",
+                "Morphs that failed and rolled back, by trigger",
+                &registry.morph_failures,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            for trigger in [
+                MorphTrigger::Manual,
+                MorphTrigger::ThreatPolicy,
+                MorphTrigger::Emergency,
+                MorphTrigger::Rollback,
+            ] {
+                let count = counter.get(trigger.name()).copied().unwrap_or(0);
+                let _ = writeln!(out, "{}{{trigger=\"{}\"}} {}", name, trigger.name(), count);
+            }
+        }
+
+        if let Some(threat) = &registry.threat {
+            header(&mut out, "threat_score", "gauge", "Overall threat score");
+            let _ = writeln!(out, "threat_score {}", threat.overall);
+            header(
+                &mut out,
+                "threat_category_score",
+                "gauge",
+                "Threat score per category",
+            );
+            let mut categories = vec![
+                ("quantum", threat.quantum),
+                ("regulatory", threat.regulatory),
+            ];
+            categories.extend(
+                threat
+                    .other
+                    .iter()
+                    .map(|(name, score)| (name.as_str(), *score)),
+            );
+            for (category, score) in categories {
+                let _ = writeln!(
+                    out,
+                    "threat_category_score{{category=\"{}\"}} {}",
+                    category, score
+                );
+            }
+        }
+
+        if !registry.key_load.is_empty() {
+            header(
+                &mut out,
+                "key_load_seconds",
+                "gauge",
+                "Time the last key setup per backend took",
+            );
+            for (backend, (took, prewarmed)) in &registry.key_load {
+                let _ = writeln!(
+                    out,
+                    "key_load_seconds{{backend=\"{}\",source=\"{}\"}} {}",
+                    backend,
+                    if *prewarmed { "prewarm" } else { "on-demand" },
+                    took.as_secs_f64()
+                );
+            }
+        }
+
+        if let Some(current) = registry.current_backend {
+            header(
+                &mut out,
+                "gas_cost_wei",
+                "gauge",
+                "Cost of verifying one proof on the current backend",
+            );
+            let gas = Measurement::typical(current).verify_gas as f64;
+            let _ = writeln!(out, "gas_cost_wei {}", gas * registry.gas_price_wei);
+        }
+
+        out
+    }
+
+    /// Answer GET /metrics; anything else is not found
+    pub fn respond(&self, request: &http::Request) -> http::Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/metrics") => {
+                http::Response::text(200, "text/plain; version=0.0.4", self.render())
+            }
+            _ => http::Response::not_found(),
+        }
+    }
+
+    fn registry(&self) -> MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// The registry the prover records into
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Serve `metrics` on GET /metrics at `addr` from a background thread
+pub fn serve(
+    addr: &str,
+    metrics: &'static Metrics,
+) -> Result<(SocketAddr, JoinHandle<()>), String> {
+    let listener =
+        TcpListener::bind(addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+    let local = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+    let handle = thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(e) = respond(stream, metrics) {
+                eprintln!("[WARN] metrics request failed: {}", e);
+            }
+        }
+    });
+    Ok((local, handle))
+}
+
+fn respond(stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    let response = match http::read_request(&stream) {
+        Ok(request) => metrics.respond(&request),
+        Err(e) => http::Response::error(400, &e),
+    };
+    http::write_response(&stream, &response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threat::ThreatModel;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_render_matches_alert_rules() {
+        let metrics = Metrics::new();
+        metrics.observe_operation(
+            BackendType::BN254,
+            Operation::Prove,
+            Duration::from_millis(40),
+        );
+        metrics.record_proof(BackendType::BN254, true);
+        metrics.record_morph(MorphTrigger::Emergency, false, BackendType::BN254);
+        metrics.set_threat(&ThreatModel::default().level(&[("quantum", 90), ("regulatory", 10)]));
+        metrics.record_key_load(BackendType::BLS12_381, Duration::from_secs(2), true);
+
+        let text = metrics.render();
+        assert!(text.contains(
+            "prover_operation_seconds_bucket{backend=\"BN254\",operation=\"prove\",le=\"0.025\"} 0"
+        ));
+        assert!(text.contains(
+            "prover_operation_seconds_bucket{backend=\"BN254\",operation=\"prove\",le=\"0.05\"} 1"
+        ));
+        assert!(text.contains("proofs_generated_total{backend=\"BN254\"} 1"));
+        assert!(text.contains("morph_failures_total{trigger=\"emergency\"} 1"));
+        assert!(text.contains("current_backend{backend=\"BLS12-381\"} 0"));
+        assert!(text.contains("threat_category_score{category=\"quantum\"} 90"));
+        assert!(text.contains("key_load_seconds{backend=\"BLS12-381\",source=\"prewarm\"} 2"));
+        // 200k gas at 1 gwei
+        assert!(text.contains("gas_cost_wei 200000000000000"));
+
+        // The names monitoring/alerts.yml queries
+        let alerts = include_str!("../../monitoring/alerts.yml");
+        for name in ["threat_score", "morph_failures_total", "gas_cost_wei"] {
+            assert!(alerts.contains(&format!("expr: {} ", name)));
+            assert!(
+                text.contains(&format!("\n{} ", name)) || text.contains(&format!("\n{}{{", name))
+            );
+        }
+
+        // HighGasCost is in the same units and stays quiet at normal prices
+        let threshold: f64 = alerts
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("expr: gas_cost_wei > "))
+            .unwrap()
+            .parse()
+            .unwrap();
+        let bls_verify_gas = Measurement::typical(BackendType::BLS12_381).verify_gas as f64;
+        assert!(bls_verify_gas * 1e9 < threshold);
+    }
+
+    #[test]
+    fn test_serves_metrics_endpoint() {
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        let metrics = METRICS.get_or_init(Metrics::new);
+        let threat = ThreatModel::default().level(&[("quantum", 80), ("regulatory", 80)]);
+        metrics.set_threat(&threat);
+        let (addr, _) = serve("127.0.0.1:0", metrics).unwrap();
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: test\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains(&format!("\nthreat_score {}\n", threat.overall)));
+        assert!(get("/other").starts_with("HTTP/1.1 404"));
+    }
+}
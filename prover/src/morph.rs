@@ -1,52 +1,554 @@
 // morph.rs
-use crate::bls12_381_backend::BLS12_381Backend;
-use crate::bn254_backend::BN254Backend;
-#[allow(dead_code)]
-use crate::types::{BackendType, MorphResult, UniversalProof};
-use std::time::Instant;
+use crate::audit::{AuditEntry, AuditLog, GENESIS_HASH};
+use crate::config::{Config, MorphConfig};
+use crate::error::ChameleonError;
+use crate::flapping::{FlapAlert, FlapDetector};
+use crate::hedge::{DualProof, DualVerification, HedgeRequirement};
+use crate::journal::{JournalRecord, MorphJournal, MorphPhase};
+use crate::jurisdiction::JurisdictionRegistry;
+use crate::latency::LatencyTracker;
+use crate::metrics;
+use crate::pool::BackendPool;
+use crate::prewarm::{Projection, TrendProjector};
+use crate::simulator::{MorphDecision, ThreatLevel};
+use crate::threat::ThreatModel;
+use crate::types::{BackendType, CanaryReport, MorphResult, MorphTrigger, UniversalProof};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, MutexGuard};
+use std::time::{Duration, Instant};
 
 // ... rest of the code
 
+/// Statement the canary proves on both the current and target backend
+const CANARY_STATEMENT: (u64, u64) = (5, 11);
+
+/// Who asked for a morph and why, recorded in the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MorphContext {
+    pub trigger: MorphTrigger,
+    pub operator: String,
+    pub reason: String,
+    pub threat: Option<ThreatLevel>,
+    pub commitment_proof_ids: Vec<String>,
+    #[serde(default)]
+    pub rollback_of: Option<u64>,
+    /// Set when the morph starts; the journal id of the morph
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub morph_id: Option<u64>,
+    /// Digest of the state the morph carries over, proven on both backends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_commitment: Option<String>,
+}
+
+impl MorphContext {
+    pub fn manual(operator: &str, reason: &str) -> Self {
+        Self {
+            trigger: MorphTrigger::Manual,
+            operator: operator.to_string(),
+            reason: reason.to_string(),
+            threat: None,
+            commitment_proof_ids: Vec::new(),
+            rollback_of: None,
+            morph_id: None,
+            state_commitment: None,
+        }
+    }
+
+    pub fn threat_policy(threat: &ThreatLevel, decision: &MorphDecision) -> Self {
+        Self {
+            trigger: MorphTrigger::ThreatPolicy,
+            operator: "threat-simulator".to_string(),
+            reason: match &decision.rule {
+                Some(rule) => format!("[{}] {}", rule, decision.reason),
+                None => decision.reason.clone(),
+            },
+            threat: Some(threat.clone()),
+            commitment_proof_ids: Vec::new(),
+            rollback_of: None,
+            morph_id: None,
+            state_commitment: None,
+        }
+    }
+
+    pub fn emergency(operator: &str, reason: &str) -> Self {
+        Self {
+            trigger: MorphTrigger::Emergency,
+            ..Self::manual(operator, reason)
+        }
+    }
+
+    /// Undo of the morph recorded at audit index `rollback_of`
+    pub fn rollback(operator: &str, reason: &str, rollback_of: Option<u64>) -> Self {
+        Self {
+            trigger: MorphTrigger::Rollback,
+            rollback_of,
+            ..Self::manual(operator, reason)
+        }
+    }
+
+    /// Auto-morph freezing on `alert`; recorded, but not a morph
+    pub fn freeze(alert: &FlapAlert) -> Self {
+        Self {
+            trigger: MorphTrigger::Freeze,
+            ..Self::manual("flap-detector", &alert.kind.to_string())
+        }
+    }
+
+    /// `operator` lifting the freeze on `alert`
+    pub fn resume(operator: &str, alert: &FlapAlert) -> Self {
+        Self {
+            trigger: MorphTrigger::Resume,
+            ..Self::manual(operator, &format!("frozen on: {}", alert.kind))
+        }
+    }
+
+    /// Attach the ids of the state commitment proofs taken around the morph
+    pub fn with_commitment_proofs(mut self, ids: Vec<String>) -> Self {
+        self.commitment_proof_ids = ids;
+        self
+    }
+}
+
+/// Record of a morph that was allowed to skip the cooldown
+#[derive(Debug, Clone)]
+pub struct EmergencyOverride {
+    pub timestamp: String,
+    pub old_backend: BackendType,
+    pub new_backend: BackendType,
+    pub reason: String,
+}
+
+/// The last committed morph, kept so it can be undone within the grace period
+#[derive(Debug, Clone)]
+struct RollbackWindow {
+    audit_index: Option<u64>,
+    morph_id: Option<u64>,
+    old_backend: BackendType,
+    new_backend: BackendType,
+    committed_at: Instant,
+    confirmed: bool,
+}
+
 pub struct MorphController {
     current_backend: BackendType,
-    bn254: Option<BN254Backend>,
-    bls12_381: Option<BLS12_381Backend>,
+    /// Backend keys and latencies, shared with anything proving
+    /// concurrently
+    pool: Arc<BackendPool>,
+    /// Starts warm-ups when the threat trend points at a morph, when set
+    projector: Option<TrendProjector>,
+    cooldown: Duration,
+    last_morph: Option<Instant>,
+    emergency_overrides: Vec<EmergencyOverride>,
+    audit_log: Option<AuditLog>,
+    journal: Option<MorphJournal>,
+    phase: MorphPhase,
+    in_flight: Option<(u64, BackendType, BackendType)>,
+    rollback_grace: Duration,
+    rollback_window: Option<RollbackWindow>,
+    /// Proofs generated on the current backend since the last morph
+    proofs_since_morph: AtomicU64,
+    /// Latency budget for the pre-morph canary, when enabled
+    canary: Option<Duration>,
+    /// Backends must be approved by the active jurisdiction, when set
+    registry: Option<JurisdictionRegistry>,
+    /// Freezes threat-policy morphs on flapping or threshold hugging, when set
+    flap: Option<FlapDetector>,
+    /// Clock the flapping detector runs on
+    started: Instant,
+    /// Halves of a hedged proof `verify_hedged` insists on
+    hedge: HedgeRequirement,
 }
 #[allow(dead_code)]
 impl MorphController {
     pub fn new(initial_backend: BackendType) -> Self {
+        metrics::global().set_current_backend(initial_backend);
         Self {
             current_backend: initial_backend,
-            bn254: None,
-            bls12_381: None,
+            pool: Arc::new(BackendPool::new()),
+            projector: None,
+            cooldown: Duration::ZERO,
+            last_morph: None,
+            emergency_overrides: Vec::new(),
+            audit_log: None,
+            journal: None,
+            phase: MorphPhase::Idle,
+            in_flight: None,
+            rollback_grace: Config::default().morph.rollback_grace(),
+            rollback_window: None,
+            proofs_since_morph: AtomicU64::new(0),
+            canary: None,
+            registry: None,
+            flap: None,
+            started: Instant::now(),
+            hedge: HedgeRequirement::Both,
+        }
+    }
+
+    /// Controller that enforces the configured morph cooldown, watching
+    /// the thresholds of `model` for flapping and pre-warm
+    pub fn from_config(
+        initial_backend: BackendType,
+        config: &MorphConfig,
+        model: &ThreatModel,
+    ) -> Self {
+        let mut controller = Self::new(initial_backend);
+        controller.cooldown = config.cooldown();
+        controller.rollback_grace = config.rollback_grace();
+        controller.hedge = config.hedge_requirement().unwrap_or(HedgeRequirement::Both);
+        if config.canary_enabled {
+            controller.canary = Some(config.canary_latency_budget());
+        }
+        if config.flap_detection.enabled {
+            controller.flap = Some(FlapDetector::new(
+                config.flap_detection.clone(),
+                model,
+                config.threat_threshold,
+                config.recovery_threshold,
+            ));
         }
+        if config.prewarm.enabled {
+            controller.projector = Some(TrendProjector::new(
+                config.prewarm.clone(),
+                model,
+                config.threat_threshold,
+                config.recovery_threshold,
+            ));
+        }
+        controller
     }
 
+    /// Set up every backend now rather than on first use
     pub fn initialize(&mut self) -> Result<(), String> {
-        let mut bn254 = BN254Backend::new();
-        bn254.setup()?;
-        self.bn254 = Some(bn254);
+        for backend in [BackendType::BN254, BackendType::BLS12_381] {
+            self.setup(backend)?;
+        }
+        Ok(())
+    }
+
+    /// Set up `backend` unless it has keys already, waiting for a warm-up
+    /// in progress rather than starting another
+    pub fn setup(&self, backend: BackendType) -> Result<(), String> {
+        self.pool.setup(backend)
+    }
 
-        let mut bls = BLS12_381Backend::new();
-        bls.setup()?;
-        self.bls12_381 = Some(bls);
+    /// Start setting up `backend` on a background thread. Returns false if
+    /// it has keys or a warm-up is already running.
+    pub fn prewarm(&self, backend: BackendType) -> bool {
+        self.pool.prewarm(backend)
+    }
 
-        Ok(())
+    pub fn is_warming(&self, backend: BackendType) -> bool {
+        self.pool.is_warming(backend)
+    }
+
+    /// Whether `backend` has keys loaded; does not wait for warm-ups
+    pub fn has_keys(&self, backend: BackendType) -> bool {
+        self.pool.has_keys(backend)
+    }
+
+    /// The keys this controller proves with, for proving without it
+    pub fn pool(&self) -> &Arc<BackendPool> {
+        &self.pool
+    }
+
+    /// Warm up backends when the threat trend projects a crossing within
+    /// `projector`'s horizon. `None` turns this off.
+    pub fn set_projector(&mut self, projector: Option<TrendProjector>) {
+        self.projector = projector;
+    }
+
+    /// Feed a reading to the trend projector; if it projects a morph, start
+    /// warming up the target and return the projection
+    pub fn anticipate(&mut self, threat: &ThreatLevel) -> Option<Projection> {
+        let projection = self
+            .projector
+            .as_mut()?
+            .observe(threat, self.current_backend)?;
+        self.prewarm(projection.target).then_some(projection)
+    }
+
+    /// Rolling latencies observed so far, canary and state proofs included
+    pub fn latency(&self) -> MutexGuard<'_, LatencyTracker> {
+        self.pool.latency()
+    }
+
+    /// Keep the latest `window` samples per backend and operation
+    pub fn set_latency_window(&mut self, window: usize) {
+        self.pool.set_latency_window(window);
     }
 
     pub fn current_backend(&self) -> BackendType {
         self.current_backend
     }
 
+    /// Time left before another non-emergency morph is allowed
+    pub fn cooldown_remaining(&self) -> Option<Duration> {
+        let elapsed = self.last_morph?.elapsed();
+        if elapsed >= self.cooldown {
+            return None;
+        }
+        Some(self.cooldown - elapsed)
+    }
+
+    /// Restore the cooldown clock from a morph that happened `elapsed` ago,
+    /// e.g. in a previous process
+    pub fn resume_cooldown(&mut self, elapsed: Duration) {
+        self.last_morph = Instant::now().checked_sub(elapsed);
+    }
+
+    pub fn emergency_overrides(&self) -> &[EmergencyOverride] {
+        &self.emergency_overrides
+    }
+
+    /// Record every successful morph in `log`
+    pub fn set_audit_log(&mut self, log: AuditLog) {
+        self.audit_log = Some(log);
+    }
+
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit_log.as_ref()
+    }
+
+    /// Refuse to morph to, or prove on, a backend the registry's active
+    /// jurisdiction does not approve
+    pub fn set_registry(&mut self, registry: JurisdictionRegistry) {
+        self.registry = Some(registry);
+    }
+
+    pub fn registry(&self) -> Option<&JurisdictionRegistry> {
+        self.registry.as_ref()
+    }
+
+    pub fn registry_mut(&mut self) -> Option<&mut JurisdictionRegistry> {
+        self.registry.as_mut()
+    }
+
+    /// Refuse `backend` if the registry does not approve it for
+    /// `jurisdiction` (the active one if `None`)
+    pub fn check_jurisdiction(
+        &self,
+        backend: BackendType,
+        jurisdiction: Option<&str>,
+    ) -> Result<(), String> {
+        match &self.registry {
+            Some(registry) => registry.check(backend, jurisdiction),
+            None => Ok(()),
+        }
+    }
+
+    /// Refuse threat-policy morphs while `detector` reports flapping
+    pub fn set_flap_detector(&mut self, detector: Option<FlapDetector>) {
+        self.flap = detector;
+    }
+
+    pub fn flap_detector(&self) -> Option<&FlapDetector> {
+        self.flap.as_ref()
+    }
+
+    /// Show a threat reading to the flapping detector; returns the alert if
+    /// it froze auto-morph
+    pub fn observe_threat(&mut self, threat: &ThreatLevel) -> Option<FlapAlert> {
+        let at = self.started.elapsed();
+        let alert = self.flap.as_mut()?.observe(at, threat)?;
+        self.record_freeze(&alert);
+        Some(alert)
+    }
+
+    /// Come back frozen on a freeze the audit log recorded before a restart
+    pub fn restore_freeze(&mut self, reason: &str) {
+        let at = self.started.elapsed();
+        if let Some(detector) = self.flap.as_mut() {
+            detector.restore_freeze(at, reason);
+        }
+    }
+
+    /// Note a freeze in the audit log, so a restart comes back frozen
+    pub fn record_freeze(&mut self, alert: &FlapAlert) {
+        let backend = self.current_backend;
+        if let Some(log) = self.audit_log.as_mut() {
+            if let Err(e) = log.append(backend, backend, &MorphContext::freeze(alert)) {
+                eprintln!("[WARN] Auto-morph freeze not audited: {}", e);
+            }
+        }
+    }
+
+    /// Operator confirmation that threat-policy morphs may resume, lifting
+    /// this controller's freeze and `frozen`, one a detector elsewhere (the
+    /// daemon's simulator) froze on. Refused unless the confirmation is
+    /// audited.
+    pub fn resume_auto_morph(
+        &mut self,
+        operator: &str,
+        frozen: Option<FlapAlert>,
+    ) -> Result<FlapAlert, String> {
+        let alert = self
+            .flap
+            .as_ref()
+            .and_then(|detector| detector.frozen().cloned())
+            .or(frozen)
+            .ok_or("Auto-morph is not frozen")?;
+        let backend = self.current_backend;
+        if let Some(log) = self.audit_log.as_mut() {
+            log.append(backend, backend, &MorphContext::resume(operator, &alert))?;
+        }
+        if let Some(detector) = self.flap.as_mut() {
+            detector.confirm();
+        }
+        eprintln!(
+            "[ALERT] {} auto-morph resumed by {} (frozen on: {})",
+            chrono::Utc::now().to_rfc3339(),
+            operator,
+            alert.kind
+        );
+        Ok(alert)
+    }
+
     pub fn morph(&mut self, target_backend: BackendType) -> Result<MorphResult, String> {
-        let start = Instant::now();
+        self.morph_with(target_backend, MorphContext::manual("local", ""))
+    }
+
+    /// Morph immediately, ignoring the cooldown. The override is logged
+    /// and kept in `emergency_overrides()` so it can be reviewed later.
+    pub fn emergency_morph(
+        &mut self,
+        target_backend: BackendType,
+        reason: &str,
+    ) -> Result<MorphResult, String> {
+        self.morph_with(target_backend, MorphContext::emergency("local", reason))
+    }
 
+    /// Morph on behalf of `context`. Only `MorphTrigger::Emergency` may
+    /// skip the cooldown; nothing skips the jurisdiction check.
+    pub fn morph_with(
+        &mut self,
+        target_backend: BackendType,
+        context: MorphContext,
+    ) -> Result<MorphResult, String> {
         if target_backend == self.current_backend {
             return Err("Cannot morph to same backend".to_string());
         }
+        self.check_jurisdiction(target_backend, None)?;
+
+        if context.trigger == MorphTrigger::Emergency {
+            if context.reason.trim().is_empty() {
+                return Err("Emergency morph requires a reason".to_string());
+            }
+        } else if context.trigger == MorphTrigger::Rollback {
+            // Checked against the grace period by rollback() instead
+        } else if let Some(remaining) = self.cooldown_remaining() {
+            return Err(format!(
+                "Morph cooldown active: {}s remaining",
+                remaining.as_secs() + 1
+            ));
+        }
+
+        if context.trigger == MorphTrigger::ThreatPolicy {
+            let at = self.started.elapsed();
+            if let Some(detector) = self.flap.as_mut() {
+                let frozen = match detector.frozen() {
+                    Some(alert) => Some(alert.clone()),
+                    None => {
+                        let froze = detector.record_request(at, target_backend);
+                        if let Some(alert) = &froze {
+                            self.record_freeze(alert);
+                        }
+                        froze
+                    }
+                };
+                if let Some(alert) = frozen {
+                    return Err(format!(
+                        "Auto-morph frozen: {}; manual confirmation required",
+                        alert.kind
+                    ));
+                }
+            }
+        }
 
+        let result = self.switch_backend(target_backend, &context);
+        metrics::global().record_morph(context.trigger, result.is_ok(), self.current_backend);
+        result
+    }
+
+    fn switch_backend(
+        &mut self,
+        target_backend: BackendType,
+        context: &MorphContext,
+    ) -> Result<MorphResult, String> {
+        let start = Instant::now();
         let old_backend = self.current_backend;
-        self.current_backend = target_backend;
+        let mut context = context.clone();
+
+        self.begin_morph(old_backend, target_backend, &mut context)?;
+
+        // Rolling back returns to a backend we were just proving on, so it
+        // skips the canary
+        let canary = match self.canary {
+            Some(budget) if context.trigger != MorphTrigger::Rollback => {
+                match self.run_canary(old_backend, target_backend, budget) {
+                    Ok(report) => Some(report),
+                    Err(e) => {
+                        let reason = format!("canary: {}", e);
+                        self.transition(MorphPhase::RolledBack, &reason).ok();
+                        return Err(ChameleonError::MorphFailed { reason }.into());
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let detail = match self.run_phases(old_backend, target_backend, &mut context) {
+            Ok(detail) => detail,
+            Err(reason) => {
+                self.current_backend = old_backend;
+                // Best effort: if even this write fails, recover() rolls back on next start
+                self.transition(MorphPhase::RolledBack, &reason).ok();
+                return Err(ChameleonError::MorphFailed { reason }.into());
+            }
+        };
+
+        if context.trigger == MorphTrigger::Emergency && self.cooldown_remaining().is_some() {
+            let record = EmergencyOverride {
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                old_backend,
+                new_backend: target_backend,
+                reason: context.reason.clone(),
+            };
+            eprintln!(
+                "[EMERGENCY] {} morph {} -> {} bypassing cooldown: {}",
+                record.timestamp,
+                record.old_backend.name(),
+                record.new_backend.name(),
+                record.reason
+            );
+            self.emergency_overrides.push(record);
+        }
+
+        self.last_morph = Some(Instant::now());
+        // The switch and its audit entry already happened; a journal that
+        // still reads ProvingNewState is finished by recover() on next start
+        if let Err(e) = self.transition(MorphPhase::Committed, &detail) {
+            eprintln!("[WARN] Morph committed but not journaled: {}", e);
+        }
+
+        self.proofs_since_morph.store(0, Ordering::Relaxed);
+        self.rollback_window = if context.trigger == MorphTrigger::Rollback {
+            None
+        } else {
+            Some(RollbackWindow {
+                audit_index: self
+                    .audit_log
+                    .as_ref()
+                    .and_then(|l| l.last())
+                    .map(|e| e.index),
+                morph_id: context.morph_id,
+                old_backend,
+                new_backend: target_backend,
+                committed_at: Instant::now(),
+                confirmed: false,
+            })
+        };
 
         let duration = start.elapsed().as_millis();
 
@@ -55,43 +557,508 @@ impl MorphController {
             old_backend,
             new_backend: target_backend,
             duration_ms: duration,
+            canary,
         })
     }
 
-    #[allow(dead_code)]
-    pub fn prove(&self, a: u64, b: u64) -> Result<UniversalProof, String> {
-        match self.current_backend {
-            BackendType::BN254 => {
-                let backend = self.bn254.as_ref().ok_or("BN254 not initialized")?;
-                backend.prove(a, b)
+    /// Prove a statement on `target`, verify it, check the public outputs
+    /// match the same statement proven on `current`, and hold the target to
+    /// the latency budget. Nothing is switched here.
+    fn run_canary(
+        &self,
+        current: BackendType,
+        target: BackendType,
+        budget: Duration,
+    ) -> Result<CanaryReport, ChameleonError> {
+        if !self.is_initialized(target) {
+            return Err(ChameleonError::SetupNotPerformed {
+                backend: target.name().to_string(),
+            });
+        }
+
+        let (a, b) = CANARY_STATEMENT;
+        let start = Instant::now();
+        let target_proof = self
+            .prove_on(target, a, b)
+            .map_err(|reason| ChameleonError::ProvingFailed { reason })?;
+        let prove_ms = start.elapsed().as_millis();
+
+        let verify_start = Instant::now();
+        let valid = self
+            .verify(&target_proof)
+            .map_err(|reason| ChameleonError::VerificationFailed { reason })?;
+        if !valid {
+            return Err(ChameleonError::VerificationFailed {
+                reason: format!("{} canary proof rejected", target.name()),
+            });
+        }
+        let verify_ms = verify_start.elapsed().as_millis();
+
+        let reference = self
+            .prove_on(current, a, b)
+            .map_err(|reason| ChameleonError::ProvingFailed { reason })?;
+        if reference.public_inputs != target_proof.public_inputs {
+            return Err(ChameleonError::PublicInputMismatch {
+                expected: reference.public_inputs.join(","),
+                got: target_proof.public_inputs.join(","),
+            });
+        }
+
+        let elapsed = start.elapsed();
+        if prove_ms + verify_ms > budget.as_millis() {
+            return Err(ChameleonError::CanaryOverBudget {
+                backend: target.name().to_string(),
+                took_ms: prove_ms + verify_ms,
+                budget_ms: budget.as_millis(),
+            });
+        }
+
+        Ok(CanaryReport {
+            proof_id: target_proof.id(),
+            prove_ms,
+            verify_ms,
+            total_ms: elapsed.as_millis(),
+        })
+    }
+
+    /// Run a canary before every morph, failing it if the target backend
+    /// takes longer than `latency_budget` to prove and verify. `None` turns
+    /// the canary off.
+    pub fn set_canary(&mut self, latency_budget: Option<Duration>) {
+        self.canary = latency_budget;
+    }
+
+    /// ProvingOldState → Switching → ProvingNewState, then seal the audit entry
+    fn run_phases(
+        &mut self,
+        old_backend: BackendType,
+        target_backend: BackendType,
+        context: &mut MorphContext,
+    ) -> Result<String, String> {
+        let commitment = context
+            .state_commitment
+            .clone()
+            .ok_or("Morph has no state commitment")?;
+
+        self.transition(MorphPhase::ProvingOldState, "")?;
+        let old_proof = self.prove_state(old_backend, &commitment)?;
+
+        self.transition(MorphPhase::Switching, &proof_detail(&old_proof))?;
+        self.current_backend = target_backend;
+
+        self.transition(MorphPhase::ProvingNewState, "")?;
+        let new_proof = self.prove_state(target_backend, &commitment)?;
+
+        if let (Some(old), Some(new)) = (&old_proof, &new_proof) {
+            if old.public_inputs != new.public_inputs {
+                return Err(format!(
+                    "State commitment mismatch: {:?} before, {:?} after",
+                    old.public_inputs, new.public_inputs
+                ));
             }
-            BackendType::BLS12_381 => {
-                let backend = self.bls12_381.as_ref().ok_or("BLS12-381 not initialized")?;
-                backend.prove(a, b)
+        }
+        context
+            .commitment_proof_ids
+            .extend(old_proof.iter().chain(new_proof.iter()).map(|p| p.id()));
+
+        // A morph we cannot record does not happen
+        if let Some(log) = self.audit_log.as_mut() {
+            log.append(old_backend, target_backend, context)?;
+        }
+
+        Ok(proof_detail(&new_proof))
+    }
+
+    /// Prove and verify `commitment` on `backend`. Returns `None` when
+    /// that backend has no keys loaded, so there is nothing to prove with.
+    fn prove_state(
+        &self,
+        backend: BackendType,
+        commitment: &str,
+    ) -> Result<Option<UniversalProof>, String> {
+        if !self.is_initialized(backend) {
+            return Ok(None);
+        }
+
+        let (a, b) = state_statement(commitment)?;
+        let proof = self.prove_on(backend, a, b)?;
+        if !self.verify(&proof)? {
+            return Err(format!("{} state proof did not verify", backend.name()));
+        }
+        if proof.public_inputs != [(a * b).to_string()] {
+            return Err(format!(
+                "{} state proof is not over commitment {}",
+                backend.name(),
+                commitment
+            ));
+        }
+        Ok(Some(proof))
+    }
+
+    /// Digest over the audit chain head the morph starts from and the morph
+    /// itself. Both backends prove it, so the new backend's proof is tied to
+    /// the state carried over rather than to a fixed statement.
+    fn state_commitment(
+        &self,
+        morph_id: u64,
+        old_backend: BackendType,
+        new_backend: BackendType,
+    ) -> String {
+        let head = self
+            .audit_log
+            .as_ref()
+            .and_then(|log| log.last())
+            .map_or(GENESIS_HASH, |entry| entry.hash.as_str());
+        let state = format!(
+            "{}:{}:{}:{}",
+            head,
+            morph_id,
+            old_backend.name(),
+            new_backend.name()
+        );
+        hex::encode(Sha256::digest(state.as_bytes()))
+    }
+
+    /// Whether `backend` has keys, once any warm-up in progress finishes
+    fn is_initialized(&self, backend: BackendType) -> bool {
+        self.pool.ready(backend)
+    }
+
+    /// Undo the last morph, if it is still inside the grace period and the
+    /// operator has not confirmed new-backend proofs as committed.
+    pub fn rollback(&mut self, operator: &str, reason: &str) -> Result<MorphResult, String> {
+        let window = self
+            .rollback_window
+            .clone()
+            .ok_or("No morph to roll back")?;
+
+        if window.committed_at.elapsed() >= self.rollback_grace {
+            return Err(format!(
+                "Rollback grace period of {}s has expired",
+                self.rollback_grace.as_secs()
+            ));
+        }
+        if window.confirmed {
+            return Err(format!(
+                "Proofs on {} were confirmed committed; rollback refused",
+                window.new_backend.name()
+            ));
+        }
+        if self.current_backend != window.new_backend {
+            return Err(format!(
+                "Current backend {} is not the one the last morph switched to",
+                self.current_backend.name()
+            ));
+        }
+
+        let context = MorphContext::rollback(operator, reason, window.audit_index);
+        self.morph_with(window.old_backend, context)
+    }
+
+    /// Time left in which `rollback()` is allowed
+    pub fn rollback_remaining(&self) -> Option<Duration> {
+        let window = self.rollback_window.as_ref()?;
+        if window.confirmed {
+            return None;
+        }
+        self.rollback_grace
+            .checked_sub(window.committed_at.elapsed())
+    }
+
+    /// Operator confirms that proofs made on the new backend so far are
+    /// committed downstream (e.g. on-chain); from now on `rollback()` is
+    /// refused. The confirmation is journaled so it outlives the process.
+    /// Returns how many proofs made in this process that covers.
+    pub fn confirm_commit_point(&mut self, operator: &str) -> Result<u64, String> {
+        let window = self
+            .rollback_window
+            .as_ref()
+            .ok_or("No morph awaiting confirmation")?;
+        let proofs = self.proofs_since_morph.load(Ordering::Relaxed);
+        if window.confirmed {
+            return Ok(proofs);
+        }
+
+        if let (Some(journal), Some(morph_id)) = (self.journal.as_mut(), window.morph_id) {
+            journal.record(JournalRecord {
+                morph_id,
+                phase: MorphPhase::Confirmed,
+                old_backend: window.old_backend,
+                new_backend: window.new_backend,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                detail: format!("confirmed by {}", operator),
+                context: None,
+            })?;
+        }
+        if self.phase.can_transition_to(MorphPhase::Confirmed) {
+            self.phase = MorphPhase::Confirmed;
+        }
+        if let Some(window) = self.rollback_window.as_mut() {
+            window.confirmed = true;
+        }
+        Ok(proofs)
+    }
+
+    /// Reopen the rollback window for a morph recorded in a previous
+    /// process, closed if the journal has its confirmation
+    pub fn resume_rollback_window(&mut self, entry: &AuditEntry, elapsed: Duration) {
+        if entry.trigger == MorphTrigger::Rollback || entry.new_backend != self.current_backend {
+            return;
+        }
+        let confirmed = entry.morph_id.is_some_and(|morph_id| {
+            self.journal.as_ref().is_some_and(|journal| {
+                journal
+                    .records()
+                    .iter()
+                    .any(|r| r.morph_id == morph_id && r.phase == MorphPhase::Confirmed)
+            })
+        });
+        if let Some(committed_at) = Instant::now().checked_sub(elapsed) {
+            self.rollback_window = Some(RollbackWindow {
+                audit_index: Some(entry.index),
+                morph_id: entry.morph_id,
+                old_backend: entry.old_backend,
+                new_backend: entry.new_backend,
+                committed_at,
+                confirmed,
+            });
+        }
+    }
+
+    pub fn phase(&self) -> MorphPhase {
+        self.phase
+    }
+
+    /// Journal every phase change of a morph in `journal`
+    pub fn set_journal(&mut self, journal: MorphJournal) {
+        self.journal = Some(journal);
+    }
+
+    pub fn journal(&self) -> Option<&MorphJournal> {
+        self.journal.as_ref()
+    }
+
+    fn begin_morph(
+        &mut self,
+        old_backend: BackendType,
+        target_backend: BackendType,
+        context: &mut MorphContext,
+    ) -> Result<(), String> {
+        if !self.phase.can_transition_to(MorphPhase::Preparing) {
+            return Err(format!("Morph already in progress ({})", self.phase.name()));
+        }
+
+        context.morph_id = self.journal.as_ref().map(|j| j.next_morph_id());
+        let morph_id = context.morph_id.unwrap_or(0);
+        context.state_commitment =
+            Some(self.state_commitment(morph_id, old_backend, target_backend));
+        self.in_flight = Some((morph_id, old_backend, target_backend));
+        self.write_phase(MorphPhase::Preparing, "", Some(context.clone()))
+    }
+
+    fn transition(&mut self, next: MorphPhase, detail: &str) -> Result<(), String> {
+        if !self.phase.can_transition_to(next) {
+            return Err(format!(
+                "Invalid morph transition {} -> {}",
+                self.phase.name(),
+                next.name()
+            ));
+        }
+        self.write_phase(next, detail, None)
+    }
+
+    fn write_phase(
+        &mut self,
+        phase: MorphPhase,
+        detail: &str,
+        context: Option<MorphContext>,
+    ) -> Result<(), String> {
+        let (morph_id, old_backend, new_backend) = self.in_flight.ok_or("No morph in progress")?;
+
+        let written = match self.journal.as_mut() {
+            Some(journal) => journal.record(JournalRecord {
+                morph_id,
+                phase,
+                old_backend,
+                new_backend,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                detail: detail.to_string(),
+                context,
+            }),
+            None => Ok(()),
+        };
+
+        // A morph that has ended is over in memory even if the journal write
+        // failed, so later morphs are not refused as "in progress"; the
+        // journal's last record sends it through recover() on next start
+        if written.is_ok() || phase.is_terminal() {
+            self.phase = phase;
+        }
+        if phase.is_terminal() {
+            self.in_flight = None;
+        }
+        written
+    }
+
+    /// Finish or undo a morph that a previous process left half done.
+    /// Anything before the switch is rolled back; a morph that already
+    /// switched is finished by re-proving on the new backend, and rolled
+    /// back if that fails. Returns the phase the morph ended in.
+    pub fn recover(&mut self) -> Result<Option<MorphPhase>, String> {
+        let pending = match self.journal.as_ref().and_then(|j| j.pending()) {
+            Some(records) => records.into_iter().cloned().collect::<Vec<_>>(),
+            None => return Ok(None),
+        };
+
+        let last = pending.last().expect("pending morph has records");
+        let (morph_id, old_backend, new_backend) =
+            (last.morph_id, last.old_backend, last.new_backend);
+        let mut context = pending
+            .iter()
+            .find_map(|r| r.context.clone())
+            .unwrap_or_else(|| MorphContext::manual("recovery", ""));
+
+        self.in_flight = Some((morph_id, old_backend, new_backend));
+        self.phase = last.phase;
+
+        if last.phase == MorphPhase::ProvingNewState {
+            self.current_backend = new_backend;
+            // The audit entry may have been written just before the crash
+            let recorded = self
+                .audit_log
+                .as_ref()
+                .and_then(|log| log.last())
+                .is_some_and(|e| {
+                    e.morph_id == Some(morph_id)
+                        && e.old_backend == old_backend
+                        && e.new_backend == new_backend
+                });
+            let commitment = context
+                .state_commitment
+                .clone()
+                .unwrap_or_else(|| self.state_commitment(morph_id, old_backend, new_backend));
+            let finished = self
+                .prove_state(new_backend, &commitment)
+                .and_then(|proof| {
+                    context.commitment_proof_ids.extend(proof.map(|p| p.id()));
+                    match self.audit_log.as_mut() {
+                        Some(log) if !recorded => {
+                            log.append(old_backend, new_backend, &context).map(|_| ())
+                        }
+                        _ => Ok(()),
+                    }
+                });
+
+            if finished.is_ok() {
+                self.write_phase(MorphPhase::Committed, "recovered after restart", None)?;
+                metrics::global().record_morph(context.trigger, true, new_backend);
+                return Ok(Some(MorphPhase::Committed));
             }
         }
+
+        self.current_backend = old_backend;
+        self.write_phase(
+            MorphPhase::RolledBack,
+            &format!("recovered after restart in {}", last.phase.name()),
+            None,
+        )?;
+        metrics::global().record_morph(context.trigger, false, old_backend);
+        Ok(Some(MorphPhase::RolledBack))
+    }
+
+    fn prove_on(&self, backend: BackendType, a: u64, b: u64) -> Result<UniversalProof, String> {
+        self.pool.prove(backend, a, b)
+    }
+
+    pub fn prove(&self, a: u64, b: u64) -> Result<UniversalProof, String> {
+        self.prove_for(a, b, None)
+    }
+
+    /// Prove on the current backend for use in `jurisdiction` (the
+    /// registry's active one if `None`)
+    pub fn prove_for(
+        &self,
+        a: u64,
+        b: u64,
+        jurisdiction: Option<&str>,
+    ) -> Result<UniversalProof, String> {
+        self.check_jurisdiction(self.current_backend, jurisdiction)?;
+        let proof = self.prove_on(self.current_backend, a, b)?;
+        self.note_proof(self.current_backend);
+        Ok(proof)
     }
 
     #[allow(dead_code)]
     pub fn verify(&self, proof: &UniversalProof) -> Result<bool, String> {
-        match proof.backend {
-            BackendType::BN254 => {
-                let backend = self.bn254.as_ref().ok_or("BN254 not initialized")?;
-                backend.verify(proof)
-            }
-            BackendType::BLS12_381 => {
-                let backend = self.bls12_381.as_ref().ok_or("BLS12-381 not initialized")?;
-                backend.verify(proof)
-            }
+        self.pool.verify(proof)
+    }
+
+    /// Prove the statement on both curves, for when it is unclear which
+    /// one to trust. Both backends must be approved for `jurisdiction`.
+    pub fn prove_hedged(
+        &self,
+        a: u64,
+        b: u64,
+        jurisdiction: Option<&str>,
+    ) -> Result<DualProof, String> {
+        self.check_jurisdiction(BackendType::BN254, jurisdiction)?;
+        self.check_jurisdiction(BackendType::BLS12_381, jurisdiction)?;
+        let dual = DualProof::new(
+            self.prove_on(BackendType::BN254, a, b)?,
+            self.prove_on(BackendType::BLS12_381, a, b)?,
+        )?;
+        self.note_proof(self.current_backend);
+        Ok(dual)
+    }
+
+    /// Verify the halves of `proof` the configured requirement asks for
+    pub fn verify_hedged(&self, proof: &DualProof) -> Result<DualVerification, String> {
+        proof.verify_with(self.hedge, |half| self.pool.verify(half))
+    }
+
+    pub fn hedge_requirement(&self) -> HedgeRequirement {
+        self.hedge
+    }
+
+    pub fn set_hedge_requirement(&mut self, requirement: HedgeRequirement) {
+        self.hedge = requirement;
+    }
+
+    /// Count a proof made on `backend` towards the commit point, if that is
+    /// still the current backend
+    pub fn note_proof(&self, backend: BackendType) {
+        if backend == self.current_backend {
+            self.proofs_since_morph.fetch_add(1, Ordering::Relaxed);
         }
     }
 }
 
+/// Two 32-bit words of `commitment` as the statement a * b, small enough
+/// that the product fits the backends' u64 output
+fn state_statement(commitment: &str) -> Result<(u64, u64), String> {
+    let bytes = hex::decode(commitment).map_err(|e| format!("Bad state commitment: {}", e))?;
+    if bytes.len() < 8 {
+        return Err(format!("State commitment {} is too short", commitment));
+    }
+    let word = |i: usize| u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+    Ok((word(0) as u64, word(4) as u64))
+}
+
+fn proof_detail(proof: &Option<UniversalProof>) -> String {
+    match proof {
+        Some(p) => format!("proof {} output {}", p.id(), p.public_inputs.join(",")),
+        None => "skipped: backend not initialized".to_string(),
+    }
+}
+
 // At the bottom of morph.rs
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::flapping::FlapConfig;
+    use crate::latency::Operation;
+    use crate::prewarm::PrewarmConfig;
 
     #[test]
     fn test_morph_controller_new() {
@@ -147,4 +1114,483 @@ mod tests {
             .expect("Second morph should succeed");
         assert_eq!(controller.current_backend(), BackendType::BN254);
     }
+
+    fn cooldown_config(cooldown_seconds: u32) -> MorphConfig {
+        MorphConfig {
+            auto_morph_enabled: true,
+            threat_threshold: 70,
+            recovery_threshold: 30,
+            cooldown_seconds,
+            rollback_grace_seconds: 900,
+            canary_enabled: false,
+            canary_latency_budget_ms: 2000,
+            drain_timeout_ms: 5000,
+            hedge_verify: "both".to_string(),
+            hedge_bn254_vk: None,
+            hedge_bls12_381_vk: None,
+            flap_detection: FlapConfig::default(),
+            prewarm: PrewarmConfig::default(),
+        }
+    }
+
+    #[test]
+    fn test_morph_blocked_during_cooldown() {
+        let mut controller = MorphController::from_config(
+            BackendType::BN254,
+            &cooldown_config(300),
+            &ThreatModel::default(),
+        );
+
+        controller
+            .morph(BackendType::BLS12_381)
+            .expect("First morph should succeed");
+        assert!(controller.cooldown_remaining().is_some());
+
+        let result = controller.morph(BackendType::BN254);
+        assert!(result.unwrap_err().starts_with("Morph cooldown active"));
+        assert_eq!(controller.current_backend(), BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_emergency_morph_bypasses_cooldown() {
+        let mut controller = MorphController::from_config(
+            BackendType::BLS12_381,
+            &cooldown_config(300),
+            &ThreatModel::default(),
+        );
+        controller
+            .morph(BackendType::BN254)
+            .expect("First morph should succeed");
+
+        assert!(controller
+            .emergency_morph(BackendType::BLS12_381, "")
+            .is_err());
+
+        controller
+            .emergency_morph(BackendType::BLS12_381, "quantum feed at 95")
+            .expect("Emergency morph should bypass cooldown");
+        assert_eq!(controller.current_backend(), BackendType::BLS12_381);
+        assert_eq!(controller.emergency_overrides().len(), 1);
+        assert_eq!(
+            controller.emergency_overrides()[0].reason,
+            "quantum feed at 95"
+        );
+    }
+
+    #[test]
+    fn test_morph_recorded_in_audit_log() {
+        let path = std::env::temp_dir().join(format!(
+            "chameleon-morph-audit-{}.jsonl",
+            std::process::id()
+        ));
+        std::fs::remove_file(&path).ok();
+
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.set_audit_log(AuditLog::open(path.to_str().unwrap()).unwrap());
+
+        let threat = ThreatLevel::critical(&ThreatModel::default());
+        let decision = MorphDecision {
+            should_morph: true,
+            recommended_backend: BackendType::BLS12_381,
+            reason: "critical quantum reading".to_string(),
+            emergency: true,
+            rule: None,
+        };
+        controller
+            .morph_with(
+                BackendType::BLS12_381,
+                MorphContext::threat_policy(&threat, &decision)
+                    .with_commitment_proofs(vec!["state-0001".to_string()]),
+            )
+            .expect("Morph should succeed");
+
+        let log = controller.audit_log().unwrap();
+        let entry = log.last().unwrap();
+        assert_eq!(entry.trigger, MorphTrigger::ThreatPolicy);
+        assert_eq!(entry.old_backend, BackendType::BN254);
+        assert_eq!(entry.threat.as_ref().unwrap().quantum, 95);
+        assert_eq!(entry.commitment_proof_ids, vec!["state-0001"]);
+        assert!(log.verify().is_ok());
+    }
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "chameleon-morph-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        std::fs::remove_file(&path).ok();
+        path.to_string_lossy().to_string()
+    }
+
+    fn crashed_journal(name: &str, last_phase: MorphPhase) -> MorphJournal {
+        let mut journal = MorphJournal::open(&temp_path(name)).unwrap();
+        let phases = [
+            MorphPhase::Preparing,
+            MorphPhase::ProvingOldState,
+            MorphPhase::Switching,
+            MorphPhase::ProvingNewState,
+        ];
+        for phase in phases.iter().take_while(|p| **p != last_phase) {
+            journal.record(record(*phase)).unwrap();
+        }
+        journal.record(record(last_phase)).unwrap();
+        journal
+    }
+
+    fn record(phase: MorphPhase) -> JournalRecord {
+        JournalRecord {
+            morph_id: 0,
+            phase,
+            old_backend: BackendType::BN254,
+            new_backend: BackendType::BLS12_381,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            detail: String::new(),
+            context: (phase == MorphPhase::Preparing)
+                .then(|| MorphContext::manual("alice", "before crash")),
+        }
+    }
+
+    #[test]
+    fn test_morph_journals_every_phase() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.initialize().expect("Init should succeed");
+        controller.set_journal(MorphJournal::open(&temp_path("phases")).unwrap());
+
+        controller
+            .morph(BackendType::BLS12_381)
+            .expect("Morph should succeed");
+
+        let phases: Vec<MorphPhase> = controller
+            .journal()
+            .unwrap()
+            .records()
+            .iter()
+            .map(|r| r.phase)
+            .collect();
+        assert_eq!(
+            phases,
+            vec![
+                MorphPhase::Preparing,
+                MorphPhase::ProvingOldState,
+                MorphPhase::Switching,
+                MorphPhase::ProvingNewState,
+                MorphPhase::Committed,
+            ]
+        );
+        assert_eq!(controller.phase(), MorphPhase::Committed);
+    }
+
+    #[test]
+    fn test_recover_rolls_back_before_switch() {
+        let mut controller = MorphController::new(BackendType::BLS12_381);
+        controller.set_journal(crashed_journal("rollback", MorphPhase::Switching));
+
+        assert_eq!(controller.recover().unwrap(), Some(MorphPhase::RolledBack));
+        assert_eq!(controller.current_backend(), BackendType::BN254);
+        assert!(controller.journal().unwrap().pending().is_none());
+        assert_eq!(controller.recover().unwrap(), None);
+    }
+
+    #[test]
+    fn test_recover_finishes_switched_morph() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.set_journal(crashed_journal("commit", MorphPhase::ProvingNewState));
+        controller.set_audit_log(AuditLog::open(&temp_path("commit-audit")).unwrap());
+
+        assert_eq!(controller.recover().unwrap(), Some(MorphPhase::Committed));
+        assert_eq!(controller.current_backend(), BackendType::BLS12_381);
+
+        let entry = controller.audit_log().unwrap().last().unwrap();
+        assert_eq!(entry.operator, "alice");
+        assert_eq!(entry.new_backend, BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_recover_does_not_repeat_audit_entry() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.set_journal(crashed_journal("audited", MorphPhase::ProvingNewState));
+        let mut log = AuditLog::open(&temp_path("audited-audit")).unwrap();
+        let mut context = MorphContext::manual("alice", "before crash");
+        context.morph_id = Some(0);
+        log.append(BackendType::BN254, BackendType::BLS12_381, &context)
+            .unwrap();
+        controller.set_audit_log(log);
+
+        assert_eq!(controller.recover().unwrap(), Some(MorphPhase::Committed));
+        assert_eq!(controller.audit_log().unwrap().entries().len(), 1);
+        assert_eq!(controller.phase(), MorphPhase::Committed);
+    }
+
+    #[test]
+    fn test_torn_journal_line_dropped() {
+        let path = temp_path("torn");
+        let mut journal = MorphJournal::open(&path).unwrap();
+        journal.record(record(MorphPhase::Preparing)).unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"{\"morph_id\":0,\"pha").unwrap();
+
+        let mut journal = MorphJournal::open(&path).unwrap();
+        assert_eq!(journal.records().len(), 1);
+        journal.record(record(MorphPhase::ProvingOldState)).unwrap();
+        assert_eq!(MorphJournal::open(&path).unwrap().records().len(), 2);
+    }
+
+    #[test]
+    fn test_rollback_restores_previous_backend() {
+        let mut controller = MorphController::from_config(
+            BackendType::BN254,
+            &cooldown_config(300),
+            &ThreatModel::default(),
+        );
+        controller.initialize().expect("Init should succeed");
+        controller.set_audit_log(AuditLog::open(&temp_path("rollback-audit")).unwrap());
+
+        controller
+            .morph(BackendType::BLS12_381)
+            .expect("Morph should succeed");
+        assert!(controller.rollback_remaining().is_some());
+
+        let result = controller
+            .rollback("alice", "downstream verifier rejects BLS12-381")
+            .expect("Rollback inside grace period should succeed");
+        assert_eq!(result.new_backend, BackendType::BN254);
+        assert_eq!(controller.current_backend(), BackendType::BN254);
+        assert!(controller.prove(2, 3).is_ok());
+
+        let log = controller.audit_log().unwrap();
+        let entry = log.last().unwrap();
+        assert_eq!(entry.trigger, MorphTrigger::Rollback);
+        assert_eq!(entry.rollback_of, Some(0));
+        assert!(log.verify().is_ok());
+
+        assert!(controller.rollback("alice", "again").is_err());
+    }
+
+    #[test]
+    fn test_rollback_refused_after_confirmed_commit() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.initialize().expect("Init should succeed");
+        controller
+            .morph(BackendType::BLS12_381)
+            .expect("Morph should succeed");
+        controller.prove(3, 7).expect("Prove should succeed");
+
+        assert_eq!(controller.confirm_commit_point("alice").unwrap(), 1);
+        let err = controller.rollback("alice", "too late").unwrap_err();
+        assert!(err.contains("confirmed committed"));
+        assert_eq!(controller.current_backend(), BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_confirmation_survives_restart() {
+        let (journal, audit) = (temp_path("confirm"), temp_path("confirm-audit"));
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.set_journal(MorphJournal::open(&journal).unwrap());
+        controller.set_audit_log(AuditLog::open(&audit).unwrap());
+        controller
+            .morph(BackendType::BLS12_381)
+            .expect("Morph should succeed");
+        controller.confirm_commit_point("alice").unwrap();
+
+        let mut restarted = MorphController::new(BackendType::BLS12_381);
+        restarted.set_journal(MorphJournal::open(&journal).unwrap());
+        let log = AuditLog::open(&audit).unwrap();
+        restarted.resume_rollback_window(log.last().unwrap(), Duration::from_secs(1));
+        restarted.set_audit_log(log);
+
+        let err = restarted.rollback("bob", "after restart").unwrap_err();
+        assert!(err.contains("confirmed committed"), "{}", err);
+        assert_eq!(restarted.recover().unwrap(), None);
+    }
+
+    #[test]
+    fn test_rollback_refused_after_grace_period() {
+        let mut config = cooldown_config(0);
+        config.rollback_grace_seconds = 0;
+        let mut controller =
+            MorphController::from_config(BackendType::BN254, &config, &ThreatModel::default());
+        controller
+            .morph(BackendType::BLS12_381)
+            .expect("Morph should succeed");
+
+        let err = controller.rollback("alice", "too late").unwrap_err();
+        assert!(err.contains("grace period"));
+    }
+
+    #[test]
+    fn test_canary_passes_within_budget() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.initialize().expect("Init should succeed");
+        controller.set_canary(Some(Duration::from_secs(60)));
+
+        let result = controller
+            .morph(BackendType::BLS12_381)
+            .expect("Canary morph should succeed");
+        let canary = result.canary.expect("Canary should have run");
+        assert!(canary.proof_id.starts_with("BLS12-381:"));
+        assert_eq!(controller.current_backend(), BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_canary_over_budget_aborts_morph() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.initialize().expect("Init should succeed");
+        controller.set_journal(MorphJournal::open(&temp_path("canary")).unwrap());
+        controller.set_canary(Some(Duration::ZERO));
+
+        let err = controller.morph(BackendType::BLS12_381).unwrap_err();
+        assert!(err.starts_with("Morph failed: canary: BLS12-381 prove+verify took"));
+        assert_eq!(controller.current_backend(), BackendType::BN254);
+        let last = controller.journal().unwrap().records().last().unwrap();
+        assert_eq!(last.phase, MorphPhase::RolledBack);
+    }
+
+    #[test]
+    fn test_canary_requires_target_keys() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.set_canary(Some(Duration::from_secs(60)));
+
+        let err = controller.morph(BackendType::BLS12_381).unwrap_err();
+        assert!(err.contains("Setup not performed for backend BLS12-381"));
+    }
+
+    #[test]
+    fn test_jurisdiction_blocks_unapproved_backend() {
+        let mut registry = JurisdictionRegistry::default();
+        let mut strict = registry.jurisdiction("EU").unwrap().clone();
+        strict.region_code = "EU-STRICT".to_string();
+        strict.approved_backends = vec![1];
+        registry.add_jurisdiction(strict).unwrap();
+        registry.set_active("EU-STRICT").unwrap();
+
+        let mut controller = MorphController::new(BackendType::BLS12_381);
+        controller.initialize().unwrap();
+        controller.set_registry(registry);
+
+        let err = controller
+            .emergency_morph(BackendType::BN254, "quantum all clear")
+            .unwrap_err();
+        assert!(err.contains("not approved in EU-STRICT"));
+        assert_eq!(controller.current_backend(), BackendType::BLS12_381);
+
+        assert!(controller.prove(3, 7).is_ok());
+        let err = controller.prove_for(3, 7, Some("CN")).unwrap_err();
+        assert!(err.contains("approved: SM2"));
+    }
+
+    #[test]
+    fn test_hedged_proof_needs_both_backends_approved() {
+        let mut controller = MorphController::new(BackendType::BN254);
+        let dual = controller.prove_hedged(6, 7, None).unwrap();
+        assert_eq!(dual.public_inputs, vec!["42"]);
+        assert!(controller.verify_hedged(&dual).unwrap().accepted);
+
+        controller.set_hedge_requirement(HedgeRequirement::Only(BackendType::BLS12_381));
+        let verification = controller.verify_hedged(&dual).unwrap();
+        assert_eq!(verification.bn254, None);
+        assert_eq!(verification.bls12_381, Some(true));
+
+        let mut registry = JurisdictionRegistry::default();
+        let mut strict = registry.jurisdiction("EU").unwrap().clone();
+        strict.region_code = "EU-STRICT".to_string();
+        strict.approved_backends = vec![1];
+        registry.add_jurisdiction(strict).unwrap();
+        controller.set_registry(registry);
+        let err = controller
+            .prove_hedged(6, 7, Some("EU-STRICT"))
+            .unwrap_err();
+        assert!(err.contains("not approved in EU-STRICT"));
+    }
+
+    #[test]
+    fn test_projected_crossing_prewarms_target() {
+        let mut controller = MorphController::from_config(
+            BackendType::BN254,
+            &cooldown_config(0),
+            &ThreatModel::default(),
+        );
+        // Keys are set up on first use
+        assert!(controller.prove(3, 7).is_ok());
+        assert!(controller.has_keys(BackendType::BN254));
+        assert!(!controller.has_keys(BackendType::BLS12_381));
+
+        let model = ThreatModel::default();
+        let projection = [20, 35, 50]
+            .into_iter()
+            .find_map(|quantum| controller.anticipate(&model.level(&[("quantum", quantum)])))
+            .expect("crossing should be projected");
+        assert_eq!(projection.target, BackendType::BLS12_381);
+        assert!(controller.is_warming(BackendType::BLS12_381));
+        assert!(!controller.prewarm(BackendType::BLS12_381));
+
+        // The morph picks up the warmed keys and proves state on them
+        let result = controller.morph(BackendType::BLS12_381).unwrap();
+        assert!(result.success);
+        assert!(controller.has_keys(BackendType::BLS12_381));
+        assert!(!controller.is_warming(BackendType::BLS12_381));
+        let setups = controller
+            .latency()
+            .summary(BackendType::BLS12_381, Operation::Setup)
+            .unwrap();
+        assert_eq!(setups.samples, 1);
+    }
+
+    #[test]
+    fn test_flapping_freezes_threat_policy_morphs() {
+        let mut controller = MorphController::from_config(
+            BackendType::BN254,
+            &cooldown_config(0),
+            &ThreatModel::default(),
+        );
+        let audit = temp_path("flap-audit");
+        controller.set_audit_log(AuditLog::open(&audit).unwrap());
+        let threat = ThreatModel::default().level(&[("quantum", 90)]);
+        let decision = |backend| MorphDecision {
+            should_morph: true,
+            recommended_backend: backend,
+            reason: "flip".to_string(),
+            emergency: false,
+            rule: None,
+        };
+
+        for backend in [
+            BackendType::BLS12_381,
+            BackendType::BN254,
+            BackendType::BLS12_381,
+        ] {
+            let context = MorphContext::threat_policy(&threat, &decision(backend));
+            controller.morph_with(backend, context).unwrap();
+        }
+        let context = MorphContext::threat_policy(&threat, &decision(BackendType::BN254));
+        let err = controller
+            .morph_with(BackendType::BN254, context)
+            .unwrap_err();
+        assert!(err.starts_with("Auto-morph frozen"));
+        assert_eq!(controller.current_backend(), BackendType::BLS12_381);
+
+        // Operators can still morph by hand, and confirm to unfreeze
+        controller.morph(BackendType::BN254).unwrap();
+        let log = controller.audit_log().unwrap();
+        assert_eq!(log.frozen().unwrap().trigger, MorphTrigger::Freeze);
+        assert_eq!(log.last_morph().unwrap().trigger, MorphTrigger::Manual);
+        assert!(controller.resume_auto_morph("ops", None).is_ok());
+        assert!(controller.flap_detector().unwrap().frozen().is_none());
+        assert!(controller.resume_auto_morph("ops", None).is_err());
+
+        // Both are on record, and the chain still verifies
+        let log = AuditLog::open(&audit).unwrap();
+        let resumed = log.last().unwrap();
+        assert_eq!(
+            (resumed.trigger, resumed.operator.as_str()),
+            (MorphTrigger::Resume, "ops")
+        );
+        assert!(log.frozen().is_none());
+        assert!(log.verify().is_ok());
+        std::fs::remove_file(&audit).ok();
+        std::fs::remove_file(format!("{}.head", audit)).ok();
+    }
 }
@@ -0,0 +1,435 @@
+// Cost/security optimizer for backend selection
+//
+// Instead of fixed thresholds, pick the backend for each decision window
+// that minimizes expected cost among the backends secure enough for the
+// current threat. Cost per proof combines proving time, on-chain
+// verification gas and proof size. Its mean per backend is learned online
+// with a normal-normal Bayesian update, and the choice is made by Thompson
+// sampling, so a backend we have few measurements for still gets tried.
+
+use crate::scenario::ScenarioReport;
+use crate::simulator::{MorphDecision, ThreatLevel};
+use crate::threat::ThreatModel;
+use crate::types::{BackendType, UniversalProof};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const BACKENDS: [BackendType; 2] = [BackendType::BN254, BackendType::BLS12_381];
+
+/// Gas to execute a morph on-chain (README)
+const MORPH_GAS: u64 = 300_000;
+
+/// Cost units per measured quantity
+#[derive(Debug, Clone, Copy)]
+pub struct CostWeights {
+    pub per_prove_ms: f64,
+    pub per_kgas: f64,
+    pub per_byte: f64,
+}
+
+impl Default for CostWeights {
+    /// A millisecond of proving is one unit; 10k gas and 20 bytes of
+    /// calldata are worth about as much
+    fn default() -> Self {
+        Self {
+            per_prove_ms: 1.0,
+            per_kgas: 0.1,
+            per_byte: 0.05,
+        }
+    }
+}
+
+impl CostWeights {
+    pub fn cost(&self, m: &Measurement) -> f64 {
+        m.prove.as_secs_f64() * 1000.0 * self.per_prove_ms
+            + m.verify_gas as f64 / 1000.0 * self.per_kgas
+            + m.proof_bytes as f64 * self.per_byte
+    }
+}
+
+/// What one proof cost on `backend`
+#[derive(Debug, Clone, Copy)]
+pub struct Measurement {
+    pub backend: BackendType,
+    pub prove: Duration,
+    pub verify_gas: u64,
+    pub proof_bytes: usize,
+}
+
+impl Measurement {
+    /// README figures: prove time from the demo run, verification gas from
+    /// the gas table, compressed Groth16 proof size
+    pub fn typical(backend: BackendType) -> Self {
+        let (prove_ms, verify_gas, proof_bytes) = match backend {
+            BackendType::BN254 => (45, 200_000, 128),
+            BackendType::BLS12_381 => (78, 500_000, 192),
+        };
+        Self {
+            backend,
+            prove: Duration::from_millis(prove_ms),
+            verify_gas,
+            proof_bytes,
+        }
+    }
+
+    /// A proof we generated and timed; verification gas is not observable
+    /// off-chain, so it is taken from the typical figures
+    pub fn from_proof(proof: &UniversalProof, prove: Duration) -> Self {
+        Self {
+            prove,
+            proof_bytes: proof.proof_bytes.len(),
+            ..Self::typical(proof.backend)
+        }
+    }
+}
+
+/// Normal posterior over a backend's mean cost per proof
+#[derive(Debug, Clone, Copy)]
+pub struct CostEstimate {
+    pub mean: f64,
+    pub variance: f64,
+    pub samples: u32,
+}
+
+impl CostEstimate {
+    fn update(&mut self, observed: f64, noise: f64) {
+        let precision = 1.0 / self.variance + 1.0 / noise;
+        self.mean = (self.mean / self.variance + observed / noise) / precision;
+        self.variance = 1.0 / precision;
+        self.samples += 1;
+    }
+}
+
+/// Security level in bits the threat calls for: BLS12-381's when the
+/// overall score reaches `threat_threshold` or any category its elevated
+/// threshold (or has gone stale), BN254's otherwise
+pub fn required_security(threat: &ThreatLevel, model: &ThreatModel, threat_threshold: u32) -> u32 {
+    let high = threat.overall >= threat_threshold
+        || model.categories.iter().any(|c| {
+            c.thresholds.is_some_and(|t| {
+                threat.score(&c.name) >= t.elevated || threat.stale.contains(&c.name)
+            })
+        });
+    if high {
+        BackendType::BLS12_381.security_bits()
+    } else {
+        BackendType::BN254.security_bits()
+    }
+}
+
+pub struct Optimizer {
+    pub weights: CostWeights,
+    /// Proofs expected in one decision window
+    pub proofs_per_window: f64,
+    /// One-off cost of a morph: state proofs on both curves plus the
+    /// on-chain morph
+    pub switch_cost: f64,
+    /// Variance of a single measurement's cost
+    pub noise: f64,
+    pub threat_threshold: u32,
+    /// Keyed by backend id
+    estimates: BTreeMap<u8, CostEstimate>,
+    rng: StdRng,
+}
+
+impl Optimizer {
+    /// Priors from the typical figures, loose enough that a few
+    /// measurements outweigh them
+    pub fn new(threat_threshold: u32, proofs_per_window: f64, seed: u64) -> Self {
+        let weights = CostWeights::default();
+        let estimates = BACKENDS
+            .iter()
+            .map(|b| {
+                let mean = weights.cost(&Measurement::typical(*b));
+                let prior = CostEstimate {
+                    mean,
+                    variance: (mean / 2.0).powi(2),
+                    samples: 0,
+                };
+                (b.id(), prior)
+            })
+            .collect();
+        let switch_cost = BACKENDS
+            .iter()
+            .map(|b| weights.cost(&Measurement::typical(*b)))
+            .sum::<f64>()
+            + MORPH_GAS as f64 / 1000.0 * weights.per_kgas;
+
+        Self {
+            weights,
+            proofs_per_window,
+            switch_cost,
+            noise: 100.0,
+            threat_threshold,
+            estimates,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn estimate(&self, backend: BackendType) -> CostEstimate {
+        self.estimates[&backend.id()]
+    }
+
+    pub fn observe(&mut self, measurement: &Measurement) {
+        let cost = self.weights.cost(measurement);
+        let noise = self.noise;
+        if let Some(estimate) = self.estimates.get_mut(&measurement.backend.id()) {
+            estimate.update(cost, noise);
+        }
+    }
+
+    /// Cheapest backend for the next window among those meeting the
+    /// threat's security requirement, by a draw from each posterior
+    pub fn decide(
+        &mut self,
+        threat: &ThreatLevel,
+        current: BackendType,
+        model: &ThreatModel,
+    ) -> MorphDecision {
+        let required = required_security(threat, model, self.threat_threshold);
+
+        let mut costs = Vec::new();
+        for backend in BACKENDS {
+            if backend.security_bits() < required {
+                continue;
+            }
+            let estimate = self.estimate(backend);
+            let draw = (estimate.mean + estimate.variance.sqrt() * standard_normal(&mut self.rng))
+                .max(0.0);
+            let switch = if backend == current {
+                0.0
+            } else {
+                self.switch_cost
+            };
+            costs.push((backend, draw * self.proofs_per_window + switch));
+        }
+        let (best, _) = costs
+            .iter()
+            .copied()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("BLS12-381 meets every security requirement");
+
+        let emergency = current.security_bits() < required
+            && model.categories.iter().any(|c| {
+                c.thresholds
+                    .is_some_and(|t| threat.score(&c.name) >= t.emergency)
+            });
+        let costs: Vec<String> = costs
+            .iter()
+            .map(|(b, cost)| format!("{} {:.0}", b.name(), cost))
+            .collect();
+
+        MorphDecision {
+            should_morph: best != current,
+            recommended_backend: best,
+            reason: format!(
+                "OPTIMIZER: {}-bit security required; expected window cost {}",
+                required,
+                costs.join(", ")
+            ),
+            emergency,
+            rule: Some("optimizer".to_string()),
+        }
+    }
+}
+
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    // Box-Muller
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Stand-in for real measurements in scenario runs without a controller:
+/// each backend's true costs, with relative jitter on proving time
+pub struct SimulatedCosts {
+    pub means: BTreeMap<u8, Measurement>,
+    /// Standard deviation of proving time as a fraction of its mean
+    pub jitter: f64,
+    rng: StdRng,
+}
+
+impl SimulatedCosts {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            means: BACKENDS
+                .iter()
+                .map(|b| (b.id(), Measurement::typical(*b)))
+                .collect(),
+            jitter: 0.1,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Make proving on `backend` take `prove` on average
+    pub fn set_prove_time(&mut self, backend: BackendType, prove: Duration) {
+        if let Some(mean) = self.means.get_mut(&backend.id()) {
+            mean.prove = prove;
+        }
+    }
+
+    pub fn measure(&mut self, backend: BackendType) -> Measurement {
+        let mean = self.means[&backend.id()];
+        let factor = (1.0 + self.jitter * standard_normal(&mut self.rng)).max(0.0);
+        Measurement {
+            prove: mean.prove.mul_f64(factor),
+            ..mean
+        }
+    }
+
+    pub fn true_cost(&self, backend: BackendType, weights: &CostWeights) -> f64 {
+        weights.cost(&self.means[&backend.id()])
+    }
+}
+
+/// What a scenario run cost, judged by the true costs
+#[derive(Debug, Clone, Copy)]
+pub struct RunCost {
+    pub cost: f64,
+    pub morphs: usize,
+    /// Cycles spent on a backend below the threat's security requirement
+    pub under_protected: u32,
+}
+
+/// Score any run, policy-driven or optimized, with the same yardstick
+pub fn run_cost(
+    report: &ScenarioReport,
+    optimizer: &Optimizer,
+    world: &SimulatedCosts,
+    model: &ThreatModel,
+) -> RunCost {
+    let mut cost = 0.0;
+    let mut under_protected = 0;
+    for cycle in &report.cycles {
+        cost += world.true_cost(cycle.backend, &optimizer.weights) * optimizer.proofs_per_window;
+        if cycle.morphed() {
+            cost += optimizer.switch_cost;
+        }
+        let required = required_security(&cycle.threat, model, optimizer.threat_threshold);
+        if cycle.backend.security_bits() < required {
+            under_protected += 1;
+        }
+    }
+
+    RunCost {
+        cost,
+        morphs: report.morph_count(),
+        under_protected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::latency::Operation;
+    use crate::morph::MorphController;
+    use crate::scenario::{self, Scenario, ThreatPattern};
+    use crate::simulator::ThreatSimulator;
+
+    #[test]
+    fn test_posterior_learns_from_measurements() {
+        let mut optimizer = Optimizer::new(50, 100.0, 1);
+        let prior = optimizer.estimate(BackendType::BN254);
+
+        let mut world = SimulatedCosts::new(2);
+        world.set_prove_time(BackendType::BN254, Duration::from_millis(400));
+        for _ in 0..50 {
+            optimizer.observe(&world.measure(BackendType::BN254));
+        }
+
+        let learned = optimizer.estimate(BackendType::BN254);
+        let truth = world.true_cost(BackendType::BN254, &optimizer.weights);
+        assert_eq!(learned.samples, 50);
+        assert!(learned.variance < prior.variance);
+        assert!((learned.mean - truth).abs() < truth * 0.05);
+    }
+
+    #[test]
+    fn test_security_floor_is_never_traded_for_cost() {
+        let model = ThreatModel::default();
+        let mut optimizer = Optimizer::new(50, 100.0, 3);
+
+        // Cheap BN254 is not an option under an elevated quantum threat
+        let decision = optimizer.decide(&ThreatLevel::high(&model), BackendType::BN254, &model);
+        assert!(decision.should_morph);
+        assert_eq!(decision.recommended_backend, BackendType::BLS12_381);
+
+        let decision =
+            optimizer.decide(&ThreatLevel::normal(&model), BackendType::BLS12_381, &model);
+        assert_eq!(decision.recommended_backend, BackendType::BN254);
+    }
+
+    #[test]
+    fn test_optimizer_compared_with_threshold_policy() {
+        let model = ThreatModel::default();
+        let scenario = Scenario {
+            pattern: ThreatPattern::RisingFalling,
+            cycles: 40,
+            interval: Duration::from_secs(60),
+            seed: 1,
+        };
+        let simulator = || {
+            let mut sim = ThreatSimulator::new(BackendType::BN254, 50);
+            sim.recovery_threshold = 20;
+            sim
+        };
+
+        // BN254 proving has regressed badly; only measurements reveal it
+        let mut world = SimulatedCosts::new(4);
+        world.set_prove_time(BackendType::BN254, Duration::from_millis(500));
+        let mut optimizer = Optimizer::new(50, 100.0, 5);
+
+        let policy = scenario::run(&scenario, &mut simulator(), None);
+        let optimized = scenario::run_optimized(
+            &scenario,
+            &mut simulator(),
+            &mut optimizer,
+            &mut world,
+            None,
+        );
+
+        let policy_cost = run_cost(&policy, &optimizer, &world, &model);
+        let optimized_cost = run_cost(&optimized, &optimizer, &world, &model);
+        assert_eq!(optimized_cost.under_protected, 0);
+        assert!(optimized_cost.cost < policy_cost.cost);
+        // Having learned BN254 is slow, it does not go back after the peak
+        assert_eq!(optimized.final_backend(), Some(BackendType::BLS12_381));
+        assert_eq!(policy.final_backend(), Some(BackendType::BN254));
+    }
+
+    #[test]
+    fn test_learns_from_timed_proofs() {
+        let scenario = Scenario {
+            pattern: ThreatPattern::Rising,
+            cycles: 4,
+            interval: Duration::from_secs(60),
+            seed: 1,
+        };
+        let mut controller = MorphController::new(BackendType::BN254);
+        controller.setup(BackendType::BN254).unwrap();
+        let mut optimizer = Optimizer::new(50, 100.0, 6);
+        let mut world = SimulatedCosts::new(6);
+
+        scenario::run_optimized(
+            &scenario,
+            &mut ThreatSimulator::new(BackendType::BN254, 50),
+            &mut optimizer,
+            &mut world,
+            Some(&mut controller),
+        );
+
+        let learned: u32 = BACKENDS
+            .iter()
+            .map(|b| optimizer.estimate(*b).samples)
+            .sum();
+        let timed: usize = BACKENDS
+            .iter()
+            .filter_map(|b| controller.latency().summary(*b, Operation::Prove))
+            .map(|s| s.samples)
+            .sum();
+        assert_eq!(learned, 4);
+        assert!(timed >= 4);
+    }
+}
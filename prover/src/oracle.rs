@@ -0,0 +1,614 @@
+// Off-chain threat aggregation oracle
+//
+// A line-for-line model of CryptoHealthOracle in
+// contracts/src/ThreatAggregationOracle.sol, so the prover can compute the
+// same overall score the chain will without a node. Reporters are plain
+// address strings and the caller passes `now` in place of block.timestamp.
+//
+// Contract behaviour kept on purpose, since the point is to agree with it:
+// - only the reported category is re-aggregated; removing a reporter or an
+//   emergency reset leaves other categories as they were
+// - a removed and re-added reporter is listed twice and counted twice
+// - reportMultipleThreats does not check the report cooldown
+// - a report with timestamp 0 counts as no report
+// - a 256th report in a category overflows the uint8 reportCount and the
+//   report is rejected
+
+use crate::simulator::{MorphDecision, ThreatLevel, ThreatSimulator};
+use crate::threat::{CategoryFreshness, ThreatCategory, ThreatModel};
+use crate::types::BackendType;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Categories and weights from the contract's constructor, in enum order
+pub const ORACLE_CATEGORIES: [(&str, u32); 5] = [
+    ("cryptographic", 35),
+    ("implementation", 25),
+    ("regulatory", 20),
+    ("network", 15),
+    ("hardware", 5),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreatReport {
+    pub reporter: String,
+    pub category: String,
+    pub score: u8,
+    pub timestamp: u64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AggregatedThreat {
+    pub average_score: u8,
+    pub max_score: u8,
+    pub report_count: u8,
+    pub last_updated: u64,
+}
+
+pub struct ThreatAggregator {
+    owner: String,
+    /// Every address ever added, like the contract's `reporters` array
+    reporters: Vec<String>,
+    active: BTreeSet<String>,
+    /// Category names and weights, in the order overall is summed
+    model: ThreatModel,
+    /// (category, reporter) -> latest report
+    latest: BTreeMap<(String, String), ThreatReport>,
+    aggregated: BTreeMap<String, AggregatedThreat>,
+    overall: u8,
+    history: Vec<(u8, u64)>,
+    morph_threshold: u8,
+    report_cooldown: u64,
+}
+
+impl ThreatAggregator {
+    /// Same state as a freshly deployed CryptoHealthOracle
+    pub fn new(owner: &str, morph_threshold: u8) -> Self {
+        let categories = ORACLE_CATEGORIES
+            .iter()
+            .map(|(name, weight)| ThreatCategory {
+                name: name.to_string(),
+                weight: *weight,
+                thresholds: None,
+                freshness: CategoryFreshness::default(),
+            })
+            .collect();
+        Self::with_model(owner, morph_threshold, ThreatModel { categories })
+    }
+
+    /// Aggregate over the categories of a local threat model instead, so
+    /// the result can drive a simulator using that model's policy
+    pub fn with_model(owner: &str, morph_threshold: u8, model: ThreatModel) -> Self {
+        Self {
+            owner: owner.to_string(),
+            reporters: vec![owner.to_string()],
+            active: BTreeSet::from([owner.to_string()]),
+            model,
+            latest: BTreeMap::new(),
+            aggregated: BTreeMap::new(),
+            overall: 0,
+            history: Vec::new(),
+            morph_threshold,
+            report_cooldown: 0,
+        }
+    }
+
+    fn only_owner(&self, caller: &str) -> Result<(), String> {
+        if caller != self.owner {
+            return Err("Not owner".to_string());
+        }
+        Ok(())
+    }
+
+    fn only_reporter(&self, caller: &str) -> Result<(), String> {
+        if !self.active.contains(caller) {
+            return Err("Not authorized reporter".to_string());
+        }
+        Ok(())
+    }
+
+    fn check_category(&self, category: &str) -> Result<(), String> {
+        if self.model.category(category).is_none() {
+            return Err(format!("Unknown threat category '{}'", category));
+        }
+        Ok(())
+    }
+
+    pub fn is_reporter(&self, address: &str) -> bool {
+        self.active.contains(address)
+    }
+
+    pub fn add_reporter(&mut self, caller: &str, reporter: &str) -> Result<(), String> {
+        self.only_owner(caller)?;
+        if self.active.contains(reporter) {
+            return Err("Already a reporter".to_string());
+        }
+        self.active.insert(reporter.to_string());
+        self.reporters.push(reporter.to_string());
+        Ok(())
+    }
+
+    pub fn remove_reporter(&mut self, caller: &str, reporter: &str) -> Result<(), String> {
+        self.only_owner(caller)?;
+        if !self.active.contains(reporter) {
+            return Err("Not a reporter".to_string());
+        }
+        if reporter == self.owner {
+            return Err("Cannot remove owner".to_string());
+        }
+        self.active.remove(reporter);
+        Ok(())
+    }
+
+    pub fn reporter_count(&self) -> usize {
+        self.reporters
+            .iter()
+            .filter(|r| self.active.contains(*r))
+            .count()
+    }
+
+    /// `reportThreat`: returns the new overall score
+    pub fn report_threat(
+        &mut self,
+        caller: &str,
+        category: &str,
+        score: u8,
+        description: &str,
+        now: u64,
+    ) -> Result<u8, String> {
+        self.only_reporter(caller)?;
+        self.check_category(category)?;
+        if score > 100 {
+            return Err("Score must be 0-100".to_string());
+        }
+        if let Some(existing) = self.latest.get(&(category.to_string(), caller.to_string())) {
+            if existing.timestamp > 0
+                && now < existing.timestamp.saturating_add(self.report_cooldown)
+            {
+                return Err("Report cooldown active".to_string());
+            }
+        }
+
+        self.reverting(|oracle| {
+            oracle.store(caller, category, score, description, now);
+            oracle.aggregate_category(category, now)
+        })?;
+        self.calculate_overall(now);
+        Ok(self.overall)
+    }
+
+    /// `reportMultipleThreats`: no cooldown check, one history entry
+    pub fn report_multiple(
+        &mut self,
+        caller: &str,
+        reports: &[(&str, u8, &str)],
+        now: u64,
+    ) -> Result<u8, String> {
+        self.only_reporter(caller)?;
+        // The contract reverts the whole call, so validate before storing
+        for (category, score, _) in reports {
+            self.check_category(category)?;
+            if *score > 100 {
+                return Err("Score must be 0-100".to_string());
+            }
+        }
+
+        self.reverting(|oracle| {
+            for (category, score, description) in reports {
+                oracle.store(caller, category, *score, description, now);
+                oracle.aggregate_category(category, now)?;
+            }
+            Ok(())
+        })?;
+        self.calculate_overall(now);
+        Ok(self.overall)
+    }
+
+    /// Run `f`, putting reports and aggregates back if it fails, as the
+    /// contract's revert would
+    fn reverting(&mut self, f: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        let latest = self.latest.clone();
+        let aggregated = self.aggregated.clone();
+        let result = f(self);
+        if result.is_err() {
+            self.latest = latest;
+            self.aggregated = aggregated;
+        }
+        result
+    }
+
+    fn store(&mut self, caller: &str, category: &str, score: u8, description: &str, now: u64) {
+        self.latest.insert(
+            (category.to_string(), caller.to_string()),
+            ThreatReport {
+                reporter: caller.to_string(),
+                category: category.to_string(),
+                score,
+                timestamp: now,
+                description: description.to_string(),
+            },
+        );
+    }
+
+    /// Fails like the contract's checked `reportCount++` once more than 255
+    /// listed reporters have reported
+    fn aggregate_category(&mut self, category: &str, now: u64) -> Result<(), String> {
+        let mut total: u32 = 0;
+        let mut max_score = 0;
+        let mut report_count: u8 = 0;
+
+        for reporter in &self.reporters {
+            if !self.active.contains(reporter) {
+                continue;
+            }
+            let Some(report) = self.latest.get(&(category.to_string(), reporter.clone())) else {
+                continue;
+            };
+            if report.timestamp == 0 {
+                continue;
+            }
+            total += report.score as u32;
+            max_score = max_score.max(report.score);
+            report_count = report_count
+                .checked_add(1)
+                .ok_or("Arithmetic overflow: more than 255 reports in a category")?;
+        }
+
+        let average_score = if report_count > 0 {
+            (total / report_count as u32) as u8
+        } else {
+            0
+        };
+        self.aggregated.insert(
+            category.to_string(),
+            AggregatedThreat {
+                average_score,
+                max_score,
+                report_count,
+                last_updated: now,
+            },
+        );
+        Ok(())
+    }
+
+    fn calculate_overall(&mut self, now: u64) {
+        let weighted: u64 = self
+            .model
+            .categories
+            .iter()
+            .map(|c| self.aggregated(&c.name).average_score as u64 * c.weight as u64)
+            .sum();
+        // uint8(weightedSum / 100)
+        self.overall = (weighted / 100) as u8;
+        self.history.push((self.overall, now));
+    }
+
+    pub fn aggregated(&self, category: &str) -> AggregatedThreat {
+        self.aggregated.get(category).copied().unwrap_or_default()
+    }
+
+    pub fn report(&self, category: &str, reporter: &str) -> Option<&ThreatReport> {
+        self.latest
+            .get(&(category.to_string(), reporter.to_string()))
+    }
+
+    pub fn overall_score(&self) -> u8 {
+        self.overall
+    }
+
+    /// (score, timestamp) after every report call and reset
+    pub fn history(&self) -> &[(u8, u64)] {
+        &self.history
+    }
+
+    pub fn morph_threshold(&self) -> u8 {
+        self.morph_threshold
+    }
+
+    pub fn is_morph_recommended(&self) -> bool {
+        self.overall >= self.morph_threshold
+    }
+
+    pub fn recommended_backend(&self) -> BackendType {
+        if self.is_morph_recommended() {
+            BackendType::BLS12_381
+        } else {
+            BackendType::BN254
+        }
+    }
+
+    pub fn threat_level_string(&self) -> &'static str {
+        match self.overall {
+            75.. => "CRITICAL",
+            50.. => "HIGH",
+            25.. => "MEDIUM",
+            _ => "LOW",
+        }
+    }
+
+    pub fn update_morph_threshold(&mut self, caller: &str, threshold: u8) -> Result<(), String> {
+        self.only_owner(caller)?;
+        if threshold > 100 {
+            return Err("Threshold must be 0-100".to_string());
+        }
+        self.morph_threshold = threshold;
+        Ok(())
+    }
+
+    /// Takes effect at the next report, as on chain
+    pub fn update_category_weight(
+        &mut self,
+        caller: &str,
+        category: &str,
+        weight: u8,
+    ) -> Result<(), String> {
+        self.only_owner(caller)?;
+        self.check_category(category)?;
+        if let Some(c) = self
+            .model
+            .categories
+            .iter_mut()
+            .find(|c| c.name == category)
+        {
+            c.weight = weight as u32;
+        }
+        Ok(())
+    }
+
+    pub fn update_report_cooldown(&mut self, caller: &str, cooldown: u64) -> Result<(), String> {
+        self.only_owner(caller)?;
+        self.report_cooldown = cooldown;
+        Ok(())
+    }
+
+    /// Zero every aggregate and the overall score; latest reports are kept
+    pub fn emergency_reset(&mut self, caller: &str, now: u64) -> Result<(), String> {
+        self.only_owner(caller)?;
+        for category in &self.model.categories {
+            self.aggregated.insert(
+                category.name.clone(),
+                AggregatedThreat {
+                    last_updated: now,
+                    ..AggregatedThreat::default()
+                },
+            );
+        }
+        self.overall = 0;
+        self.history.push((0, now));
+        Ok(())
+    }
+
+    /// Categories and current weights
+    pub fn model(&self) -> &ThreatModel {
+        &self.model
+    }
+
+    /// Category averages as a threat level, with the oracle's overall score
+    pub fn threat_level(&self) -> ThreatLevel {
+        let scores: Vec<(&str, u32)> = self
+            .model
+            .categories
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    self.aggregated(&c.name).average_score as u32,
+                )
+            })
+            .collect();
+        let mut level = self.model.level(&scores);
+        level.overall = self.overall as u32;
+        level
+    }
+
+    /// Run the current aggregate through a simulator
+    pub fn feed(&self, simulator: &mut ThreatSimulator) -> MorphDecision {
+        simulator.step(&self.threat_level())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Conformance cases: contracts/test/ThreatAggregationOracle.t.sol runs
+    // the same sequences against the contract with the same expected values.
+    use super::*;
+
+    const OWNER: &str = "0x0000000000000000000000000000000000000001";
+    const ALICE: &str = "0x000000000000000000000000000000000000a11c";
+
+    fn oracle() -> ThreatAggregator {
+        ThreatAggregator::new(OWNER, 50)
+    }
+
+    #[test]
+    fn test_weighted_overall_and_history() {
+        let mut oracle = oracle();
+        for (category, score) in [
+            ("cryptographic", 90),
+            ("implementation", 33),
+            ("regulatory", 47),
+            ("network", 12),
+            ("hardware", 99),
+        ] {
+            oracle.report_threat(OWNER, category, score, "", 1).unwrap();
+        }
+
+        let scores: Vec<u8> = oracle.history().iter().map(|(s, _)| *s).collect();
+        assert_eq!(scores, vec![31, 39, 49, 50, 55]);
+        assert_eq!(oracle.overall_score(), 55);
+        assert!(oracle.is_morph_recommended());
+        assert_eq!(oracle.threat_level_string(), "HIGH");
+        assert_eq!(oracle.recommended_backend(), BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_reporters_average_and_max() {
+        let mut oracle = oracle();
+        oracle.add_reporter(OWNER, ALICE).unwrap();
+        assert_eq!(oracle.reporter_count(), 2);
+        assert!(oracle.add_reporter(ALICE, "0x3").is_err());
+
+        oracle
+            .report_threat(OWNER, "cryptographic", 80, "", 1)
+            .unwrap();
+        oracle
+            .report_threat(ALICE, "cryptographic", 45, "", 1)
+            .unwrap();
+
+        let agg = oracle.aggregated("cryptographic");
+        assert_eq!(
+            (agg.average_score, agg.max_score, agg.report_count),
+            (62, 80, 2)
+        );
+        assert_eq!(oracle.overall_score(), 21);
+        assert_eq!(
+            oracle
+                .report_threat("0x3", "network", 10, "", 1)
+                .unwrap_err(),
+            "Not authorized reporter"
+        );
+        assert_eq!(
+            oracle
+                .report_threat(OWNER, "network", 101, "", 1)
+                .unwrap_err(),
+            "Score must be 0-100"
+        );
+    }
+
+    #[test]
+    fn test_report_cooldown() {
+        let mut oracle = oracle();
+        oracle.update_report_cooldown(OWNER, 3600).unwrap();
+
+        oracle
+            .report_threat(OWNER, "cryptographic", 10, "", 1000)
+            .unwrap();
+        assert_eq!(
+            oracle
+                .report_threat(OWNER, "cryptographic", 20, "", 2000)
+                .unwrap_err(),
+            "Report cooldown active"
+        );
+        oracle
+            .report_threat(OWNER, "cryptographic", 20, "", 4600)
+            .unwrap();
+
+        // The batch call skips the cooldown
+        oracle
+            .report_multiple(OWNER, &[("cryptographic", 30, "")], 4601)
+            .unwrap();
+        assert_eq!(oracle.aggregated("cryptographic").average_score, 30);
+        assert_eq!(oracle.history().len(), 3);
+    }
+
+    #[test]
+    fn test_huge_cooldown_does_not_overflow() {
+        let mut oracle = oracle();
+        oracle.update_report_cooldown(OWNER, u64::MAX).unwrap();
+        oracle
+            .report_threat(OWNER, "cryptographic", 10, "", 1000)
+            .unwrap();
+        assert_eq!(
+            oracle
+                .report_threat(OWNER, "cryptographic", 20, "", u64::MAX - 1)
+                .unwrap_err(),
+            "Report cooldown active"
+        );
+    }
+
+    #[test]
+    fn test_report_count_overflow_reverts() {
+        let mut oracle = oracle();
+        for i in 2..=256 {
+            oracle
+                .add_reporter(OWNER, &format!("0x{:040x}", i))
+                .unwrap();
+        }
+        for i in 2..=256 {
+            let reporter = format!("0x{:040x}", i);
+            oracle
+                .report_threat(&reporter, "network", 40, "", 1)
+                .unwrap();
+        }
+        assert_eq!(oracle.aggregated("network").report_count, 255);
+
+        // The owner's report would be the 256th: rejected, nothing stored
+        let err = oracle
+            .report_multiple(OWNER, &[("regulatory", 90, ""), ("network", 90, "")], 2)
+            .unwrap_err();
+        assert!(err.contains("overflow"), "{}", err);
+        assert_eq!(oracle.aggregated("regulatory").report_count, 0);
+        assert_eq!(oracle.aggregated("network").average_score, 40);
+        assert_eq!(oracle.history().len(), 255);
+    }
+
+    #[test]
+    fn test_removed_and_readded_reporter() {
+        let mut oracle = oracle();
+        oracle.add_reporter(OWNER, ALICE).unwrap();
+        oracle
+            .report_threat(OWNER, "cryptographic", 80, "", 1)
+            .unwrap();
+        oracle
+            .report_threat(ALICE, "cryptographic", 40, "", 1)
+            .unwrap();
+        assert_eq!(oracle.aggregated("cryptographic").average_score, 60);
+
+        // Removal does not re-aggregate until that category is reported again
+        oracle.remove_reporter(OWNER, ALICE).unwrap();
+        assert_eq!(oracle.report_threat(OWNER, "regulatory", 50, "", 1), Ok(31));
+        assert_eq!(
+            oracle.report_threat(OWNER, "cryptographic", 80, "", 1),
+            Ok(38)
+        );
+
+        // Re-adding lists Alice twice, so her old report counts twice
+        oracle.add_reporter(OWNER, ALICE).unwrap();
+        assert_eq!(
+            oracle.report_threat(OWNER, "cryptographic", 80, "", 1),
+            Ok(28)
+        );
+        let agg = oracle.aggregated("cryptographic");
+        assert_eq!((agg.average_score, agg.report_count), (53, 3));
+        assert_eq!(
+            oracle.remove_reporter(OWNER, OWNER).unwrap_err(),
+            "Cannot remove owner"
+        );
+    }
+
+    #[test]
+    fn test_emergency_reset() {
+        let mut oracle = oracle();
+        assert_eq!(
+            oracle.report_threat(OWNER, "cryptographic", 90, "", 1),
+            Ok(31)
+        );
+        assert_eq!(oracle.emergency_reset(ALICE, 2).unwrap_err(), "Not owner");
+
+        oracle.emergency_reset(OWNER, 2).unwrap();
+        assert_eq!(oracle.overall_score(), 0);
+        assert_eq!(oracle.history().last(), Some(&(0, 2)));
+
+        // Old reports survive the reset and come back when re-aggregated
+        assert_eq!(oracle.report_threat(OWNER, "regulatory", 50, "", 3), Ok(10));
+        assert_eq!(
+            oracle.report_threat(OWNER, "implementation", 10, "", 3),
+            Ok(12)
+        );
+        assert_eq!(oracle.aggregated("cryptographic").average_score, 0);
+    }
+
+    #[test]
+    fn test_feeds_simulator() {
+        let mut oracle = ThreatAggregator::with_model(OWNER, 50, ThreatModel::default());
+        oracle.add_reporter(OWNER, ALICE).unwrap();
+        oracle.report_threat(OWNER, "quantum", 90, "", 1).unwrap();
+        oracle.report_threat(ALICE, "quantum", 70, "", 1).unwrap();
+
+        let mut sim = ThreatSimulator::new(BackendType::BN254, 50);
+        let decision = oracle.feed(&mut sim);
+        assert!(decision.should_morph && decision.emergency);
+        assert_eq!(sim.current_backend, BackendType::BLS12_381);
+        assert_eq!(oracle.threat_level().quantum, 80);
+    }
+}
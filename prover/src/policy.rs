@@ -0,0 +1,690 @@
+// Declarative morph policy
+//
+// A policy is an ordered list of rules. Each rule has a list of conditions
+// that must all hold and a target backend; the first rule that matches
+// decides. Policies are JSON so the security team can change them without
+// a rebuild, e.g.:
+//
+//   {
+//     "name": "default",
+//     "rules": [
+//       {
+//         "name": "quantum-emergency",
+//         "when": [{ "category": "quantum", "at_least": 80 }],
+//         "target": "BLS12_381",
+//         "emergency": true,
+//         "reason": "CRITICAL: Quantum threat {quantum} requires maximum security"
+//       }
+//     ]
+//   }
+//
+// `{overall}` and `{<category>}` in a reason are replaced with the scores.
+// A policy without rules uses the built-in ones, which are built from the
+// thresholds in simulator/threat_config.json, so a policy file need not
+// repeat those numbers.
+
+use crate::latency::BREACH_SCORE;
+use crate::simulator::{MorphDecision, ThreatLevel};
+use crate::threat::ThreatModel;
+use crate::types::BackendType;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// One clause of a rule. Every field that is set must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    /// Threat category the score bounds apply to, or "overall"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at_least: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<BackendType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend_not: Option<BackendType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jurisdiction_in: Option<Vec<String>>,
+    /// Seconds; holds if we have never morphed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_last_morph_at_least: Option<u64>,
+    /// Seconds; never holds if we have never morphed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_last_morph_below: Option<u64>,
+    /// Category whose feed has gone silent, or "any"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale: Option<String>,
+    /// Bits; the current backend's estimated classical security is lower
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_below: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    pub name: String,
+    #[serde(default)]
+    pub when: Vec<Condition>,
+    /// Backend to be on when this rule fires; `None` means stay put
+    #[serde(default)]
+    pub target: Option<BackendType>,
+    #[serde(default)]
+    pub emergency: bool,
+    pub reason: String,
+}
+
+/// What to make of a category whose feed has gone silent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleData {
+    /// Use whatever the (decayed) reading says
+    #[default]
+    Ignore,
+    /// Read it as at least its elevated threshold, before any rule runs
+    AssumeElevated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MorphPolicy {
+    pub name: String,
+    #[serde(default)]
+    pub stale_data: StaleData,
+    /// Empty means the built-in rules from the configured thresholds
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+/// Everything a rule can look at
+#[derive(Debug, Clone)]
+pub struct PolicyInput<'a> {
+    pub threat: &'a ThreatLevel,
+    /// Category weights and thresholds, for the stale-data fail-safe
+    pub model: &'a ThreatModel,
+    pub current_backend: BackendType,
+    pub jurisdiction: Option<&'a str>,
+    pub since_last_morph: Option<Duration>,
+}
+
+/// How one rule was evaluated
+#[derive(Debug, Clone)]
+pub struct RuleTrace {
+    pub rule: String,
+    pub matched: bool,
+    /// Each clause in words, with whether it held
+    pub clauses: Vec<(String, bool)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyExplanation {
+    pub policy: String,
+    pub decision: MorphDecision,
+    /// Scores changed before the rules ran, e.g. by the stale-data fail-safe
+    pub adjustments: Vec<String>,
+    /// Rules up to and including the one that fired
+    pub trace: Vec<RuleTrace>,
+}
+
+impl PolicyExplanation {
+    pub fn print(&self) {
+        println!("Policy: {}", self.policy);
+        for adjustment in &self.adjustments {
+            println!("  [adj  ] {}", adjustment);
+        }
+        for rule in &self.trace {
+            println!(
+                "  [{}] {}",
+                if rule.matched { "FIRED" } else { "skip " },
+                rule.rule
+            );
+            for (clause, held) in &rule.clauses {
+                println!("        {} {}", if *held { "+" } else { "-" }, clause);
+            }
+        }
+        match &self.decision.rule {
+            Some(rule) => println!("Decision by rule '{}': {}", rule, self.decision.reason),
+            None => println!("No rule matched: {}", self.decision.reason),
+        }
+    }
+}
+
+impl MorphPolicy {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read policy: {}", e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse policy: {}", e))
+    }
+
+    /// Load config/morph_policy.json if there is one
+    pub fn load_default() -> Result<Option<Self>, String> {
+        let paths = vec![
+            "config/morph_policy.json",
+            "../config/morph_policy.json",
+            "morph_policy.json",
+        ];
+
+        for path in paths {
+            if Path::new(path).exists() {
+                return Self::load(path).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The built-in rules: per-category emergency and elevated thresholds
+    /// from the threat model, then upgrade/recovery on the overall score.
+    pub fn from_thresholds(
+        model: &ThreatModel,
+        threat_threshold: u32,
+        recovery_threshold: u32,
+    ) -> Self {
+        let mut rules = Vec::new();
+        let score = |category: &str, at_least| Condition {
+            category: Some(category.to_string()),
+            at_least: Some(at_least),
+            ..Condition::default()
+        };
+
+        for category in &model.categories {
+            if let Some(limits) = category.thresholds {
+                rules.push(PolicyRule {
+                    name: format!("{}-emergency", category.name),
+                    when: vec![score(&category.name, limits.emergency)],
+                    target: Some(BackendType::BLS12_381),
+                    emergency: true,
+                    reason: format!(
+                        "CRITICAL: {} threat level requires maximum security",
+                        crate::simulator::capitalize(&category.name)
+                    ),
+                });
+            }
+        }
+        for category in &model.categories {
+            if let Some(limits) = category.thresholds {
+                rules.push(PolicyRule {
+                    name: format!("{}-elevated", category.name),
+                    when: vec![score(&category.name, limits.elevated)],
+                    target: Some(BackendType::BLS12_381),
+                    emergency: false,
+                    reason: format!(
+                        "WARNING: Elevated {} threat, recommend higher security",
+                        category.name
+                    ),
+                });
+            }
+        }
+
+        rules.push(PolicyRule {
+            name: "overall-upgrade".to_string(),
+            when: vec![score("overall", threat_threshold)],
+            target: Some(BackendType::BLS12_381),
+            emergency: false,
+            reason: format!(
+                "WARNING: Overall threat {{overall}} above upgrade threshold {}",
+                threat_threshold
+            ),
+        });
+        // Nothing above fired, so security allows BN254; take it if the
+        // current backend is missing its latency SLOs
+        if model.category("performance").is_some() {
+            rules.push(PolicyRule {
+                name: "performance-recovery".to_string(),
+                when: vec![
+                    score("performance", BREACH_SCORE),
+                    Condition {
+                        backend_not: Some(BackendType::BN254),
+                        ..Condition::default()
+                    },
+                ],
+                target: Some(BackendType::BN254),
+                emergency: false,
+                reason: "SLO BREACH: Performance {performance}, security allows BN254".to_string(),
+            });
+        }
+        // Between the two thresholds no rule fires and we keep whatever we
+        // are on, so a reading hovering around one line cannot flip us.
+        rules.push(PolicyRule {
+            name: "overall-recovery".to_string(),
+            when: vec![
+                Condition {
+                    category: Some("overall".to_string()),
+                    below: Some(recovery_threshold),
+                    ..Condition::default()
+                },
+                Condition {
+                    backend_not: Some(BackendType::BN254),
+                    ..Condition::default()
+                },
+            ],
+            target: Some(BackendType::BN254),
+            emergency: false,
+            reason: "LOW THREAT: Can optimize for speed with BN254".to_string(),
+        });
+
+        Self {
+            name: "built-in thresholds".to_string(),
+            stale_data: StaleData::AssumeElevated,
+            rules,
+        }
+    }
+
+    /// Check every category a rule names exists in `model`
+    pub fn validate(&self, model: &ThreatModel) -> Result<(), String> {
+        for rule in &self.rules {
+            for condition in &rule.when {
+                if let Some(category) = &condition.category {
+                    if category != "overall" && model.category(category).is_none() {
+                        return Err(format!(
+                            "Rule '{}' refers to unknown category '{}'",
+                            rule.name, category
+                        ));
+                    }
+                    if condition.at_least.is_none() && condition.below.is_none() {
+                        return Err(format!(
+                            "Rule '{}' names category '{}' without at_least/below",
+                            rule.name, category
+                        ));
+                    }
+                } else if condition.at_least.is_some() || condition.below.is_some() {
+                    return Err(format!(
+                        "Rule '{}' has a score bound without a category",
+                        rule.name
+                    ));
+                }
+                if let Some(category) = &condition.stale {
+                    if category != "any" && model.category(category).is_none() {
+                        return Err(format!(
+                            "Rule '{}' refers to unknown category '{}'",
+                            rule.name, category
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn evaluate(&self, input: &PolicyInput) -> MorphDecision {
+        self.explain(input).decision
+    }
+
+    /// Evaluate rules in order, recording why each one did or did not fire
+    pub fn explain(&self, input: &PolicyInput) -> PolicyExplanation {
+        let mut trace = Vec::new();
+        let (adjusted, adjustments) = self.fail_safe(input);
+        let input = &PolicyInput {
+            threat: adjusted.as_ref().unwrap_or(input.threat),
+            ..input.clone()
+        };
+
+        for rule in &self.rules {
+            let clauses: Vec<(String, bool)> = rule
+                .when
+                .iter()
+                .flat_map(|condition| check(condition, input))
+                .collect();
+            let matched = clauses.iter().all(|(_, held)| *held);
+            trace.push(RuleTrace {
+                rule: rule.name.clone(),
+                matched,
+                clauses,
+            });
+
+            if matched {
+                let target = rule.target.unwrap_or(input.current_backend);
+                return PolicyExplanation {
+                    policy: self.name.clone(),
+                    decision: MorphDecision {
+                        should_morph: target != input.current_backend,
+                        recommended_backend: target,
+                        reason: fill_scores(&rule.reason, input.threat),
+                        emergency: rule.emergency,
+                        rule: Some(rule.name.clone()),
+                    },
+                    adjustments,
+                    trace,
+                };
+            }
+        }
+
+        PolicyExplanation {
+            policy: self.name.clone(),
+            decision: MorphDecision {
+                should_morph: false,
+                recommended_backend: input.current_backend,
+                reason: "Current backend is appropriate for threat level".to_string(),
+                emergency: false,
+                rule: None,
+            },
+            adjustments,
+            trace,
+        }
+    }
+}
+
+impl MorphPolicy {
+    /// Apply `stale_data` to the threat; returns the adjusted threat, if
+    /// anything changed, and a note per change
+    fn fail_safe(&self, input: &PolicyInput) -> (Option<ThreatLevel>, Vec<String>) {
+        let mut notes = Vec::new();
+        if self.stale_data != StaleData::AssumeElevated || input.threat.stale.is_empty() {
+            return (None, notes);
+        }
+
+        let mut scores: Vec<(&str, u32)> = input
+            .model
+            .categories
+            .iter()
+            .map(|c| (c.name.as_str(), input.threat.score(&c.name)))
+            .collect();
+        for (name, score) in scores.iter_mut() {
+            let limits = input.model.category(name).and_then(|c| c.thresholds);
+            if let (true, Some(limits)) = (input.threat.stale.contains(*name), limits) {
+                if *score < limits.elevated {
+                    notes.push(format!(
+                        "{} feed stale: assuming elevated ({} -> {})",
+                        name, score, limits.elevated
+                    ));
+                    *score = limits.elevated;
+                }
+            }
+        }
+        if notes.is_empty() {
+            return (None, notes);
+        }
+
+        let mut threat = input.model.level(&scores);
+        threat.stale = input.threat.stale.clone();
+        (Some(threat), notes)
+    }
+}
+
+/// Each set field of `condition` in words, with whether it held
+fn check(condition: &Condition, input: &PolicyInput) -> Vec<(String, bool)> {
+    let mut clauses = Vec::new();
+
+    if let Some(category) = &condition.category {
+        let score = if category == "overall" {
+            input.threat.overall
+        } else {
+            input.threat.score(category)
+        };
+        if let Some(min) = condition.at_least {
+            clauses.push((format!("{} {} >= {}", category, score, min), score >= min));
+        }
+        if let Some(max) = condition.below {
+            clauses.push((format!("{} {} < {}", category, score, max), score < max));
+        }
+    }
+    if let Some(backend) = condition.backend {
+        clauses.push((
+            format!(
+                "on {} (current {})",
+                backend.name(),
+                input.current_backend.name()
+            ),
+            input.current_backend == backend,
+        ));
+    }
+    if let Some(backend) = condition.backend_not {
+        clauses.push((
+            format!(
+                "not on {} (current {})",
+                backend.name(),
+                input.current_backend.name()
+            ),
+            input.current_backend != backend,
+        ));
+    }
+    if let Some(bits) = condition.security_below {
+        let security = input.current_backend.security_bits();
+        clauses.push((
+            format!(
+                "{} security {}-bit < {}-bit",
+                input.current_backend.name(),
+                security,
+                bits
+            ),
+            security < bits,
+        ));
+    }
+    if let Some(allowed) = &condition.jurisdiction_in {
+        let held = input
+            .jurisdiction
+            .is_some_and(|j| allowed.iter().any(|a| a.eq_ignore_ascii_case(j)));
+        clauses.push((
+            format!(
+                "jurisdiction {} in [{}]",
+                input.jurisdiction.unwrap_or("(none)"),
+                allowed.join(", ")
+            ),
+            held,
+        ));
+    }
+    if let Some(category) = &condition.stale {
+        let held = if category == "any" {
+            !input.threat.stale.is_empty()
+        } else {
+            input.threat.stale.contains(category)
+        };
+        let stale: Vec<&str> = input.threat.stale.iter().map(String::as_str).collect();
+        clauses.push((
+            format!("{} feed stale (stale: [{}])", category, stale.join(", ")),
+            held,
+        ));
+    }
+    let since = input.since_last_morph.map(|d| d.as_secs());
+    let since_text = since.map_or("never".to_string(), |s| format!("{}s", s));
+    if let Some(min) = condition.since_last_morph_at_least {
+        clauses.push((
+            format!("last morph {} ago >= {}s", since_text, min),
+            since.is_none_or(|s| s >= min),
+        ));
+    }
+    if let Some(max) = condition.since_last_morph_below {
+        clauses.push((
+            format!("last morph {} ago < {}s", since_text, max),
+            since.is_some_and(|s| s < max),
+        ));
+    }
+
+    clauses
+}
+
+fn fill_scores(reason: &str, threat: &ThreatLevel) -> String {
+    let mut text = reason.replace("{overall}", &threat.overall.to_string());
+    text = text.replace("{quantum}", &threat.quantum.to_string());
+    text = text.replace("{regulatory}", &threat.regulatory.to_string());
+    for (name, score) in &threat.other {
+        text = text.replace(&format!("{{{}}}", name), &score.to_string());
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input<'a>(
+        threat: &'a ThreatLevel,
+        model: &'a ThreatModel,
+        backend: BackendType,
+    ) -> PolicyInput<'a> {
+        PolicyInput {
+            threat,
+            model,
+            current_backend: backend,
+            jurisdiction: None,
+            since_last_morph: None,
+        }
+    }
+
+    #[test]
+    fn test_builtin_policy_matches_thresholds() {
+        let model = ThreatModel::default();
+        let policy = MorphPolicy::from_thresholds(&model, 50, 20);
+
+        let critical = ThreatLevel::critical(&model);
+        let decision = policy.evaluate(&input(&critical, &model, BackendType::BN254));
+        assert!(decision.should_morph && decision.emergency);
+        assert_eq!(decision.rule.as_deref(), Some("quantum-emergency"));
+
+        let calm = ThreatLevel::normal(&model);
+        let decision = policy.evaluate(&input(&calm, &model, BackendType::BLS12_381));
+        assert_eq!(decision.recommended_backend, BackendType::BN254);
+        assert_eq!(decision.rule.as_deref(), Some("overall-recovery"));
+
+        let decision = policy.evaluate(&input(
+            &model.level(&[("quantum", 50), ("regulatory", 50)]),
+            &model,
+            BackendType::BLS12_381,
+        ));
+        assert!(!decision.should_morph);
+        assert!(decision.rule.is_none());
+    }
+
+    #[test]
+    fn test_json_policy_with_jurisdiction_and_time() {
+        let policy: MorphPolicy = serde_json::from_str(
+            r#"{
+                "name": "eu-strict",
+                "rules": [
+                    {
+                        "name": "eu-always-bls",
+                        "when": [{ "jurisdiction_in": ["EU"] }, { "backend": "BN254" }],
+                        "target": "BLS12_381",
+                        "reason": "EU deployments stay on BLS12-381"
+                    },
+                    {
+                        "name": "settle-before-recovery",
+                        "when": [
+                            { "category": "overall", "below": 20 },
+                            { "since_last_morph_at_least": 3600 }
+                        ],
+                        "target": "BN254",
+                        "reason": "Calm for an hour at {overall}"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let model = ThreatModel::default();
+        policy.validate(&model).unwrap();
+
+        let calm = ThreatLevel::normal(&model);
+        let mut eu = input(&calm, &model, BackendType::BN254);
+        eu.jurisdiction = Some("eu");
+        assert_eq!(policy.evaluate(&eu).rule.as_deref(), Some("eu-always-bls"));
+
+        let mut recent = input(&calm, &model, BackendType::BLS12_381);
+        recent.since_last_morph = Some(Duration::from_secs(60));
+        assert!(!policy.evaluate(&recent).should_morph);
+
+        recent.since_last_morph = Some(Duration::from_secs(7200));
+        let explanation = policy.explain(&recent);
+        assert!(explanation.decision.should_morph);
+        assert_eq!(explanation.trace.len(), 2);
+        assert!(!explanation.trace[0].matched);
+        assert_eq!(explanation.decision.reason, "Calm for an hour at 8");
+    }
+
+    #[test]
+    fn test_security_floor_uses_estimate() {
+        let policy: MorphPolicy = serde_json::from_str(
+            r#"{
+                "name": "floor",
+                "rules": [{
+                    "name": "security-floor",
+                    "when": [{ "security_below": 112 }],
+                    "target": "BLS12_381",
+                    "reason": "Below the 112-bit floor"
+                }]
+            }"#,
+        )
+        .unwrap();
+        let model = ThreatModel::default();
+        let calm = ThreatLevel::normal(&model);
+
+        let explanation = policy.explain(&input(&calm, &model, BackendType::BN254));
+        assert_eq!(explanation.decision.rule.as_deref(), Some("security-floor"));
+        assert_eq!(
+            explanation.trace[0].clauses[0].0,
+            "BN254 security 103-bit < 112-bit"
+        );
+        let decision = policy.evaluate(&input(&calm, &model, BackendType::BLS12_381));
+        assert!(decision.rule.is_none());
+    }
+
+    #[test]
+    fn test_unknown_category_rejected() {
+        let mut policy = MorphPolicy::from_thresholds(&ThreatModel::default(), 50, 20);
+        policy.rules[0].when[0].category = Some("weather".to_string());
+        assert!(policy.validate(&ThreatModel::default()).is_err());
+    }
+
+    #[test]
+    fn test_slo_breach_returns_to_bn254_when_safe() {
+        let model = ThreatModel::default();
+        let policy = MorphPolicy::from_thresholds(&model, 50, 20);
+
+        let slow = model.level(&[("quantum", 30), ("regulatory", 30), ("performance", 75)]);
+        let decision = policy.evaluate(&input(&slow, &model, BackendType::BLS12_381));
+        assert!(decision.should_morph);
+        assert_eq!(decision.rule.as_deref(), Some("performance-recovery"));
+
+        // Security still comes first
+        let slow_and_risky = model.level(&[("quantum", 65), ("performance", 100)]);
+        let decision = policy.evaluate(&input(&slow_and_risky, &model, BackendType::BLS12_381));
+        assert!(!decision.should_morph);
+        assert_eq!(decision.rule.as_deref(), Some("quantum-elevated"));
+    }
+
+    #[test]
+    fn test_stale_feed_assumes_elevated() {
+        let model = ThreatModel::default();
+        let policy = MorphPolicy::from_thresholds(&model, 50, 20);
+
+        // A silent quantum feed must not read as "all clear"
+        let mut silent = ThreatLevel::normal(&model);
+        silent.stale.insert("quantum".to_string());
+        let explanation = policy.explain(&input(&silent, &model, BackendType::BN254));
+        assert_eq!(explanation.adjustments.len(), 1);
+        assert!(explanation.decision.should_morph);
+        assert_eq!(
+            explanation.decision.rule.as_deref(),
+            Some("quantum-elevated")
+        );
+
+        // ...nor trigger a recovery back to BN254
+        let decision = policy.evaluate(&input(&silent, &model, BackendType::BLS12_381));
+        assert!(!decision.should_morph);
+
+        let ignore = MorphPolicy {
+            stale_data: StaleData::Ignore,
+            ..policy.clone()
+        };
+        assert!(
+            !ignore
+                .evaluate(&input(&silent, &model, BackendType::BN254))
+                .should_morph
+        );
+
+        let mut strict = ignore;
+        strict.rules.insert(
+            0,
+            serde_json::from_str(
+                r#"{ "name": "stale-hold", "when": [{ "stale": "any" }], "reason": "Hold while blind" }"#,
+            )
+            .unwrap(),
+        );
+        strict.validate(&model).unwrap();
+        let decision = strict.evaluate(&input(&silent, &model, BackendType::BLS12_381));
+        assert_eq!(decision.rule.as_deref(), Some("stale-hold"));
+        assert!(!decision.should_morph);
+    }
+}
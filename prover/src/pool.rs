@@ -0,0 +1,250 @@
+// Backend keys, shared by everything that proves
+//
+// Each backend's Groth16 keys are set up once, on first use or ahead of it
+// on a background thread, and never change after that. The pool is shared
+// through an Arc between the controller and any number of proving threads,
+// so proofs do not need the controller and can carry on while it morphs.
+// Latency samples for setup, prove and verify are kept here too, since
+// that is where the work happens.
+
+use crate::bls12_381_backend::BLS12_381Backend;
+use crate::bn254_backend::BN254Backend;
+use crate::latency::{LatencyTracker, Operation};
+use crate::metrics;
+use crate::types::{BackendType, UniversalProof};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Keys for one backend, set up on a background thread
+enum WarmKeys {
+    Bn254(Box<BN254Backend>),
+    Bls12_381(Box<BLS12_381Backend>),
+}
+
+impl WarmKeys {
+    fn setup(backend: BackendType) -> Result<Self, String> {
+        match backend {
+            BackendType::BN254 => {
+                let mut keys = BN254Backend::new();
+                keys.setup()?;
+                Ok(WarmKeys::Bn254(Box::new(keys)))
+            }
+            BackendType::BLS12_381 => {
+                let mut keys = BLS12_381Backend::new();
+                keys.setup()?;
+                Ok(WarmKeys::Bls12_381(Box::new(keys)))
+            }
+        }
+    }
+}
+
+type WarmupHandle = JoinHandle<Result<(WarmKeys, Duration), String>>;
+
+struct Warmup {
+    backend: BackendType,
+    /// Taken by whichever thread joins it; others wait on this lock, not
+    /// on the pool's `warming` list
+    handle: Arc<Mutex<Option<WarmupHandle>>>,
+}
+
+#[derive(Default)]
+pub struct BackendPool {
+    /// Set up on first use, or ahead of it by `prewarm`
+    bn254: OnceLock<BN254Backend>,
+    bls12_381: OnceLock<BLS12_381Backend>,
+    /// Background setups not yet collected
+    warming: Mutex<Vec<Warmup>>,
+    /// Held while setting up in the foreground, so two threads needing the
+    /// same keys do not both generate them
+    setup: Mutex<()>,
+    /// Setup/prove/verify times per backend
+    latency: Mutex<LatencyTracker>,
+}
+
+impl BackendPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set up `backend` unless it has keys already, waiting for a warm-up
+    /// in progress rather than starting another
+    pub fn setup(&self, backend: BackendType) -> Result<(), String> {
+        if self.ready(backend) {
+            return Ok(());
+        }
+        let _setup = self.setup.lock().unwrap_or_else(|e| e.into_inner());
+        if self.has_keys(backend) {
+            return Ok(());
+        }
+        let start = Instant::now();
+        self.install(WarmKeys::setup(backend)?);
+        self.record_latency(backend, Operation::Setup, start);
+        metrics::global().record_key_load(backend, start.elapsed(), false);
+        Ok(())
+    }
+
+    /// Start setting up `backend` on a background thread. Returns false if
+    /// it has keys or a warm-up is already running.
+    pub fn prewarm(&self, backend: BackendType) -> bool {
+        let mut warming = self.warming();
+        if self.has_keys(backend) || warming.iter().any(|w| w.backend == backend) {
+            return false;
+        }
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+            WarmKeys::setup(backend).map(|keys| (keys, start.elapsed()))
+        });
+        warming.push(Warmup {
+            backend,
+            handle: Arc::new(Mutex::new(Some(handle))),
+        });
+        true
+    }
+
+    pub fn is_warming(&self, backend: BackendType) -> bool {
+        self.warming().iter().any(|w| w.backend == backend)
+    }
+
+    /// Whether `backend` has keys loaded; does not wait for warm-ups
+    pub fn has_keys(&self, backend: BackendType) -> bool {
+        match backend {
+            BackendType::BN254 => self.bn254.get().is_some(),
+            BackendType::BLS12_381 => self.bls12_381.get().is_some(),
+        }
+    }
+
+    /// Whether `backend` has keys, once any warm-up in progress finishes
+    pub fn ready(&self, backend: BackendType) -> bool {
+        self.finish_warmup(backend);
+        self.has_keys(backend)
+    }
+
+    /// Prove on `backend`, setting it up first if need be
+    pub fn prove(&self, backend: BackendType, a: u64, b: u64) -> Result<UniversalProof, String> {
+        let proof = self.prove_unrecorded(backend, a, b);
+        metrics::global().record_proof(backend, proof.is_ok());
+        proof
+    }
+
+    fn prove_unrecorded(
+        &self,
+        backend: BackendType,
+        a: u64,
+        b: u64,
+    ) -> Result<UniversalProof, String> {
+        self.setup(backend)?;
+        let start = Instant::now();
+        let proof = match backend {
+            BackendType::BN254 => {
+                let keys = self.bn254.get().ok_or("BN254 not initialized")?;
+                keys.prove(a, b)
+            }
+            BackendType::BLS12_381 => {
+                let keys = self.bls12_381.get().ok_or("BLS12-381 not initialized")?;
+                keys.prove(a, b)
+            }
+        }?;
+        self.record_latency(backend, Operation::Prove, start);
+        Ok(proof)
+    }
+
+    pub fn verify(&self, proof: &UniversalProof) -> Result<bool, String> {
+        // Fresh keys could not verify anything proven before, so no lazy setup
+        self.finish_warmup(proof.backend);
+        let start = Instant::now();
+        let valid = match proof.backend {
+            BackendType::BN254 => {
+                let keys = self.bn254.get().ok_or("BN254 not initialized")?;
+                keys.verify(proof)
+            }
+            BackendType::BLS12_381 => {
+                let keys = self.bls12_381.get().ok_or("BLS12-381 not initialized")?;
+                keys.verify(proof)
+            }
+        }?;
+        self.record_latency(proof.backend, Operation::Verify, start);
+        Ok(valid)
+    }
+
+    /// Compressed verifying key of `backend`, for verifying elsewhere
+    pub fn verifying_key(&self, backend: BackendType) -> Result<Vec<u8>, String> {
+        self.finish_warmup(backend);
+        match backend {
+            BackendType::BN254 => self
+                .bn254
+                .get()
+                .ok_or("BN254 not initialized")?
+                .verifying_key_bytes(),
+            BackendType::BLS12_381 => self
+                .bls12_381
+                .get()
+                .ok_or("BLS12-381 not initialized")?
+                .verifying_key_bytes(),
+        }
+    }
+
+    /// Rolling latencies observed so far
+    pub fn latency(&self) -> MutexGuard<'_, LatencyTracker> {
+        self.latency.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Keep the latest `window` samples per backend and operation
+    pub fn set_latency_window(&self, window: usize) {
+        *self.latency() = LatencyTracker::new(window);
+    }
+
+    fn record_latency(&self, backend: BackendType, operation: Operation, start: Instant) {
+        let took = start.elapsed();
+        self.latency().record(backend, operation, took);
+        metrics::global().observe_operation(backend, operation, took);
+    }
+
+    fn warming(&self) -> MutexGuard<'_, Vec<Warmup>> {
+        self.warming.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn install(&self, keys: WarmKeys) {
+        // A lost race leaves equally good keys in place
+        match keys {
+            WarmKeys::Bn254(keys) => {
+                self.bn254.set(*keys).ok();
+            }
+            WarmKeys::Bls12_381(keys) => {
+                self.bls12_381.set(*keys).ok();
+            }
+        }
+    }
+
+    /// Wait for a warm-up of `backend`, if one is running, and load its
+    /// keys. Other threads asking for the same backend wait on that
+    /// warm-up's lock; the `warming` list is only held to look it up, so
+    /// proofs on other backends carry on meanwhile.
+    fn finish_warmup(&self, backend: BackendType) {
+        let Some(slot) = self
+            .warming()
+            .iter()
+            .find(|w| w.backend == backend)
+            .map(|w| w.handle.clone())
+        else {
+            return;
+        };
+
+        let mut handle = slot.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(handle) = handle.take() {
+            match handle.join() {
+                Ok(Ok((keys, took))) => {
+                    self.install(keys);
+                    self.latency().record(backend, Operation::Setup, took);
+                    metrics::global().observe_operation(backend, Operation::Setup, took);
+                    metrics::global().record_key_load(backend, took, true);
+                }
+                Ok(Err(e)) => eprintln!("[WARN] {} warm-up failed: {}", backend.name(), e),
+                Err(_) => eprintln!("[WARN] {} warm-up panicked", backend.name()),
+            }
+        }
+        // Keys are installed before the warm-up leaves the list, so nobody
+        // sees neither and starts a second setup
+        self.warming().retain(|w| !Arc::ptr_eq(&w.handle, &slot));
+    }
+}
@@ -0,0 +1,242 @@
+// Projecting threshold crossings, so keys can be warmed up ahead of a morph
+//
+// Setup is the slow part of a morph: generating Groth16 keys for the
+// target curve takes far longer than proving with them. Rather than set up
+// every backend at start, the controller sets up lazily and uses this
+// projector to guess which backend it will need next. The projector fits a
+// least-squares line through the last few readings of each score that
+// drives the policy (thresholded categories and the overall score) and
+// reports the backend on the far side of any threshold the line crosses
+// within `horizon_cycles`.
+
+use crate::simulator::ThreatLevel;
+use crate::threat::ThreatModel;
+use crate::types::BackendType;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrewarmConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Warm up when a crossing is projected within this many readings
+    #[serde(default = "default_horizon_cycles")]
+    pub horizon_cycles: u32,
+    /// Readings the trend is fitted over
+    #[serde(default = "default_window")]
+    pub window: usize,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_horizon_cycles() -> u32 {
+    3
+}
+
+fn default_window() -> usize {
+    4
+}
+
+impl Default for PrewarmConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            horizon_cycles: default_horizon_cycles(),
+            window: default_window(),
+        }
+    }
+}
+
+/// A threshold the policy acts on, and the backend it sends us to
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Watch {
+    /// Category name or "overall"
+    score: String,
+    threshold: u32,
+    /// Crossed upwards (score reaches it) or downwards (drops below it)
+    rising: bool,
+    target: BackendType,
+}
+
+/// A crossing the trend points at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    pub target: BackendType,
+    pub score: String,
+    pub threshold: u32,
+    /// Readings until the line reaches the threshold; 0 if already there
+    pub cycles: f64,
+}
+
+pub struct TrendProjector {
+    config: PrewarmConfig,
+    watches: Vec<Watch>,
+    history: BTreeMap<String, VecDeque<u32>>,
+}
+
+impl TrendProjector {
+    /// Watch each category's elevated threshold and the overall
+    /// upgrade/recovery thresholds of `model`
+    pub fn new(
+        config: PrewarmConfig,
+        model: &ThreatModel,
+        threat_threshold: u32,
+        recovery_threshold: u32,
+    ) -> Self {
+        let watch = |score: &str, threshold, rising, target| Watch {
+            score: score.to_string(),
+            threshold,
+            rising,
+            target,
+        };
+        let mut watches: Vec<Watch> = model
+            .categories
+            .iter()
+            .filter_map(|c| {
+                let limits = c.thresholds?;
+                Some(watch(
+                    &c.name,
+                    limits.elevated,
+                    true,
+                    BackendType::BLS12_381,
+                ))
+            })
+            .collect();
+        watches.push(watch(
+            "overall",
+            threat_threshold,
+            true,
+            BackendType::BLS12_381,
+        ));
+        watches.push(watch(
+            "overall",
+            recovery_threshold,
+            false,
+            BackendType::BN254,
+        ));
+
+        Self {
+            config,
+            watches,
+            history: BTreeMap::new(),
+        }
+    }
+
+    /// Record a reading and return the nearest crossing projected within the
+    /// horizon that would take us off `current`
+    pub fn observe(&mut self, threat: &ThreatLevel, current: BackendType) -> Option<Projection> {
+        for watch in &self.watches {
+            self.history.entry(watch.score.clone()).or_default();
+        }
+        for (score, readings) in self.history.iter_mut() {
+            if readings.len() == self.config.window.max(2) {
+                readings.pop_front();
+            }
+            readings.push_back(match score.as_str() {
+                "overall" => threat.overall,
+                name => threat.score(name),
+            });
+        }
+
+        self.watches
+            .iter()
+            .filter(|w| w.target != current)
+            .filter_map(|w| {
+                let cycles = cycles_to(&self.history[&w.score], w.threshold, w.rising)?;
+                (cycles <= self.config.horizon_cycles as f64).then(|| Projection {
+                    target: w.target,
+                    score: w.score.clone(),
+                    threshold: w.threshold,
+                    cycles,
+                })
+            })
+            .min_by(|a, b| a.cycles.total_cmp(&b.cycles))
+    }
+}
+
+/// Readings until the least-squares line through `readings` reaches
+/// `threshold` in the given direction; `None` if it is heading away
+fn cycles_to(readings: &VecDeque<u32>, threshold: u32, rising: bool) -> Option<f64> {
+    let latest = *readings.back()? as f64;
+    let threshold = threshold as f64;
+    let crossed = if rising {
+        latest >= threshold
+    } else {
+        latest < threshold
+    };
+    if crossed {
+        return Some(0.0);
+    }
+    if readings.len() < 2 {
+        return None;
+    }
+
+    let n = readings.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = readings.iter().map(|r| *r as f64).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, y) in readings.iter().enumerate() {
+        let dx = x as f64 - mean_x;
+        covariance += dx * (*y as f64 - mean_y);
+        variance += dx * dx;
+    }
+    let slope = covariance / variance;
+    if (rising && slope <= 0.0) || (!rising && slope >= 0.0) {
+        return None;
+    }
+    Some((threshold - latest) / slope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn projector() -> TrendProjector {
+        TrendProjector::new(PrewarmConfig::default(), &ThreatModel::default(), 50, 20)
+    }
+
+    #[test]
+    fn test_rising_quantum_projects_upgrade() {
+        let model = ThreatModel::default();
+        let mut projector = projector();
+        let mut projections = Vec::new();
+        for quantum in [10, 20, 30, 40] {
+            let reading = model.level(&[("quantum", quantum)]);
+            projections.push(projector.observe(&reading, BackendType::BN254));
+        }
+        // 40 rising by 10 a reading reaches the elevated 60 in two
+        assert!(projections[..2].iter().all(Option::is_none));
+        let projection = projections[3].clone().unwrap();
+        assert_eq!(projection.target, BackendType::BLS12_381);
+        assert_eq!(projection.score, "quantum");
+        assert!((projection.cycles - 2.0).abs() < 1e-9);
+
+        // Already on BLS12-381: nothing to warm up
+        let reading = model.level(&[("quantum", 50)]);
+        assert!(projector
+            .observe(&reading, BackendType::BLS12_381)
+            .is_none());
+    }
+
+    #[test]
+    fn test_falling_threat_projects_recovery() {
+        let model = ThreatModel::default();
+        let mut projector = projector();
+        let mut last = None;
+        for quantum in [90, 90, 90, 90] {
+            let reading = model.level(&[("quantum", quantum)]);
+            last = projector.observe(&reading, BackendType::BLS12_381);
+        }
+        assert!(last.is_none());
+
+        for quantum in [70, 50] {
+            let reading = model.level(&[("quantum", quantum)]);
+            last = projector.observe(&reading, BackendType::BLS12_381);
+        }
+        let projection = last.unwrap();
+        assert_eq!(projection.target, BackendType::BN254);
+        assert_eq!(projection.score, "overall");
+    }
+}
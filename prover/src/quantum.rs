@@ -0,0 +1,331 @@
+// Shor resource estimates and the quantum threat score they imply
+//
+// Breaking either backend means solving a discrete log on an elliptic
+// curve over its base field. Roetteler, Naehrig, Svore and Lauter,
+// "Quantum resource estimates for computing elliptic curve discrete
+// logarithms" (ASIACRYPT 2017), cost that for an n-bit prime field at
+//
+//   logical qubits  9n + 2*ceil(log2 n) + 10
+//   Toffoli gates   448 n^3 log2 n + 4090 n^3
+//
+// A forecast of hardware by year (physical qubits, physical qubits per
+// logical qubit after error correction, logical Toffoli rate) is
+// interpolated log-linearly between its points and held flat after the
+// last one. The first year a machine has enough logical qubits and runs the
+// circuit within `max_runtime_days` is the break year; the years left until
+// then are the margin, and the quantum threat score rises from 0 at
+// `horizon_years` of margin to 100 at none.
+//
+// The shipped forecast is illustrative. Replace config/quantum_forecast.json
+// with whatever roadmap you trust.
+
+use crate::simulator::ThreatSimulator;
+use crate::types::BackendType;
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Logical resources Shor's algorithm needs against one backend's curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShorEstimate {
+    pub backend: BackendType,
+    /// Size of the base field the curve arithmetic runs in
+    pub field_bits: u32,
+    pub logical_qubits: u64,
+    pub toffoli_count: f64,
+}
+
+impl ShorEstimate {
+    pub fn for_backend(backend: BackendType) -> Self {
+        let field_bits = match backend {
+            BackendType::BN254 => 254,
+            BackendType::BLS12_381 => 381,
+        };
+        let n = field_bits as f64;
+        let log_n = n.log2();
+        Self {
+            backend,
+            field_bits,
+            logical_qubits: 9 * field_bits as u64 + 2 * log_n.ceil() as u64 + 10,
+            toffoli_count: 448.0 * n.powi(3) * log_n + 4090.0 * n.powi(3),
+        }
+    }
+
+    /// Wall time at `toffoli_per_second` logical Toffoli gates per second
+    pub fn runtime(&self, toffoli_per_second: f64) -> Duration {
+        Duration::from_secs_f64(self.toffoli_count / toffoli_per_second.max(f64::MIN_POSITIVE))
+    }
+}
+
+/// Hardware expected to exist in `year`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HardwarePoint {
+    pub year: u32,
+    pub physical_qubits: f64,
+    /// Error-correction overhead
+    pub physical_per_logical: f64,
+    /// Logical Toffoli gates per second, magic state distillation included
+    pub toffoli_per_second: f64,
+}
+
+impl HardwarePoint {
+    pub fn logical_qubits(&self) -> f64 {
+        self.physical_qubits / self.physical_per_logical
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuantumForecast {
+    pub points: Vec<HardwarePoint>,
+    /// Longest run that still counts as a practical attack
+    #[serde(default = "default_max_runtime_days")]
+    pub max_runtime_days: f64,
+    /// Margin at which the quantum score reaches 0
+    #[serde(default = "default_horizon_years")]
+    pub horizon_years: f64,
+}
+
+fn default_max_runtime_days() -> f64 {
+    30.0
+}
+
+fn default_horizon_years() -> f64 {
+    15.0
+}
+
+/// What a forecast means for one backend, as seen from `year`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantumAssessment {
+    pub estimate: ShorEstimate,
+    pub year: f64,
+    /// First year the attack is practical; `None` if not within the forecast
+    pub break_year: Option<f64>,
+    /// Years from `year` to `break_year`, 0 if already past it
+    pub margin_years: Option<f64>,
+    pub score: u32,
+}
+
+impl QuantumForecast {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read quantum forecast: {}", e))?;
+        let forecast: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse quantum forecast: {}", e))?;
+        forecast.validate()?;
+        Ok(forecast)
+    }
+
+    /// Load from default location, falling back to the built-in forecast
+    pub fn load_default() -> Result<Self, String> {
+        let paths = vec![
+            "config/quantum_forecast.json",
+            "../config/quantum_forecast.json",
+            "quantum_forecast.json",
+        ];
+
+        for path in paths {
+            if Path::new(path).exists() {
+                return Self::load(path);
+            }
+        }
+
+        Ok(Self::default())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.points.is_empty() {
+            return Err("Quantum forecast has no points".to_string());
+        }
+        if self.points.windows(2).any(|w| w[0].year >= w[1].year) {
+            return Err("Quantum forecast years must be strictly increasing".to_string());
+        }
+        for point in &self.points {
+            if point.physical_qubits <= 0.0
+                || point.physical_per_logical < 1.0
+                || point.toffoli_per_second <= 0.0
+            {
+                return Err(format!(
+                    "Quantum forecast for {}: qubits and Toffoli rate must be positive \
+                     and physical_per_logical at least 1",
+                    point.year
+                ));
+            }
+        }
+        if self.max_runtime_days <= 0.0 || self.horizon_years <= 0.0 {
+            return Err(
+                "Quantum forecast max_runtime_days and horizon_years must be positive".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Logical qubits and Toffoli rate in `year`, interpolated
+    /// log-linearly between forecast points and flat outside them
+    pub fn capability(&self, year: f64) -> (f64, f64) {
+        let first = &self.points[0];
+        let last = &self.points[self.points.len() - 1];
+        if year <= first.year as f64 {
+            return (first.logical_qubits(), first.toffoli_per_second);
+        }
+        if year >= last.year as f64 {
+            return (last.logical_qubits(), last.toffoli_per_second);
+        }
+
+        let segment = self
+            .points
+            .windows(2)
+            .find(|w| year < w[1].year as f64)
+            .expect("year is inside the forecast");
+        let (a, b) = (&segment[0], &segment[1]);
+        let t = (year - a.year as f64) / (b.year - a.year) as f64;
+        let lerp = |x: f64, y: f64| (x.ln() + t * (y.ln() - x.ln())).exp();
+        (
+            lerp(a.logical_qubits(), b.logical_qubits()),
+            lerp(a.toffoli_per_second, b.toffoli_per_second),
+        )
+    }
+
+    pub fn feasible(&self, estimate: &ShorEstimate, year: f64) -> bool {
+        let (logical, rate) = self.capability(year);
+        let max_runtime = Duration::from_secs_f64(self.max_runtime_days * 86_400.0);
+        logical >= estimate.logical_qubits as f64 && estimate.runtime(rate) <= max_runtime
+    }
+
+    /// First year, to a tenth, at which `estimate` is practical
+    pub fn break_year(&self, estimate: &ShorEstimate) -> Option<f64> {
+        let first = self.points[0].year * 10;
+        let last = self.points[self.points.len() - 1].year * 10;
+        (first..=last)
+            .map(|tenths| tenths as f64 / 10.0)
+            .find(|year| self.feasible(estimate, *year))
+    }
+
+    pub fn assess(&self, backend: BackendType, year: f64) -> QuantumAssessment {
+        let estimate = ShorEstimate::for_backend(backend);
+        let break_year = self.break_year(&estimate);
+        let margin_years = break_year.map(|at| (at - year).max(0.0));
+        let score = match margin_years {
+            Some(margin) => {
+                let share = 1.0 - margin / self.horizon_years;
+                (share.clamp(0.0, 1.0) * 100.0).round() as u32
+            }
+            None => 0,
+        };
+        QuantumAssessment {
+            estimate,
+            year,
+            break_year,
+            margin_years,
+            score,
+        }
+    }
+
+    /// Quantum threat score in `year`: that of the most exposed backend, so
+    /// the score does not depend on which backend we are on
+    pub fn quantum_score(&self, year: f64) -> u32 {
+        [BackendType::BN254, BackendType::BLS12_381]
+            .into_iter()
+            .map(|backend| self.assess(backend, year).score)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Ingest the quantum score for `year` into `simulator`
+    pub fn feed(&self, year: f64, simulator: &mut ThreatSimulator) -> Result<u32, String> {
+        let score = self.quantum_score(year);
+        simulator.ingest("quantum", score)?;
+        Ok(score)
+    }
+}
+
+impl Default for QuantumForecast {
+    /// Illustrative roadmap: a thousandfold growth in physical qubits per
+    /// decade with slowly falling error-correction overhead
+    fn default() -> Self {
+        let point =
+            |year, physical_qubits, physical_per_logical, toffoli_per_second| HardwarePoint {
+                year,
+                physical_qubits,
+                physical_per_logical,
+                toffoli_per_second,
+            };
+        Self {
+            points: vec![
+                point(2025, 1e3, 1000.0, 1e3),
+                point(2030, 1e5, 1000.0, 1e4),
+                point(2035, 1e6, 500.0, 1e5),
+                point(2040, 1e7, 500.0, 1e6),
+                point(2045, 1e8, 300.0, 1e6),
+            ],
+            max_runtime_days: default_max_runtime_days(),
+            horizon_years: default_horizon_years(),
+        }
+    }
+}
+
+/// Today as a fractional year, e.g. 2026.8
+pub fn current_year() -> f64 {
+    let today = chrono::Utc::now().date_naive();
+    today.year() as f64 + today.ordinal0() as f64 / 365.25
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shor_estimates_match_paper() {
+        // Table 1 of Roetteler et al. lists 2330 qubits for n = 256
+        let bn254 = ShorEstimate::for_backend(BackendType::BN254);
+        assert_eq!(bn254.logical_qubits, 2312);
+        let bls = ShorEstimate::for_backend(BackendType::BLS12_381);
+        assert_eq!(bls.logical_qubits, 3457);
+        assert!(bls.toffoli_count > 3.0 * bn254.toffoli_count);
+        assert!((bn254.toffoli_count / 1.26e11 - 1.0).abs() < 0.01);
+        assert_eq!(
+            bn254.runtime(1e6).as_secs(),
+            (bn254.toffoli_count / 1e6) as u64
+        );
+    }
+
+    #[test]
+    fn test_break_year_and_margin() {
+        let forecast = QuantumForecast::default();
+        forecast.validate().unwrap();
+
+        let (logical, _) = forecast.capability(2032.5);
+        assert!(logical > 100.0 && logical < 2000.0);
+
+        let bn254 = forecast.assess(BackendType::BN254, 2026.0);
+        let bls = forecast.assess(BackendType::BLS12_381, 2026.0);
+        let bn254_break = bn254.break_year.unwrap();
+        assert!((2035.0..2036.0).contains(&bn254_break));
+        assert!(bls.break_year.unwrap() > bn254_break);
+        assert!(bls.score < bn254.score);
+        assert_eq!(forecast.quantum_score(2026.0), bn254.score);
+
+        // Past the break year the margin is gone
+        assert_eq!(forecast.assess(BackendType::BN254, 2040.0).score, 100);
+
+        // A forecast that never gets there leaves no break year
+        let mut stalled = forecast.clone();
+        stalled.points.truncate(2);
+        assert_eq!(stalled.assess(BackendType::BN254, 2026.0).break_year, None);
+        assert_eq!(stalled.quantum_score(2026.0), 0);
+    }
+
+    #[test]
+    fn test_forecast_feeds_quantum_category() {
+        let forecast = QuantumForecast::default();
+        let mut simulator = ThreatSimulator::new(BackendType::BN254, 50);
+        let score = forecast.feed(2034.0, &mut simulator).unwrap();
+        assert!(score > 80);
+        assert_eq!(simulator.current_threat().quantum, score);
+
+        let mut unsorted = forecast.clone();
+        unsorted.points.swap(0, 1);
+        assert!(unsorted.validate().is_err());
+    }
+}
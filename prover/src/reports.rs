@@ -0,0 +1,580 @@
+// Signed threat reports
+//
+// Threat scores only count if they come from a registered reporter key,
+// enough reporters agree, and they are not far from what the others say.
+// ThreatAggregator stays an exact mirror of the on-chain oracle; this is
+// the hardened feed the prover itself trusts.
+//
+// A category's score is the mean of the reports that survive outlier
+// rejection (median absolute deviation), and only counts once `quorum`
+// reporters back it. Everything turned away is logged with the reason.
+
+use crate::simulator::ThreatLevel;
+use crate::threat::ThreatModel;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Domain separation for report signatures
+const SIGNING_CONTEXT: &str = "chameleon-threat-report/v1";
+
+/// Scale that makes MAD comparable to a standard deviation
+const MAD_SCALE: f64 = 1.4826;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedThreatReport {
+    pub reporter: String,
+    pub category: String,
+    pub score: u32,
+    /// Unix seconds; must increase per reporter and category
+    pub timestamp: u64,
+    /// Hex ed25519 signature over `signing_bytes()`
+    pub signature: String,
+}
+
+impl SignedThreatReport {
+    pub fn sign(
+        key: &SigningKey,
+        reporter: &str,
+        category: &str,
+        score: u32,
+        timestamp: u64,
+    ) -> Self {
+        let mut report = Self {
+            reporter: reporter.to_string(),
+            category: category.to_string(),
+            score,
+            timestamp,
+            signature: String::new(),
+        };
+        report.signature = hex::encode(key.sign(&report.signing_bytes()).to_bytes());
+        report
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            SIGNING_CONTEXT, self.reporter, self.category, self.score, self.timestamp
+        )
+        .into_bytes()
+    }
+
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), String> {
+        let bytes: [u8; 64] = hex::decode(&self.signature)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or("malformed signature")?;
+        key.verify(&self.signing_bytes(), &Signature::from_bytes(&bytes))
+            .map_err(|_| "bad signature".to_string())
+    }
+}
+
+/// Registered reporters and how their reports are combined, as stored in
+/// config/reporters.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReporterConfig {
+    /// Reporters needed before a category's score counts
+    pub quorum: usize,
+    /// Per-category overrides of `quorum`
+    #[serde(default)]
+    pub category_quorum: BTreeMap<String, usize>,
+    /// Reject reports more than this many scaled MADs from the median
+    pub mad_threshold: f64,
+    /// Reject reports timestamped further than this into the future
+    #[serde(default = "default_max_clock_skew_seconds")]
+    pub max_clock_skew_seconds: u64,
+    /// Reporter name -> hex ed25519 public key
+    pub reporters: BTreeMap<String, String>,
+}
+
+fn default_max_clock_skew_seconds() -> u64 {
+    300
+}
+
+impl ReporterConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read reporter config: {}", e))?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse reporter config: {}", e))
+    }
+
+    /// Load from default location
+    pub fn load_default() -> Result<Self, String> {
+        let paths = vec![
+            "config/reporters.json",
+            "../config/reporters.json",
+            "reporters.json",
+        ];
+
+        for path in paths {
+            if Path::new(path).exists() {
+                return Self::load(path);
+            }
+        }
+
+        Err("No reporter config found (config/reporters.json)".to_string())
+    }
+
+    pub fn quorum_for(&self, category: &str) -> usize {
+        self.category_quorum
+            .get(category)
+            .copied()
+            .unwrap_or(self.quorum)
+    }
+}
+
+pub fn parse_verifying_key(hex_key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(hex_key.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or("Public key must be 32 hex-encoded bytes")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))
+}
+
+pub fn parse_signing_key(hex_key: &str) -> Result<SigningKey, String> {
+    let bytes: [u8; 32] = hex::decode(hex_key.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or("Secret key must be 32 hex-encoded bytes")?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedReport {
+    pub reporter: String,
+    pub category: String,
+    pub score: u32,
+    pub timestamp: u64,
+    pub reason: String,
+}
+
+/// Result of combining the reports for one category
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryConsensus {
+    /// Mean of the accepted reports, if there were enough of them
+    pub score: Option<u32>,
+    /// Reporters whose reports were used
+    pub reporters: Vec<String>,
+    pub quorum: usize,
+    /// No quorum, or the newest accepted report is past the category's
+    /// `stale_after_seconds`
+    pub stale: bool,
+}
+
+pub struct ThreatFeed {
+    config: ReporterConfig,
+    keys: BTreeMap<String, VerifyingKey>,
+    model: ThreatModel,
+    /// (category, reporter) -> latest accepted report
+    latest: BTreeMap<(String, String), SignedThreatReport>,
+    /// Last score each category reached with quorum
+    last_scores: BTreeMap<String, u32>,
+    rejected: Vec<RejectedReport>,
+    /// Outliers already logged, so re-aggregating does not log them twice
+    logged_outliers: BTreeSet<(String, String, u64)>,
+    rejection_log: Option<PathBuf>,
+}
+
+impl ThreatFeed {
+    pub fn new(config: ReporterConfig, model: ThreatModel) -> Result<Self, String> {
+        let keys = config
+            .reporters
+            .iter()
+            .map(|(name, key)| {
+                parse_verifying_key(key)
+                    .map(|k| (name.clone(), k))
+                    .map_err(|e| format!("Reporter '{}': {}", name, e))
+            })
+            .collect::<Result<_, String>>()?;
+        if config.quorum == 0 {
+            return Err("Reporter quorum must be at least 1".to_string());
+        }
+
+        Ok(Self {
+            config,
+            keys,
+            model,
+            latest: BTreeMap::new(),
+            last_scores: BTreeMap::new(),
+            rejected: Vec::new(),
+            logged_outliers: BTreeSet::new(),
+            rejection_log: None,
+        })
+    }
+
+    /// Also append rejected reports to a JSONL file
+    pub fn set_rejection_log(&mut self, path: &str) {
+        self.rejection_log = Some(PathBuf::from(path));
+    }
+
+    pub fn rejected(&self) -> &[RejectedReport] {
+        &self.rejected
+    }
+
+    /// Check a report received at unix time `now` and keep it as its
+    /// reporter's latest for the category
+    pub fn submit(&mut self, report: SignedThreatReport, now: u64) -> Result<(), String> {
+        match self.check(&report, now) {
+            Ok(()) => {
+                self.latest
+                    .insert((report.category.clone(), report.reporter.clone()), report);
+                Ok(())
+            }
+            Err(reason) => {
+                self.reject(&report, &reason);
+                Err(reason)
+            }
+        }
+    }
+
+    fn check(&self, report: &SignedThreatReport, now: u64) -> Result<(), String> {
+        let key = self
+            .keys
+            .get(&report.reporter)
+            .ok_or("unregistered reporter")?;
+        report.verify(key)?;
+
+        if self.model.category(&report.category).is_none() {
+            return Err(format!("unknown category '{}'", report.category));
+        }
+        if report.score > 100 {
+            return Err("score must be 0-100".to_string());
+        }
+        // A report from the future would block the reporter's real ones
+        // until the clock caught up with it
+        if report.timestamp > now.saturating_add(self.config.max_clock_skew_seconds) {
+            return Err(format!(
+                "timestamp {}s in the future",
+                report.timestamp - now
+            ));
+        }
+        let previous = self
+            .latest
+            .get(&(report.category.clone(), report.reporter.clone()));
+        if let Some(previous) = previous {
+            if report.timestamp <= previous.timestamp {
+                return Err("replayed or out-of-order report".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn reject(&mut self, report: &SignedThreatReport, reason: &str) {
+        let rejected = RejectedReport {
+            reporter: report.reporter.clone(),
+            category: report.category.clone(),
+            score: report.score,
+            timestamp: report.timestamp,
+            reason: reason.to_string(),
+        };
+        eprintln!(
+            "[REJECTED] {} report from '{}' (score {}): {}",
+            rejected.category, rejected.reporter, rejected.score, rejected.reason
+        );
+
+        if let Some(path) = &self.rejection_log {
+            if let Err(e) = append_json_line(path, &rejected) {
+                eprintln!("[REJECTED] could not write rejection log: {}", e);
+            }
+        }
+        self.rejected.push(rejected);
+    }
+
+    /// Consensus for `category` at unix time `now`. Outliers are judged on
+    /// the reported scores; the mean is taken after decaying each report by
+    /// its age.
+    pub fn consensus(&mut self, category: &str, now: u64) -> CategoryConsensus {
+        let reports: Vec<SignedThreatReport> = self
+            .latest
+            .iter()
+            .filter(|((c, _), _)| c == category)
+            .map(|(_, r)| r.clone())
+            .collect();
+        let scores: Vec<u32> = reports.iter().map(|r| r.score).collect();
+        let keep = mad_filter(&scores, self.config.mad_threshold);
+
+        let mut accepted = Vec::new();
+        for (report, keep) in reports.iter().zip(keep) {
+            if keep {
+                accepted.push(report);
+                continue;
+            }
+            let id = (
+                report.category.clone(),
+                report.reporter.clone(),
+                report.timestamp,
+            );
+            if self.logged_outliers.insert(id) {
+                self.reject(report, "outlier: too far from the other reporters");
+            }
+        }
+
+        let quorum = self.config.quorum_for(category);
+        let age = |r: &SignedThreatReport| Duration::from_secs(now.saturating_sub(r.timestamp));
+        let score = (accepted.len() >= quorum && !accepted.is_empty()).then(|| {
+            accepted
+                .iter()
+                .map(|r| self.model.decayed(category, r.score, age(r)))
+                .sum::<u32>()
+                / accepted.len() as u32
+        });
+        let newest = accepted.iter().map(|r| age(r)).min();
+        let stale = score.is_none() || self.model.is_stale(category, newest);
+        if let Some(score) = score {
+            self.last_scores.insert(category.to_string(), score);
+        }
+
+        CategoryConsensus {
+            score,
+            reporters: accepted.iter().map(|r| r.reporter.clone()).collect(),
+            quorum,
+            stale,
+        }
+    }
+
+    /// Threat level at unix time `now` from every category's consensus.
+    /// A category short of quorum is marked stale and keeps the last score
+    /// it reached with quorum (0 if it never did), so the policy's
+    /// `stale_data` setting decides how much to trust it.
+    pub fn threat_level(&mut self, now: u64) -> (ThreatLevel, BTreeMap<String, CategoryConsensus>) {
+        let names: Vec<String> = self
+            .model
+            .categories
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        let consensus: BTreeMap<String, CategoryConsensus> = names
+            .into_iter()
+            .map(|name| {
+                let c = self.consensus(&name, now);
+                (name, c)
+            })
+            .collect();
+
+        let scores: Vec<(&str, u32)> = consensus
+            .iter()
+            .map(|(name, c)| {
+                let last = self.last_scores.get(name).copied();
+                (name.as_str(), c.score.or(last).unwrap_or(0))
+            })
+            .collect();
+        let mut level = self.model.level(&scores);
+        level.stale = consensus
+            .iter()
+            .filter(|(_, c)| c.stale)
+            .map(|(name, _)| name.clone())
+            .collect();
+        (level, consensus)
+    }
+}
+
+/// Which scores to keep: those within `threshold` scaled MADs of the
+/// median. With fewer than three reports there is no majority to compare
+/// against, so everything is kept. The MAD is floored at one point so a
+/// unanimous group does not reject a reporter who is off by one.
+pub fn mad_filter(scores: &[u32], threshold: f64) -> Vec<bool> {
+    if scores.len() < 3 {
+        return vec![true; scores.len()];
+    }
+
+    let values: Vec<f64> = scores.iter().map(|s| *s as f64).collect();
+    let med = median(&values);
+    let deviations: Vec<f64> = values.iter().map(|v| (v - med).abs()).collect();
+    let mad = (median(&deviations) * MAD_SCALE).max(1.0);
+
+    deviations.iter().map(|d| *d <= threshold * mad).collect()
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Read signed reports, one JSON object per line
+pub fn load_reports(path: &str) -> Result<Vec<SignedThreatReport>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read threat reports: {}", e))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| format!("Failed to parse report line {}: {}", n + 1, e))
+        })
+        .collect()
+}
+
+fn append_json_line<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+    }
+    let line = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn feed(quorum: usize) -> ThreatFeed {
+        let reporters = ["alice", "bob", "carol", "dave"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let public = key(i as u8 + 1).verifying_key();
+                (name.to_string(), hex::encode(public.to_bytes()))
+            })
+            .collect();
+        let config = ReporterConfig {
+            quorum,
+            category_quorum: BTreeMap::new(),
+            mad_threshold: 3.0,
+            max_clock_skew_seconds: default_max_clock_skew_seconds(),
+            reporters,
+        };
+        ThreatFeed::new(config, ThreatModel::default()).unwrap()
+    }
+
+    fn report(seed: u8, reporter: &str, category: &str, score: u32) -> SignedThreatReport {
+        SignedThreatReport::sign(&key(seed), reporter, category, score, 100)
+    }
+
+    #[test]
+    fn test_signatures_and_replays() {
+        let mut feed = feed(1);
+        feed.submit(report(1, "alice", "quantum", 40), 100).unwrap();
+
+        // Signed by bob's key but claiming to be alice
+        assert_eq!(
+            feed.submit(report(2, "alice", "quantum", 95), 100)
+                .unwrap_err(),
+            "bad signature"
+        );
+        let mut tampered = report(2, "bob", "quantum", 40);
+        tampered.score = 95;
+        assert!(feed.submit(tampered, 100).is_err());
+        assert!(feed
+            .submit(report(9, "mallory", "quantum", 95), 100)
+            .is_err());
+        // Same timestamp as alice's accepted report
+        assert!(feed.submit(report(1, "alice", "quantum", 95), 100).is_err());
+
+        assert_eq!(feed.rejected().len(), 4);
+        assert_eq!(feed.consensus("quantum", 100).score, Some(40));
+    }
+
+    #[test]
+    fn test_quorum_required() {
+        let mut feed = feed(2);
+        feed.submit(report(1, "alice", "quantum", 95), 100).unwrap();
+        let (level, consensus) = feed.threat_level(100);
+        assert_eq!(consensus["quantum"].score, None);
+        assert_eq!(level.quantum, 0);
+        assert!(level.stale.contains("quantum"));
+
+        feed.submit(report(2, "bob", "quantum", 85), 100).unwrap();
+        assert_eq!(feed.threat_level(100).0.quantum, 90);
+
+        // Losing quorum keeps the last agreed score rather than dropping to 0
+        feed.config.quorum = 3;
+        let (level, consensus) = feed.threat_level(100);
+        assert_eq!(consensus["quantum"].score, None);
+        assert_eq!(level.quantum, 90);
+        assert!(level.stale.contains("quantum"));
+    }
+
+    #[test]
+    fn test_future_reports_rejected() {
+        let mut feed = feed(1);
+        let ahead = SignedThreatReport::sign(&key(1), "alice", "quantum", 95, 100 + 3600);
+        let err = feed.submit(ahead, 100).unwrap_err();
+        assert!(err.contains("in the future"), "{}", err);
+
+        // Within the skew limit, and the real report after it still lands
+        let skewed = SignedThreatReport::sign(&key(1), "alice", "quantum", 30, 160);
+        feed.submit(skewed, 100).unwrap();
+        let real = SignedThreatReport::sign(&key(1), "alice", "quantum", 40, 200);
+        feed.submit(real, 200).unwrap();
+        assert_eq!(feed.consensus("quantum", 200).score, Some(40));
+    }
+
+    #[test]
+    fn test_single_compromised_feed_is_outvoted() {
+        let mut feed = feed(3);
+        feed.submit(report(1, "alice", "quantum", 20), 100).unwrap();
+        feed.submit(report(2, "bob", "quantum", 24), 100).unwrap();
+        feed.submit(report(3, "carol", "quantum", 22), 100).unwrap();
+        feed.submit(report(4, "dave", "quantum", 100), 100).unwrap();
+
+        let consensus = feed.consensus("quantum", 100);
+        assert_eq!(consensus.score, Some(22));
+        assert!(!consensus.reporters.contains(&"dave".to_string()));
+
+        // Logged once, however often we aggregate
+        feed.consensus("quantum", 100);
+        assert_eq!(feed.rejected().len(), 1);
+        assert!(feed.rejected()[0].reason.contains("outlier"));
+    }
+
+    #[test]
+    fn test_old_reports_decay_then_go_stale() {
+        let mut feed = feed(1);
+        let quantum = feed
+            .model
+            .categories
+            .iter_mut()
+            .find(|c| c.name == "quantum")
+            .unwrap();
+        quantum.freshness.half_life_seconds = Some(3600);
+        quantum.freshness.stale_after_seconds = Some(7200);
+
+        // Nothing reported yet: a silent feed, not an all-clear
+        assert!(feed.threat_level(100).0.stale.contains("quantum"));
+
+        feed.submit(report(1, "alice", "quantum", 80), 100).unwrap();
+        feed.submit(report(1, "alice", "regulatory", 10), 100)
+            .unwrap();
+        let consensus = feed.consensus("quantum", 100 + 3600);
+        assert_eq!(consensus.score, Some(40));
+        assert!(!consensus.stale);
+
+        let (level, _) = feed.threat_level(100 + 7201);
+        assert!(level.stale.contains("quantum"));
+        assert!(!level.stale.contains("regulatory"));
+    }
+
+    #[test]
+    fn test_mad_filter() {
+        assert_eq!(mad_filter(&[10, 90], 3.0), vec![true, true]);
+        assert_eq!(mad_filter(&[50, 50, 51], 3.0), vec![true, true, true]);
+        assert_eq!(
+            mad_filter(&[30, 35, 40, 32, 99], 3.0),
+            vec![true, true, true, true, false]
+        );
+    }
+}
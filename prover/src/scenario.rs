@@ -0,0 +1,631 @@
+// Time-series threat scenarios
+//
+// A scenario is a timeline of threat readings, one per cycle, generated
+// from a pattern or replayed from a CSV file. The runner feeds the timeline
+// to a ThreatSimulator cycle by cycle (on a simulated clock, so cooldowns
+// and per-hour limits behave as they would in production) and, if given a
+// controller, performs each morph the policy asks for. Assertions are then
+// checked against the resulting history.
+
+use crate::config::ThreatConfig;
+use crate::morph::{MorphContext, MorphController};
+use crate::optimizer::{Measurement, Optimizer, SimulatedCosts};
+use crate::simulator::{MorphDecision, ThreatLevel, ThreatSimulator};
+use crate::threat::ThreatModel;
+use crate::types::BackendType;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThreatPattern {
+    Rising,
+    Falling,
+    /// Up to 100 at the midpoint, then back down
+    RisingFalling,
+    /// Calm, a burst between 40% and 70% of the run, calm again
+    Spike,
+    /// Three ramps from 0 to 100
+    Sawtooth,
+    /// Bounded random walk, reproducible from the seed
+    RandomWalk,
+    /// Adversarial: quantum flips between 90 and 10 every cycle
+    Oscillate,
+    /// Adversarial: quantum kept within 3 points of 60, reproducible from
+    /// the seed
+    ThresholdHug,
+    /// Per-cycle category scores read from a CSV file
+    Replay(Vec<BTreeMap<String, u32>>),
+}
+
+impl ThreatPattern {
+    /// Pattern by name as used in threat_config.json; `csv:<path>` replays
+    /// a file
+    pub fn parse(name: &str) -> Result<Self, String> {
+        if let Some(path) = name.strip_prefix("csv:") {
+            return Self::from_csv(path);
+        }
+
+        match name.to_lowercase().as_str() {
+            "rising" | "linear" => Ok(ThreatPattern::Rising),
+            "falling" => Ok(ThreatPattern::Falling),
+            "rising_falling" => Ok(ThreatPattern::RisingFalling),
+            "spike" | "sudden_spike" => Ok(ThreatPattern::Spike),
+            "sawtooth" => Ok(ThreatPattern::Sawtooth),
+            "random_walk" | "random" => Ok(ThreatPattern::RandomWalk),
+            "oscillate" | "flapping" => Ok(ThreatPattern::Oscillate),
+            "threshold_hug" => Ok(ThreatPattern::ThresholdHug),
+            _ => Err(format!(
+                "Unknown threat pattern '{}' (expected rising, falling, rising_falling, \
+                 spike, sawtooth, random_walk, oscillate, threshold_hug or csv:<path>)",
+                name
+            )),
+        }
+    }
+
+    /// Load a CSV with a header row of category names. A `cycle` column,
+    /// if present, is ignored; rows are replayed in file order.
+    pub fn from_csv(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read scenario CSV: {}", e))?;
+        Self::parse_csv(&content)
+    }
+
+    pub fn parse_csv(content: &str) -> Result<Self, String> {
+        let mut lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+
+        let header: Vec<String> = lines
+            .next()
+            .ok_or("Scenario CSV is empty")?
+            .split(',')
+            .map(|h| h.trim().to_lowercase())
+            .collect();
+
+        let mut rows = Vec::new();
+        for (n, line) in lines.enumerate() {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != header.len() {
+                return Err(format!(
+                    "Scenario CSV row {}: expected {} fields, found {}",
+                    n + 1,
+                    header.len(),
+                    fields.len()
+                ));
+            }
+
+            let mut row = BTreeMap::new();
+            for (name, field) in header.iter().zip(fields) {
+                if name == "cycle" {
+                    continue;
+                }
+                let score: u32 = field.parse().map_err(|_| {
+                    format!("Scenario CSV row {}: bad {} score '{}'", n + 1, name, field)
+                })?;
+                row.insert(name.clone(), score.min(100));
+            }
+            rows.push(row);
+        }
+
+        if rows.is_empty() {
+            return Err("Scenario CSV has no rows".to_string());
+        }
+        Ok(ThreatPattern::Replay(rows))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThreatPattern::Rising => "rising",
+            ThreatPattern::Falling => "falling",
+            ThreatPattern::RisingFalling => "rising_falling",
+            ThreatPattern::Spike => "spike",
+            ThreatPattern::Sawtooth => "sawtooth",
+            ThreatPattern::RandomWalk => "random_walk",
+            ThreatPattern::Oscillate => "oscillate",
+            ThreatPattern::ThresholdHug => "threshold_hug",
+            ThreatPattern::Replay(_) => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub pattern: ThreatPattern,
+    /// Ignored for CSV replays, which run one cycle per row
+    pub cycles: u32,
+    /// Simulated time between two readings
+    pub interval: Duration,
+    /// Seed for `RandomWalk` and `ThresholdHug`
+    pub seed: u64,
+}
+
+impl Scenario {
+    /// Pattern, cycle count and interval from simulator/threat_config.json
+    pub fn from_config(config: &ThreatConfig) -> Result<Self, String> {
+        Ok(Self {
+            pattern: ThreatPattern::parse(&config.threat_pattern)?,
+            cycles: config.total_cycles,
+            interval: Duration::from_secs(config.check_interval_seconds as u64),
+            seed: 1,
+        })
+    }
+
+    /// The threat reading for every cycle
+    pub fn timeline(&self, model: &ThreatModel) -> Vec<ThreatLevel> {
+        if let ThreatPattern::Replay(rows) = &self.pattern {
+            return rows
+                .iter()
+                .map(|row| {
+                    let scores: Vec<(&str, u32)> = row
+                        .iter()
+                        .map(|(name, score)| (name.as_str(), *score))
+                        .collect();
+                    model.level(&scores)
+                })
+                .collect();
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut walk = 20i64;
+        (1..=self.cycles)
+            .map(|cycle| {
+                let intensity = match self.pattern {
+                    ThreatPattern::RandomWalk => {
+                        walk = (walk + rng.gen_range(-15..=15)).clamp(0, 100);
+                        walk as u32
+                    }
+                    ThreatPattern::ThresholdHug => {
+                        60u32.saturating_add_signed(rng.gen_range(-3..=3))
+                    }
+                    _ => intensity(&self.pattern, cycle, self.cycles),
+                };
+                let scores: Vec<(&str, u32)> = model
+                    .categories
+                    .iter()
+                    .map(|c| (c.name.as_str(), category_score(&c.name, intensity)))
+                    .collect();
+                model.level(&scores)
+            })
+            .collect()
+    }
+}
+
+/// Peak-relative intensity (0-100) of a generated pattern at `cycle`
+/// (1-based), using the same shapes as scripts/threat_simulator.sh
+fn intensity(pattern: &ThreatPattern, cycle: u32, total: u32) -> u32 {
+    let total = total.max(1);
+    let mid = (total / 2).max(1);
+
+    match pattern {
+        ThreatPattern::Rising => cycle * 100 / total,
+        ThreatPattern::Falling => 100 - cycle * 100 / total,
+        ThreatPattern::RisingFalling => {
+            if cycle <= mid {
+                cycle * 100 / mid
+            } else {
+                let remain = (total - mid).max(1);
+                100u32.saturating_sub((cycle - mid) * 100 / remain)
+            }
+        }
+        ThreatPattern::Spike => {
+            if cycle < total * 40 / 100 {
+                10
+            } else if cycle < total * 70 / 100 {
+                95
+            } else {
+                8
+            }
+        }
+        ThreatPattern::Sawtooth => {
+            let period = (total / 3).max(2);
+            ((cycle - 1) % period + 1) * 100 / period
+        }
+        ThreatPattern::Oscillate => {
+            if cycle % 2 == 1 {
+                90
+            } else {
+                10
+            }
+        }
+        ThreatPattern::RandomWalk | ThreatPattern::ThresholdHug | ThreatPattern::Replay(_) => 0,
+    }
+}
+
+/// Category scores follow the pattern at different strengths, as in the
+/// shell simulator: quantum leads, performance stays flat
+fn category_score(category: &str, intensity: u32) -> u32 {
+    match category {
+        "quantum" => intensity,
+        "regulatory" => intensity * 60 / 100,
+        "performance" => 20,
+        _ => intensity * 40 / 100,
+    }
+}
+
+/// What happened in one cycle
+#[derive(Debug, Clone)]
+pub struct CycleRecord {
+    /// 1-based
+    pub cycle: u32,
+    /// Simulated time of the reading
+    pub at: Duration,
+    pub threat: ThreatLevel,
+    pub decision: MorphDecision,
+    /// Backend before and after the cycle
+    pub from: BackendType,
+    pub backend: BackendType,
+    /// Set if the controller refused or failed the morph
+    pub error: Option<String>,
+    /// Backend the controller started warming up after this cycle
+    pub prewarmed: Option<BackendType>,
+    /// Auto-morph was frozen by the flapping detector after this cycle
+    pub frozen: bool,
+}
+
+impl CycleRecord {
+    pub fn morphed(&self) -> bool {
+        self.from != self.backend
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScenarioReport {
+    pub cycles: Vec<CycleRecord>,
+}
+
+impl ScenarioReport {
+    pub fn morphs(&self) -> impl Iterator<Item = &CycleRecord> {
+        self.cycles.iter().filter(|c| c.morphed())
+    }
+
+    pub fn morph_count(&self) -> usize {
+        self.morphs().count()
+    }
+
+    pub fn final_backend(&self) -> Option<BackendType> {
+        self.cycles.last().map(|c| c.backend)
+    }
+}
+
+/// Run `scenario` through `simulator`, switching the simulator to a
+/// simulated clock. With a controller, every morph the simulator decides
+/// on is carried out for real; a refused or failed morph leaves the
+/// simulator on its old backend. The controller's own cooldown runs on wall
+/// time, so give it a zero cooldown and let the simulator enforce it.
+pub fn run(
+    scenario: &Scenario,
+    simulator: &mut ThreatSimulator,
+    controller: Option<&mut MorphController>,
+) -> ScenarioReport {
+    run_with(scenario, simulator, None, controller)
+}
+
+/// Like `run`, but `optimizer` picks the backend each cycle instead of the
+/// policy. After every cycle it learns from a proof the controller times on
+/// the backend it ended up on, or without a controller from a measurement
+/// `world` makes.
+pub fn run_optimized(
+    scenario: &Scenario,
+    simulator: &mut ThreatSimulator,
+    optimizer: &mut Optimizer,
+    world: &mut SimulatedCosts,
+    controller: Option<&mut MorphController>,
+) -> ScenarioReport {
+    run_with(scenario, simulator, Some((optimizer, world)), controller)
+}
+
+fn run_with(
+    scenario: &Scenario,
+    simulator: &mut ThreatSimulator,
+    mut learner: Option<(&mut Optimizer, &mut SimulatedCosts)>,
+    mut controller: Option<&mut MorphController>,
+) -> ScenarioReport {
+    simulator.use_simulated_clock();
+    let timeline = scenario.timeline(&simulator.model);
+    let mut report = ScenarioReport::default();
+
+    for (i, threat) in timeline.into_iter().enumerate() {
+        let at = simulator.now();
+        let from = simulator.current_backend;
+        let decision = match learner.as_mut() {
+            Some((optimizer, _)) => {
+                simulator.observe(&threat);
+                let decision = optimizer.decide(&threat, from, &simulator.model);
+                simulator.apply(decision)
+            }
+            None => simulator.step(&threat),
+        };
+
+        let mut error = None;
+        let mut prewarmed = None;
+        if let Some(controller) = controller.as_deref_mut() {
+            if decision.should_morph {
+                let context = MorphContext::threat_policy(&threat, &decision);
+                if let Err(e) = controller.morph_with(decision.recommended_backend, context) {
+                    simulator.current_backend = from;
+                    error = Some(e);
+                }
+            }
+            prewarmed = controller.anticipate(&threat).map(|p| p.target);
+        }
+
+        report.cycles.push(CycleRecord {
+            cycle: i as u32 + 1,
+            at,
+            threat,
+            decision,
+            from,
+            backend: simulator.current_backend,
+            error,
+            prewarmed,
+            frozen: simulator
+                .detector
+                .as_ref()
+                .is_some_and(|d| d.frozen().is_some()),
+        });
+        if let Some((optimizer, world)) = learner.as_mut() {
+            let measurement = match controller.as_deref() {
+                Some(controller) => measure_proof(controller).unwrap_or_else(|e| {
+                    eprintln!(
+                        "[WARN] Could not time a proof, using simulated costs: {}",
+                        e
+                    );
+                    world.measure(simulator.current_backend)
+                }),
+                None => world.measure(simulator.current_backend),
+            };
+            optimizer.observe(&measurement);
+        }
+        simulator.advance(scenario.interval);
+    }
+
+    report
+}
+
+/// Prove once on the controller's backend and time it
+fn measure_proof(controller: &MorphController) -> Result<Measurement, String> {
+    let started = Instant::now();
+    let proof = controller.prove(3, 7)?;
+    Ok(Measurement::from_proof(&proof, started.elapsed()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Assertion {
+    /// On `backend` no more than `cycles` cycles after the policy first
+    /// asked for it
+    MorphedWithin {
+        backend: BackendType,
+        cycles: u32,
+    },
+    /// No window of length `window` contains more than `max` morphs
+    MaxMorphsPer {
+        max: usize,
+        window: Duration,
+    },
+    MaxMorphs(usize),
+    EndsOn(BackendType),
+    /// Auto-morph frozen by the flapping detector by cycle `n`
+    FrozenWithin(u32),
+    NeverFrozen,
+}
+
+impl Assertion {
+    /// `within:<backend>:<cycles>`, `rate:<max>/<seconds>`,
+    /// `max-morphs:<n>`, `ends-on:<backend>`, `frozen-within:<cycles>` or
+    /// `never-frozen`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let bad = || format!("Bad assertion '{}'", text);
+        let backend = |name: &str| {
+            BackendType::parse(name).ok_or_else(|| format!("Unknown backend '{}'", name))
+        };
+        let parts: Vec<&str> = text.split(':').collect();
+
+        match parts.as_slice() {
+            ["within", name, cycles] => Ok(Assertion::MorphedWithin {
+                backend: backend(name)?,
+                cycles: cycles.parse().map_err(|_| bad())?,
+            }),
+            ["rate", spec] => {
+                let (max, seconds) = spec.split_once('/').ok_or_else(bad)?;
+                Ok(Assertion::MaxMorphsPer {
+                    max: max.parse().map_err(|_| bad())?,
+                    window: Duration::from_secs(seconds.parse().map_err(|_| bad())?),
+                })
+            }
+            ["max-morphs", n] => Ok(Assertion::MaxMorphs(n.parse().map_err(|_| bad())?)),
+            ["ends-on", name] => Ok(Assertion::EndsOn(backend(name)?)),
+            ["frozen-within", n] => Ok(Assertion::FrozenWithin(n.parse().map_err(|_| bad())?)),
+            ["never-frozen"] => Ok(Assertion::NeverFrozen),
+            _ => Err(bad()),
+        }
+    }
+
+    pub fn check(&self, report: &ScenarioReport) -> Result<(), String> {
+        match self {
+            Assertion::MorphedWithin { backend, cycles } => {
+                let asked = report
+                    .cycles
+                    .iter()
+                    .find(|c| c.from != *backend && c.decision.recommended_backend == *backend)
+                    .ok_or_else(|| format!("policy never asked for {}", backend.name()))?;
+                let reached = report
+                    .cycles
+                    .iter()
+                    .skip_while(|c| c.cycle < asked.cycle)
+                    .find(|c| c.backend == *backend)
+                    .ok_or_else(|| {
+                        format!(
+                            "asked for {} at cycle {} but never got there",
+                            backend.name(),
+                            asked.cycle
+                        )
+                    })?;
+                let took = reached.cycle - asked.cycle;
+                if took > *cycles {
+                    return Err(format!(
+                        "asked at cycle {}, reached at cycle {} ({} cycles)",
+                        asked.cycle, reached.cycle, took
+                    ));
+                }
+                Ok(())
+            }
+            Assertion::MaxMorphsPer { max, window } => {
+                let times: Vec<Duration> = report.morphs().map(|c| c.at).collect();
+                for (i, start) in times.iter().enumerate() {
+                    let count = times[i..].iter().filter(|t| **t < *start + *window).count();
+                    if count > *max {
+                        return Err(format!(
+                            "{} morphs within {}s starting at t={}s",
+                            count,
+                            window.as_secs(),
+                            start.as_secs()
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Assertion::MaxMorphs(max) => {
+                let count = report.morph_count();
+                if count > *max {
+                    return Err(format!("{} morphs", count));
+                }
+                Ok(())
+            }
+            Assertion::EndsOn(backend) => match report.final_backend() {
+                Some(last) if last == *backend => Ok(()),
+                Some(last) => Err(format!("ended on {}", last.name())),
+                None => Err("scenario had no cycles".to_string()),
+            },
+            Assertion::FrozenWithin(cycles) => match report.cycles.iter().find(|c| c.frozen) {
+                Some(c) if c.cycle <= *cycles => Ok(()),
+                Some(c) => Err(format!("frozen at cycle {}", c.cycle)),
+                None => Err("never frozen".to_string()),
+            },
+            Assertion::NeverFrozen => match report.cycles.iter().find(|c| c.frozen) {
+                Some(c) => Err(format!("frozen at cycle {}", c.cycle)),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assertion::MorphedWithin { backend, cycles } => {
+                write!(f, "morphed to {} within {} cycles", backend.name(), cycles)
+            }
+            Assertion::MaxMorphsPer { max, window } => {
+                write!(f, "at most {} morphs per {}s", max, window.as_secs())
+            }
+            Assertion::MaxMorphs(max) => write!(f, "at most {} morphs", max),
+            Assertion::EndsOn(backend) => write!(f, "ends on {}", backend.name()),
+            Assertion::FrozenWithin(cycles) => {
+                write!(f, "auto-morph frozen within {} cycles", cycles)
+            }
+            Assertion::NeverFrozen => write!(f, "auto-morph never frozen"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flapping::FlapConfig;
+
+    fn scenario(pattern: ThreatPattern, cycles: u32) -> Scenario {
+        Scenario {
+            pattern,
+            cycles,
+            interval: Duration::from_secs(60),
+            seed: 7,
+        }
+    }
+
+    fn simulator(cooldown_seconds: u64) -> ThreatSimulator {
+        let mut sim = ThreatSimulator::new(BackendType::BN254, 50);
+        sim.recovery_threshold = 20;
+        sim.cooldown = Duration::from_secs(cooldown_seconds);
+        sim
+    }
+
+    #[test]
+    fn test_patterns_shapes() {
+        let model = ThreatModel::default();
+
+        let rising_falling = scenario(ThreatPattern::RisingFalling, 20).timeline(&model);
+        assert_eq!(rising_falling.len(), 20);
+        assert_eq!(rising_falling[9].quantum, 100);
+        assert!(rising_falling[0].quantum < rising_falling[5].quantum);
+        assert!(rising_falling[19].quantum < rising_falling[12].quantum);
+
+        let walk = scenario(ThreatPattern::RandomWalk, 50);
+        assert_eq!(
+            walk.timeline(&model)[49].quantum,
+            walk.timeline(&model)[49].quantum
+        );
+
+        let replay =
+            ThreatPattern::parse_csv("cycle,quantum,regulatory\n1,90,10\n2,5,5\n").unwrap();
+        let timeline = scenario(replay, 0).timeline(&model);
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].quantum, 90);
+    }
+
+    #[test]
+    fn test_rising_falling_assertions() {
+        let mut sim = simulator(0);
+        let report = run(&scenario(ThreatPattern::RisingFalling, 20), &mut sim, None);
+
+        for assertion in ["within:bls12-381:0", "max-morphs:2", "ends-on:bn254"] {
+            let assertion = Assertion::parse(assertion).unwrap();
+            assert!(assertion.check(&report).is_ok(), "{} failed", assertion);
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_uses_simulated_time() {
+        // Sawtooth crosses both thresholds every few cycles
+        let pattern = ThreatPattern::Sawtooth;
+        let rate = Assertion::parse("rate:2/3600").unwrap();
+
+        let report = run(&scenario(pattern.clone(), 30), &mut simulator(0), None);
+        assert!(report.morph_count() > 2);
+        assert!(rate.check(&report).is_err());
+
+        let report = run(&scenario(pattern, 30), &mut simulator(1800), None);
+        assert!(rate.check(&report).is_ok());
+    }
+
+    #[test]
+    fn test_adversarial_feeds_freeze_auto_morph() {
+        let frozen = Assertion::parse("frozen-within:10").unwrap();
+        for pattern in [ThreatPattern::Oscillate, ThreatPattern::ThresholdHug] {
+            let mut sim = simulator(0);
+            sim.enable_flap_detection(&FlapConfig::default());
+            let report = run(&scenario(pattern.clone(), 30), &mut sim, None);
+            assert!(
+                frozen.check(&report).is_ok(),
+                "{} not frozen",
+                pattern.name()
+            );
+            // Nothing moves once frozen
+            let at = report.cycles.iter().position(|c| c.frozen).unwrap();
+            assert!(report.cycles[at + 1..].iter().all(|c| !c.morphed()));
+
+            assert!(sim.resume_auto_morph().is_some());
+        }
+
+        // A genuine rise and fall is left alone
+        let mut sim = simulator(0);
+        sim.enable_flap_detection(&FlapConfig::default());
+        let report = run(&scenario(ThreatPattern::RisingFalling, 20), &mut sim, None);
+        assert!(Assertion::NeverFrozen.check(&report).is_ok());
+        assert_eq!(report.morph_count(), 2);
+    }
+}
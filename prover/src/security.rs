@@ -0,0 +1,192 @@
+// Classical security estimates per curve
+//
+// A pairing-friendly curve can be attacked in two places: the discrete log
+// in its prime-order subgroups, by Pollard rho, or the discrete log in the
+// target field F_{p^k} the pairing maps into, by the (special) extended
+// tower number field sieve. The curve is as strong as the cheaper of the
+// two.
+//
+// - Pollard rho takes sqrt(pi r / 4) group operations for a subgroup of
+//   order r [Pollard 1978].
+// - exTNFS runs in L_Q[1/3, c] for Q = p^k, with c = (48/9)^(1/3) in
+//   general and (32/9)^(1/3) when p comes from a low-weight polynomial, as
+//   for BN and BLS curves [Kim-Barbulescu 2016].
+//
+// The L-notation drops an o(1) term worth several bits at these sizes, so
+// the sieve estimate is lowered by a fixed offset anchored on BN254, the
+// most studied case, which works out to about 2^103 [Barbulescu-Duquesne
+// 2019]. Other sizes are extrapolated from there; for BLS12-381 this gives
+// a little less than the ~126 bits usually quoted, i.e. errs low.
+
+use crate::types::BackendType;
+use std::f64::consts::{LN_2, PI};
+use std::fmt;
+
+pub const POLLARD_RHO: &str = "J. M. Pollard, Monte Carlo methods for index computation (mod p), \
+                               Mathematics of Computation 32 (1978)";
+pub const EXTNFS: &str = "T. Kim, R. Barbulescu, Extended Tower Number Field Sieve: A New \
+                          Complexity for the Medium Prime Case, CRYPTO 2016";
+pub const KEY_SIZES: &str = "R. Barbulescu, S. Duquesne, Updating Key Size Estimations for \
+                             Pairings, Journal of Cryptology 32 (2019)";
+
+/// Bits the sieve's L-notation overestimates by, so that BN254 comes out
+/// at 2^103
+const TNFS_OFFSET_BITS: f64 = 6.5;
+
+/// What the estimate needs to know about a curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveParams {
+    pub name: &'static str,
+    /// Bits of the base field prime p
+    pub p_bits: u32,
+    /// Bits of the prime subgroup order r
+    pub r_bits: u32,
+    pub embedding_degree: u32,
+    /// p is the value of a low-weight polynomial (BN, BLS), which lets the
+    /// special variant of the sieve apply
+    pub special_p: bool,
+}
+
+impl CurveParams {
+    pub fn for_backend(backend: BackendType) -> Self {
+        match backend {
+            BackendType::BN254 => Self {
+                name: "BN254",
+                p_bits: 254,
+                r_bits: 254,
+                embedding_degree: 12,
+                special_p: true,
+            },
+            BackendType::BLS12_381 => Self {
+                name: "BLS12-381",
+                p_bits: 381,
+                r_bits: 255,
+                embedding_degree: 12,
+                special_p: true,
+            },
+        }
+    }
+
+    /// Bits of the pairing target field F_{p^k}
+    pub fn target_field_bits(&self) -> u32 {
+        self.p_bits * self.embedding_degree
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attack {
+    PollardRho,
+    ExTnfs,
+}
+
+impl Attack {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Attack::PollardRho => "Pollard rho",
+            Attack::ExTnfs => "exTNFS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityEstimate {
+    pub curve: CurveParams,
+    /// log2 of the cost of each attack
+    pub rho_bits: f64,
+    pub tnfs_bits: f64,
+    /// The cheaper of the two, rounded down
+    pub bits: u32,
+    pub limiting: Attack,
+    pub citations: [&'static str; 3],
+}
+
+impl SecurityEstimate {
+    pub fn estimate(curve: CurveParams) -> Self {
+        let rho_bits = pollard_rho_bits(curve.r_bits);
+        let tnfs_bits = extnfs_bits(curve.target_field_bits(), curve.special_p);
+        let (cost, limiting) = if tnfs_bits < rho_bits {
+            (tnfs_bits, Attack::ExTnfs)
+        } else {
+            (rho_bits, Attack::PollardRho)
+        };
+        Self {
+            curve,
+            rho_bits,
+            tnfs_bits,
+            bits: cost.floor() as u32,
+            limiting,
+            citations: [POLLARD_RHO, EXTNFS, KEY_SIZES],
+        }
+    }
+
+    pub fn for_backend(backend: BackendType) -> Self {
+        Self::estimate(CurveParams::for_backend(backend))
+    }
+}
+
+impl fmt::Display for SecurityEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-bit ({}; rho 2^{:.1}, exTNFS in {}-bit F_p^{} 2^{:.1})",
+            self.bits,
+            self.limiting.name(),
+            self.rho_bits,
+            self.curve.target_field_bits(),
+            self.curve.embedding_degree,
+            self.tnfs_bits
+        )
+    }
+}
+
+/// log2 of sqrt(pi r / 4) for an `r_bits`-bit subgroup order
+pub fn pollard_rho_bits(r_bits: u32) -> f64 {
+    (r_bits as f64 + (PI / 4.0).log2()) / 2.0
+}
+
+/// log2 of L_Q[1/3, c] for a `q_bits`-bit target field, less the offset
+pub fn extnfs_bits(q_bits: u32, special_p: bool) -> f64 {
+    let c: f64 = if special_p { 32.0 / 9.0 } else { 48.0 / 9.0 };
+    let ln_q = q_bits as f64 * LN_2;
+    let nats = c.cbrt() * ln_q.cbrt() * ln_q.ln().powf(2.0 / 3.0);
+    nats / LN_2 - TNFS_OFFSET_BITS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bn254_limited_by_tnfs() {
+        let estimate = SecurityEstimate::for_backend(BackendType::BN254);
+        assert_eq!(estimate.curve.target_field_bits(), 3048);
+        assert_eq!(estimate.limiting, Attack::ExTnfs);
+        assert_eq!(estimate.bits, 103);
+        assert!((estimate.rho_bits - 126.8).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_bls12_381_stronger_than_bn254() {
+        let bls = SecurityEstimate::for_backend(BackendType::BLS12_381);
+        let bn254 = SecurityEstimate::for_backend(BackendType::BN254);
+        assert!(bls.bits > bn254.bits + 15);
+        assert!(bls.bits < 128);
+        assert!(bls.to_string().contains("4572-bit F_p^12"));
+    }
+
+    #[test]
+    fn test_rho_limits_a_large_embedding_degree() {
+        // Same subgroup as BLS12-381, far bigger target field: the sieve is
+        // out of reach and rho sets the level
+        let curve = CurveParams {
+            name: "toy-k24",
+            p_bits: 509,
+            r_bits: 255,
+            embedding_degree: 24,
+            special_p: false,
+        };
+        let estimate = SecurityEstimate::estimate(curve);
+        assert_eq!(estimate.limiting, Attack::PollardRho);
+        assert_eq!(estimate.bits, 127);
+    }
+}
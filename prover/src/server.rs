@@ -0,0 +1,1233 @@
+// `chameleon serve`: the prover as a long-running HTTP/JSON daemon
+//
+// Keys are set up once at start (or warmed up ahead of a projected morph)
+// and kept for the life of the process. Each connection gets its own
+// thread, up to `server.max_connections`; proofs go through the
+// ConcurrentController, so a morph drains them rather than waiting for a
+// quiet moment.
+//
+//   POST /prove     {"a": 3, "b": 7, "jurisdiction": "EU"}  -> UniversalProof
+//   POST /verify    UniversalProof                          -> {"valid": true}
+//   POST /morph     {"to": "bls12-381", "reason": "...", "emergency": false}
+//                   with `Authorization: Bearer <token>` of an operator in
+//                   `server.operators`, who is recorded as its operator
+//   POST /threat    SignedThreatReport                     -> reading, decision
+//   POST /resume    lift a flapping freeze on auto-morph; operator token as
+//                   for /morph, recorded in the audit log
+//   POST /jobs      JobRequest                              -> 202 {"id": 1, ...}
+//   GET  /jobs/1    the job as it stands; /jobs/1/wait holds on until it
+//                   finishes, up to 30 s
+//   GET  /status
+//   GET  /metrics   Prometheus text format
+//
+// Threat reports go through the same ThreatFeed as `simulate --reports`:
+// signed by a reporter in config/reporters.json, counted once a quorum
+// agrees. Morphs they lead to are always threat-policy morphs under the
+// cooldown; only an operator can call an emergency. A LatencyChecker also
+// scores the current backend's latency against the SLOs every so often
+// and decides on that performance reading with the feed's consensus.
+//
+// Errors come back as {"error": "..."} with 400 for malformed requests,
+// 401 for a morph without a valid operator token, 409 for a morph or proof
+// the controller refuses, 404/405 for unknown routes. Shutdown stops
+// accepting, lets requests in progress finish and joins their threads.
+// `--grpc` serves the same ProverService over gRPC (grpc.rs).
+
+use crate::concurrent::{ConcurrentController, DrainOutcome};
+use crate::config::MorphConfig;
+use crate::http::{self, Request, Response};
+use crate::jobs::{Job, JobQueue, JobRequest, Submitted};
+use crate::latency::Slo;
+use crate::metrics;
+use crate::morph::{MorphContext, MorphController};
+use crate::reports::{SignedThreatReport, ThreatFeed};
+use crate::security::SecurityEstimate;
+use crate::simulator::{MorphDecision, ThreatLevel, ThreatSimulator};
+use crate::types::{BackendType, MorphTrigger, UniversalProof};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Longest GET /jobs/<id>/wait holds on
+const JOB_WAIT: Duration = Duration::from_secs(30);
+
+/// How often a LatencyChecker looks whether it should stop
+const CHECK_POLL: Duration = Duration::from_millis(200);
+
+/// Most of a refused request read and thrown away before closing
+const DRAIN_BYTES: u64 = 64 << 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Operator name -> SHA-256 (hex) of their bearer token, e.g. from
+    /// `printf %s TOKEN | sha256sum`; morphs are refused when empty
+    #[serde(default)]
+    pub operators: BTreeMap<String, String>,
+    /// Connections served at once; more are answered 503
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+}
+
+fn default_max_connections() -> usize {
+    64
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            operators: BTreeMap::new(),
+            max_connections: default_max_connections(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProveRequest {
+    pub a: u64,
+    pub b: u64,
+    #[serde(default)]
+    pub jurisdiction: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub valid: bool,
+    pub backend: BackendType,
+}
+
+/// The operator is whoever the request authenticated as
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MorphRequest {
+    pub to: String,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub emergency: bool,
+}
+
+/// Answer to POST /resume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumeResponse {
+    pub operator: String,
+    /// What auto-morph was frozen on
+    pub frozen_on: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MorphResponse {
+    pub old_backend: BackendType,
+    pub new_backend: BackendType,
+    pub duration_ms: u128,
+    /// Proofs on the old backend when the morph started
+    pub in_flight: usize,
+    /// Of those, still running when the drain timed out
+    pub stragglers: usize,
+    pub drain_ms: u128,
+}
+
+impl From<&DrainOutcome> for MorphResponse {
+    fn from(outcome: &DrainOutcome) -> Self {
+        Self {
+            old_backend: outcome.result.old_backend,
+            new_backend: outcome.result.new_backend,
+            duration_ms: outcome.result.duration_ms,
+            in_flight: outcome.in_flight,
+            stragglers: outcome.stragglers,
+            drain_ms: outcome.waited.as_millis(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThreatResponse {
+    pub threat: ThreatLevel,
+    pub decision: MorphDecision,
+    /// The morph the decision led to, if auto-morph is on
+    pub morph: Option<MorphResponse>,
+    /// Why the decided morph did not happen
+    pub morph_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackendStatus {
+    pub backend: BackendType,
+    pub keys_loaded: bool,
+    pub warming: bool,
+    pub security_bits: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub current_backend: BackendType,
+    pub backends: Vec<BackendStatus>,
+    pub threat: ThreatLevel,
+    pub proofs_in_flight: usize,
+    pub cooldown_remaining_secs: Option<u64>,
+    pub auto_morph: bool,
+    pub auto_morph_frozen: Option<String>,
+    /// Jobs per state, if the job queue is enabled
+    pub jobs: Option<BTreeMap<String, usize>>,
+}
+
+/// Why a request was not carried out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceError {
+    /// Malformed or out-of-range input
+    Invalid(String),
+    /// Well-formed, but the controller refused: jurisdiction, cooldown...
+    Refused(String),
+    /// Missing or unknown operator token
+    Unauthorized(String),
+}
+
+impl ServiceError {
+    pub fn message(&self) -> &str {
+        match self {
+            ServiceError::Invalid(message)
+            | ServiceError::Refused(message)
+            | ServiceError::Unauthorized(message) => message,
+        }
+    }
+}
+
+impl From<ServiceError> for Response {
+    fn from(error: ServiceError) -> Self {
+        let status = match error {
+            ServiceError::Invalid(_) => 400,
+            ServiceError::Refused(_) => 409,
+            ServiceError::Unauthorized(_) => 401,
+        };
+        Response::error(status, error.message())
+    }
+}
+
+/// A morph carried out by the daemon, for watchers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MorphEvent {
+    pub timestamp: String,
+    pub trigger: MorphTrigger,
+    pub operator: String,
+    pub reason: String,
+    pub morph: MorphResponse,
+    /// For threat-policy morphs, the reading and decision behind them
+    pub threat: Option<ThreatLevel>,
+    pub decision: Option<MorphDecision>,
+}
+
+/// The daemon's state, shared by the HTTP and gRPC front ends
+pub struct ProverService {
+    controller: ConcurrentController,
+    simulator: Mutex<ThreatSimulator>,
+    auto_morph: bool,
+    watchers: Mutex<Vec<Sender<MorphEvent>>>,
+    jobs: Option<Arc<JobQueue>>,
+    feed: Option<Mutex<ThreatFeed>>,
+    /// Operator name -> SHA-256 of their token
+    operators: BTreeMap<String, String>,
+    /// Latency SLOs the performance category is scored against
+    slos: Vec<Slo>,
+}
+
+impl ProverService {
+    /// `simulator` decides on ingested readings; it should start on the
+    /// controller's backend
+    pub fn new(
+        controller: MorphController,
+        simulator: ThreatSimulator,
+        config: &MorphConfig,
+    ) -> Self {
+        Self {
+            controller: ConcurrentController::new(controller, config.drain_timeout()),
+            simulator: Mutex::new(simulator),
+            auto_morph: config.auto_morph_enabled,
+            watchers: Mutex::new(Vec::new()),
+            jobs: None,
+            feed: None,
+            operators: BTreeMap::new(),
+            slos: Vec::new(),
+        }
+    }
+
+    /// Accept signed threat reports on POST /threat
+    pub fn with_feed(mut self, feed: ThreatFeed) -> Self {
+        self.feed = Some(Mutex::new(feed));
+        self
+    }
+
+    /// Operators allowed to morph, name -> SHA-256 (hex) of their token
+    pub fn with_operators(mut self, operators: BTreeMap<String, String>) -> Self {
+        self.operators = operators;
+        self
+    }
+
+    /// Score the performance category against `slos`; see `check_latency`
+    pub fn with_slos(mut self, slos: Vec<Slo>) -> Self {
+        self.slos = slos;
+        self
+    }
+
+    /// Accept proving jobs into `queue`; a JobRunner proves them with
+    /// `prove_job`
+    pub fn with_jobs(mut self, queue: Arc<JobQueue>) -> Self {
+        self.jobs = Some(queue);
+        self
+    }
+
+    pub fn controller(&self) -> &ConcurrentController {
+        &self.controller
+    }
+
+    /// Answer one HTTP request
+    pub fn handle(&self, request: &Request) -> Response {
+        let result = match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/prove") => parse(request)
+                .and_then(|r| self.prove(&r))
+                .map(|proof| Response::json(200, &proof)),
+            ("POST", "/verify") => parse(request).and_then(|proof: UniversalProof| {
+                let valid = self.verify(&proof)?;
+                Ok(Response::json(
+                    200,
+                    &VerifyResponse {
+                        valid,
+                        backend: proof.backend,
+                    },
+                ))
+            }),
+            ("POST", "/morph") => self
+                .authenticate(request.bearer_token())
+                .and_then(|operator| parse(request).and_then(|r| self.morph(&r, &operator)))
+                .map(|morph| Response::json(200, &morph)),
+            ("POST", "/resume") => self
+                .authenticate(request.bearer_token())
+                .and_then(|operator| self.resume(&operator))
+                .map(|resumed| Response::json(200, &resumed)),
+            ("POST", "/threat") => parse(request)
+                .and_then(|r| self.ingest(r))
+                .map(|ingested| Response::json(200, &ingested)),
+            ("GET", "/status") => Ok(Response::json(200, &self.status())),
+            ("GET", "/metrics") => Ok(metrics::global().respond(request)),
+            ("POST", "/jobs") => parse(request)
+                .and_then(|r| self.submit_job(r))
+                .map(|submitted| Response::json(202, &submitted)),
+            ("GET", path) if path.starts_with("/jobs/") => self.get_job(path),
+            (
+                _,
+                "/prove" | "/verify" | "/morph" | "/resume" | "/threat" | "/status" | "/metrics"
+                | "/jobs",
+            ) => {
+                return Response::error(405, "Method not allowed");
+            }
+            _ => return Response::not_found(),
+        };
+        result.unwrap_or_else(Response::from)
+    }
+
+    /// Prove on the current backend, under a lease a morph will drain
+    pub fn prove(&self, request: &ProveRequest) -> Result<UniversalProof, ServiceError> {
+        if request.a.checked_mul(request.b).is_none() {
+            return Err(ServiceError::Invalid("a * b overflows 64 bits".to_string()));
+        }
+        let lease = self.controller.lease();
+        let leased = self
+            .controller
+            .prove_with(
+                &lease,
+                request.a,
+                request.b,
+                request.jurisdiction.as_deref(),
+            )
+            .map_err(ServiceError::Refused)?;
+        Ok(leased.proof)
+    }
+
+    pub fn verify(&self, proof: &UniversalProof) -> Result<bool, ServiceError> {
+        if proof.public_inputs.len() != 1 || proof.public_inputs[0].parse::<u64>().is_err() {
+            return Err(ServiceError::Invalid(
+                "public_inputs must hold one unsigned integer".to_string(),
+            ));
+        }
+        self.controller.verify(proof).map_err(ServiceError::Refused)
+    }
+
+    /// Operator whose token this is
+    pub fn authenticate(&self, token: Option<&str>) -> Result<String, ServiceError> {
+        let token = token.ok_or_else(|| {
+            ServiceError::Unauthorized("Morphing needs an operator token".to_string())
+        })?;
+        let digest = hex::encode(Sha256::digest(token.as_bytes()));
+        self.operators
+            .iter()
+            .find(|(_, hash)| hash.eq_ignore_ascii_case(&digest))
+            .map(|(name, _)| name.clone())
+            .ok_or_else(|| ServiceError::Unauthorized("Unknown operator token".to_string()))
+    }
+
+    /// Morph on behalf of an authenticated `operator`
+    pub fn morph(
+        &self,
+        request: &MorphRequest,
+        operator: &str,
+    ) -> Result<MorphResponse, ServiceError> {
+        let target = BackendType::parse(&request.to)
+            .ok_or_else(|| ServiceError::Invalid(format!("Unknown backend '{}'", request.to)))?;
+        let context = if request.emergency {
+            if request.reason.trim().is_empty() {
+                return Err(ServiceError::Invalid(
+                    "An emergency morph needs a reason".to_string(),
+                ));
+            }
+            MorphContext::emergency(operator, &request.reason)
+        } else {
+            MorphContext::manual(operator, &request.reason)
+        };
+        let outcome = self
+            .controller
+            .morph(target, context.clone())
+            .map_err(ServiceError::Refused)?;
+        self.simulator().current_backend = outcome.result.new_backend;
+        Ok(self.announce(context, &outcome, None))
+    }
+
+    /// Operator confirmation that threat-policy morphs may resume after a
+    /// flapping freeze; refused unless the audit log takes it
+    pub fn resume(&self, operator: &str) -> Result<ResumeResponse, ServiceError> {
+        let mut simulator = self.simulator();
+        let frozen = simulator
+            .detector
+            .as_ref()
+            .and_then(|d| d.frozen().cloned());
+        let alert = self
+            .controller
+            .with_controller(|c| c.resume_auto_morph(operator, frozen))
+            .map_err(ServiceError::Refused)?;
+        simulator.resume_auto_morph();
+        Ok(ResumeResponse {
+            operator: operator.to_string(),
+            frozen_on: alert.kind.to_string(),
+        })
+    }
+
+    /// Take a signed report, decide on the feed's consensus, and morph if
+    /// the policy says so
+    pub fn ingest(&self, report: SignedThreatReport) -> Result<ThreatResponse, ServiceError> {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        self.feed()?
+            .submit(report, now)
+            .map_err(|e| ServiceError::Invalid(format!("Report rejected: {}", e)))?;
+        self.decide(now)
+    }
+
+    /// Score the current backend's observed latency against the SLOs as the
+    /// performance category and decide on it, as `ingest` does on a report.
+    /// None until some SLO has samples, or without a threat feed to tell
+    /// whether security allows acting on it.
+    pub fn check_latency(&self) -> Result<Option<ThreatResponse>, ServiceError> {
+        let current = self.controller.current_backend();
+        let pool = self.controller.pool();
+        let observed = pool.latency().performance_score(&self.slos, current);
+        if self.feed.is_none() || observed.is_none() {
+            return Ok(None);
+        }
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        self.decide(now).map(Some)
+    }
+
+    /// Decide on the current reading and morph if the policy says so
+    fn decide(&self, now: u64) -> Result<ThreatResponse, ServiceError> {
+        let (threat, decision, from, froze) = {
+            let mut simulator = self.simulator();
+            let from = simulator.current_backend;
+            let threat = self.threat(&mut simulator, now)?;
+            let frozen =
+                |s: &ThreatSimulator| s.detector.as_ref().and_then(|d| d.frozen().cloned());
+            let was_frozen = frozen(&simulator).is_some();
+            simulator.observe(&threat);
+            let mut decision = simulator.evaluate(&threat);
+            // Readings never skip the cooldown, however high they score; an
+            // emergency morph is an operator's call
+            decision.emergency = false;
+            if self.auto_morph {
+                decision = simulator.apply(decision);
+            }
+            let froze = frozen(&simulator).filter(|_| !was_frozen);
+            (threat, decision, from, froze)
+        };
+        if let Some(alert) = froze {
+            self.controller.with_controller(|c| c.record_freeze(&alert));
+        }
+
+        let (mut morph, mut morph_error) = (None, None);
+        if self.auto_morph && decision.should_morph {
+            let context = MorphContext::threat_policy(&threat, &decision);
+            match self
+                .controller
+                .morph(decision.recommended_backend, context.clone())
+            {
+                Ok(outcome) => morph = Some(self.announce(context, &outcome, Some(&decision))),
+                Err(e) => {
+                    self.simulator().current_backend = from;
+                    morph_error = Some(e);
+                }
+            }
+        }
+        self.controller.with_controller(|c| c.anticipate(&threat));
+
+        Ok(ThreatResponse {
+            threat,
+            decision,
+            morph,
+            morph_error,
+        })
+    }
+
+    /// The feed's consensus, with the performance category taken from the
+    /// current backend's latency once the SLOs have samples
+    fn threat(
+        &self,
+        simulator: &mut ThreatSimulator,
+        now: u64,
+    ) -> Result<ThreatLevel, ServiceError> {
+        let mut threat = self.feed()?.threat_level(now).0;
+        let latency = self.controller.pool().latency();
+        // Fails only if the model has no performance category
+        if let Ok(Some(score)) = latency.feed(&self.slos, simulator) {
+            threat = simulator.model.with_score(&threat, "performance", score);
+        }
+        Ok(threat)
+    }
+
+    /// Queue a job pinned to the current backend
+    pub fn submit_job(&self, request: JobRequest) -> Result<Submitted, ServiceError> {
+        if request.a.checked_mul(request.b).is_none() {
+            return Err(ServiceError::Invalid("a * b overflows 64 bits".to_string()));
+        }
+        self.job_queue()?
+            .submit(request, self.controller.current_backend())
+            .map_err(ServiceError::Refused)
+    }
+
+    /// Prove a job from the queue: on its pinned backend, or on the current
+    /// one if it is re-targetable
+    pub fn prove_job(&self, job: &Job) -> Result<UniversalProof, String> {
+        let request = &job.request;
+        let jurisdiction = request.jurisdiction.as_deref();
+        if request.retargetable {
+            let lease = self.controller.lease();
+            self.controller
+                .prove_with(&lease, request.a, request.b, jurisdiction)
+                .map(|leased| leased.proof)
+        } else {
+            self.controller
+                .prove_on(job.backend, request.a, request.b, jurisdiction)
+        }
+    }
+
+    /// GET /jobs/<id> or /jobs/<id>/wait
+    fn get_job(&self, path: &str) -> Result<Response, ServiceError> {
+        let rest = &path["/jobs/".len()..];
+        let (id, wait) = match rest.strip_suffix("/wait") {
+            Some(id) => (id, true),
+            None => (rest, false),
+        };
+        let id: u64 = id
+            .parse()
+            .map_err(|_| ServiceError::Invalid(format!("Invalid job id '{}'", id)))?;
+        let queue = self.job_queue()?;
+        let job = if wait {
+            queue.wait(id, JOB_WAIT)
+        } else {
+            queue.get(id)
+        };
+        Ok(match job {
+            Some(job) => Response::json(200, &job),
+            None => Response::not_found(),
+        })
+    }
+
+    fn job_queue(&self) -> Result<&JobQueue, ServiceError> {
+        self.jobs
+            .as_deref()
+            .ok_or_else(|| ServiceError::Refused("Job queue is not enabled".to_string()))
+    }
+
+    pub fn status(&self) -> StatusResponse {
+        let frozen = self
+            .simulator()
+            .detector
+            .as_ref()
+            .and_then(|d| d.frozen())
+            .map(|alert| alert.kind.to_string());
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let threat = {
+            let mut simulator = self.simulator();
+            self.threat(&mut simulator, now)
+                .unwrap_or_else(|_| simulator.current_threat())
+        };
+        let cooldown = self
+            .controller
+            .with_controller(|c| c.cooldown_remaining().map(|left| left.as_secs() + 1));
+        let pool = self.controller.pool();
+        StatusResponse {
+            current_backend: self.controller.current_backend(),
+            backends: [BackendType::BN254, BackendType::BLS12_381]
+                .into_iter()
+                .map(|backend| BackendStatus {
+                    backend,
+                    keys_loaded: pool.has_keys(backend),
+                    warming: pool.is_warming(backend),
+                    security_bits: SecurityEstimate::for_backend(backend).bits,
+                })
+                .collect(),
+            threat,
+            proofs_in_flight: self.controller.in_flight(),
+            cooldown_remaining_secs: cooldown,
+            auto_morph: self.auto_morph,
+            auto_morph_frozen: frozen,
+            jobs: self.jobs.as_ref().map(|queue| {
+                queue
+                    .counts()
+                    .into_iter()
+                    .map(|(state, count)| (state.to_string(), count))
+                    .collect()
+            }),
+        }
+    }
+
+    /// Morphs from now on, until the receiver is dropped
+    pub fn watch_morphs(&self) -> Receiver<MorphEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.watchers().push(tx);
+        rx
+    }
+
+    /// Tell watchers about a morph that went through
+    fn announce(
+        &self,
+        context: MorphContext,
+        outcome: &DrainOutcome,
+        decision: Option<&MorphDecision>,
+    ) -> MorphResponse {
+        let morph = MorphResponse::from(outcome);
+        let event = MorphEvent {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            trigger: context.trigger,
+            operator: context.operator,
+            reason: context.reason,
+            morph: morph.clone(),
+            threat: context.threat,
+            decision: decision.cloned(),
+        };
+        self.watchers()
+            .retain(|watcher| watcher.send(event.clone()).is_ok());
+        morph
+    }
+
+    fn feed(&self) -> Result<MutexGuard<'_, ThreatFeed>, ServiceError> {
+        self.feed
+            .as_ref()
+            .map(|feed| feed.lock().unwrap_or_else(|e| e.into_inner()))
+            .ok_or_else(|| {
+                ServiceError::Refused(
+                    "Threat feed is not enabled (config/reporters.json)".to_string(),
+                )
+            })
+    }
+
+    fn simulator(&self) -> MutexGuard<'_, ThreatSimulator> {
+        self.simulator.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn watchers(&self) -> MutexGuard<'_, Vec<Sender<MorphEvent>>> {
+        self.watchers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn parse<T: DeserializeOwned>(request: &Request) -> Result<T, ServiceError> {
+    serde_json::from_slice(&request.body)
+        .map_err(|e| ServiceError::Invalid(format!("Invalid request body: {}", e)))
+}
+
+/// Calls `check_latency` every `interval` until stopped
+pub struct LatencyChecker {
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl LatencyChecker {
+    pub fn start(service: Arc<ProverService>, interval: Duration) -> Self {
+        let stopping = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stopping = Arc::clone(&stopping);
+            thread::spawn(move || {
+                let mut waited = Duration::ZERO;
+                while !stopping.load(Ordering::SeqCst) {
+                    thread::sleep(CHECK_POLL);
+                    waited += CHECK_POLL;
+                    if waited < interval {
+                        continue;
+                    }
+                    waited = Duration::ZERO;
+                    match service.check_latency() {
+                        Ok(Some(ThreatResponse {
+                            morph_error: Some(e),
+                            ..
+                        })) => eprintln!("[WARN] Latency morph failed: {}", e),
+                        Ok(_) => {}
+                        Err(e) => eprintln!("[WARN] Latency check: {}", e.message()),
+                    }
+                }
+            })
+        };
+        Self { stopping, thread }
+    }
+
+    pub fn stop(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.thread.join().ok();
+    }
+}
+
+/// Stops a running server from another thread, e.g. a signal handler
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    stopping: Arc<AtomicBool>,
+    addr: SocketAddr,
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        if !self.stopping.swap(true, Ordering::SeqCst) {
+            // Wake the accept loop so it sees the flag
+            TcpStream::connect(self.addr).ok();
+        }
+    }
+}
+
+pub struct Server {
+    addr: SocketAddr,
+    shutdown: ShutdownHandle,
+    accept: JoinHandle<()>,
+}
+
+impl Server {
+    /// Bind `addr` and serve `service` from a background thread, at most
+    /// `max_connections` at a time
+    pub fn start(
+        addr: &str,
+        service: Arc<ProverService>,
+        max_connections: usize,
+    ) -> Result<Self, String> {
+        let listener =
+            TcpListener::bind(addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+        let local = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+        let stopping = Arc::new(AtomicBool::new(false));
+
+        let flag = Arc::clone(&stopping);
+        let accept = thread::spawn(move || {
+            let mut workers: Vec<JoinHandle<()>> = Vec::new();
+            for stream in listener.incoming() {
+                if flag.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                workers.retain(|worker| !worker.is_finished());
+                if workers.len() >= max_connections {
+                    turn_away(stream);
+                    continue;
+                }
+                let service = Arc::clone(&service);
+                workers.push(thread::spawn(move || serve_connection(stream, &service)));
+            }
+            for worker in workers {
+                worker.join().ok();
+            }
+        });
+
+        Ok(Self {
+            addr: local,
+            shutdown: ShutdownHandle {
+                stopping,
+                addr: local,
+            },
+            accept,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Wait until shut down and every request in progress has finished
+    pub fn join(self) {
+        self.accept.join().ok();
+    }
+
+    pub fn shutdown(self) {
+        self.shutdown.shutdown();
+        self.join();
+    }
+}
+
+/// Answer 503 without reading the request, and without letting a slow
+/// client hold up the accept loop
+fn turn_away(stream: TcpStream) {
+    stream
+        .set_write_timeout(Some(Duration::from_millis(100)))
+        .ok();
+    http::write_response(&stream, &Response::error(503, "Too many connections")).ok();
+}
+
+fn serve_connection(stream: TcpStream, service: &ProverService) {
+    let response = match http::read_request(&stream) {
+        Ok(request) => service.handle(&request),
+        Err(e) => {
+            refuse(&stream, &Response::error(400, &e));
+            return;
+        }
+    };
+    if let Err(e) = http::write_response(&stream, &response) {
+        eprintln!("[WARN] Failed to write response: {}", e);
+    }
+}
+
+/// Answer a request we stopped reading part way. Closing with the rest
+/// of it unread resets the connection, which can lose the answer, so
+/// drain a bounded amount first.
+fn refuse(stream: &TcpStream, response: &Response) {
+    if let Err(e) = http::write_response(stream, response) {
+        eprintln!("[WARN] Failed to write response: {}", e);
+        return;
+    }
+    stream.shutdown(Shutdown::Write).ok();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .ok();
+    io::copy(&mut stream.take(DRAIN_BYTES), &mut io::sink()).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+    use crate::config::Config;
+    use crate::flapping::FlapConfig;
+    use crate::jobs::JobRunner;
+    use crate::policy::{MorphPolicy, StaleData};
+    use crate::reports::ReporterConfig;
+    use crate::threat::ThreatModel;
+    use ed25519_dalek::SigningKey;
+    use std::io::{Read, Write};
+
+    const TOKEN: &str = "ops-secret";
+
+    fn operators() -> BTreeMap<String, String> {
+        let hash = hex::encode(Sha256::digest(TOKEN.as_bytes()));
+        BTreeMap::from([("ops".to_string(), hash)])
+    }
+
+    /// Reporters "alice" and "bob", both needed for a category to count
+    fn feed() -> ThreatFeed {
+        let reporters = [("alice", 1), ("bob", 2)]
+            .into_iter()
+            .map(|(name, seed)| {
+                let public = SigningKey::from_bytes(&[seed; 32]).verifying_key();
+                (name.to_string(), hex::encode(public.to_bytes()))
+            })
+            .collect();
+        let config = ReporterConfig {
+            quorum: 2,
+            category_quorum: BTreeMap::new(),
+            mad_threshold: 3.0,
+            max_clock_skew_seconds: 300,
+            reporters,
+        };
+        ThreatFeed::new(config, ThreatModel::default()).unwrap()
+    }
+
+    /// A report body; `tick` keeps a reporter's timestamps increasing
+    fn report(reporter: &str, category: &str, score: u32, tick: u64) -> String {
+        let seed = if reporter == "alice" { 1 } else { 2 };
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        let now = chrono::Utc::now().timestamp() as u64;
+        let report = SignedThreatReport::sign(&key, reporter, category, score, now + tick);
+        serde_json::to_string(&report).unwrap()
+    }
+
+    fn service_on(backend: BackendType, cooldown_seconds: u32) -> Arc<ProverService> {
+        let mut config = Config::default();
+        config.morph.cooldown_seconds = cooldown_seconds;
+        let controller =
+            MorphController::from_config(backend, &config.morph, &ThreatModel::default());
+        controller.setup(backend).unwrap();
+        let mut simulator = ThreatSimulator::from_config(backend, &config.morph);
+        // Built-in rules, but categories short of quorum are left to the
+        // quorum tests rather than read as elevated
+        simulator.policy = Some(MorphPolicy {
+            name: "test".to_string(),
+            stale_data: StaleData::Ignore,
+            rules: Vec::new(),
+        });
+        Arc::new(
+            ProverService::new(controller, simulator, &config.morph)
+                .with_feed(feed())
+                .with_operators(operators()),
+        )
+    }
+
+    fn service() -> Arc<ProverService> {
+        service_on(BackendType::BN254, 0)
+    }
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    fn as_operator(mut request: Request, token: &str) -> Request {
+        request
+            .headers
+            .push(("authorization".to_string(), format!("Bearer {}", token)));
+        request
+    }
+
+    /// Status line and body from a real round trip
+    fn call(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: test\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or("").to_string();
+        (status, body)
+    }
+
+    #[test]
+    fn test_prove_verify_round_trip() {
+        let service = service();
+        let response = service.handle(&request("POST", "/prove", r#"{"a": 6, "b": 7}"#));
+        assert_eq!(response.status, 200);
+        let proof: UniversalProof = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(proof.backend, BackendType::BN254);
+        assert_eq!(proof.public_inputs, vec!["42"]);
+
+        let response = service.handle(&request("POST", "/verify", &response.body));
+        let verified: VerifyResponse = serde_json::from_str(&response.body).unwrap();
+        assert!(verified.valid);
+    }
+
+    #[test]
+    fn test_rejects_invalid_requests() {
+        let service = service();
+        let status = |method: &str, path: &str, body: &str| {
+            service.handle(&request(method, path, body)).status
+        };
+        assert_eq!(status("POST", "/prove", "{\"a\": 1}"), 400);
+        assert_eq!(status("POST", "/prove", "not json"), 400);
+        assert_eq!(
+            status(
+                "POST",
+                "/prove",
+                &format!("{{\"a\": {}, \"b\": 2}}", u64::MAX)
+            ),
+            400
+        );
+        let morph = |body: &str, token: &str| {
+            let request = as_operator(request("POST", "/morph", body), token);
+            service.handle(&request).status
+        };
+        assert_eq!(morph("{\"to\": \"secp256k1\"}", TOKEN), 400);
+        assert_eq!(morph("{\"to\": \"bn254\"}", TOKEN), 409);
+        let emergency = r#"{"to": "bls12-381", "emergency": true}"#;
+        assert_eq!(morph(emergency, TOKEN), 400);
+        // No token, or one no operator holds
+        assert_eq!(status("POST", "/morph", "{\"to\": \"bls12-381\"}"), 401);
+        assert_eq!(morph("{\"to\": \"bls12-381\"}", "guess"), 401);
+        assert_eq!(service.controller().current_backend(), BackendType::BN254);
+
+        // Threat readings must be signed reports from registered reporters
+        assert_eq!(
+            status(
+                "POST",
+                "/threat",
+                "{\"category\": \"quantum\", \"score\": 95}"
+            ),
+            400
+        );
+        assert_eq!(
+            status("POST", "/threat", &report("alice", "quantum", 101, 0)),
+            400
+        );
+        let mut forged: SignedThreatReport =
+            serde_json::from_str(&report("alice", "quantum", 10, 0)).unwrap();
+        forged.score = 95;
+        let forged = serde_json::to_string(&forged).unwrap();
+        assert_eq!(status("POST", "/threat", &forged), 400);
+        assert_eq!(status("GET", "/prove", ""), 405);
+        assert_eq!(status("GET", "/nowhere", ""), 404);
+        let response = service.handle(&request("POST", "/prove", "{}"));
+        assert!(response.body.starts_with("{\"error\":"));
+    }
+
+    #[test]
+    fn test_threat_ingest_morphs_over_http() {
+        let service = service();
+        let events = service.watch_morphs();
+        let server = Server::start("127.0.0.1:0", Arc::clone(&service), 8).unwrap();
+        let addr = server.addr();
+
+        // One reporter is short of quorum
+        let (status, body) = call(addr, "POST", "/threat", &report("alice", "quantum", 95, 0));
+        assert_eq!(status, 200);
+        let ingested: ThreatResponse = serde_json::from_str(&body).unwrap();
+        assert!(ingested.morph.is_none());
+        assert_eq!(ingested.threat.quantum, 0);
+
+        let (status, body) = call(addr, "POST", "/threat", &report("bob", "quantum", 95, 0));
+        assert_eq!(status, 200);
+        let ingested: ThreatResponse = serde_json::from_str(&body).unwrap();
+        assert!(ingested.decision.should_morph);
+        assert!(!ingested.decision.emergency);
+        let morph = ingested.morph.unwrap();
+        assert_eq!(morph.new_backend, BackendType::BLS12_381);
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.trigger, MorphTrigger::ThreatPolicy);
+
+        let (_, body) = call(addr, "GET", "/status", "");
+        let status: StatusResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(status.current_backend, BackendType::BLS12_381);
+        assert_eq!(status.threat.quantum, 95);
+
+        let (status, body) = call(addr, "POST", "/prove", r#"{"a": 2, "b": 3}"#);
+        assert_eq!(status, 200);
+        assert!(body.contains("\"backend\":\"BLS12_381\""));
+
+        server.shutdown();
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[test]
+    fn test_slow_backend_morphs_back_on_latency() {
+        let service = service_on(BackendType::BLS12_381, 0);
+        let service = Arc::into_inner(service)
+            .unwrap()
+            .with_slos(vec![Slo::parse("p95 prove < 1ms").unwrap()]);
+        // Only latency can bring it back to BN254
+        service.simulator().recovery_threshold = 0;
+        assert!(service.check_latency().unwrap().is_none());
+
+        let response = service.handle(&request("POST", "/prove", r#"{"a": 2, "b": 3}"#));
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            service.controller().current_backend(),
+            BackendType::BLS12_381
+        );
+        let checked = service.check_latency().unwrap().unwrap();
+        assert_eq!(checked.threat.score("performance"), 100);
+        assert_eq!(
+            checked.decision.rule.as_deref(),
+            Some("performance-recovery")
+        );
+        assert_eq!(checked.morph.unwrap().new_backend, BackendType::BN254);
+        assert_eq!(service.controller().current_backend(), BackendType::BN254);
+    }
+
+    #[test]
+    fn test_resume_is_authorized_and_audited() {
+        let service = service();
+        let audit = std::env::temp_dir().join(format!("server-audit-{}.jsonl", std::process::id()));
+        let audit = audit.to_str().unwrap().to_string();
+        std::fs::remove_file(&audit).ok();
+        service
+            .controller()
+            .with_controller(|c| c.set_audit_log(AuditLog::open(&audit).unwrap()));
+        let resume = |token: Option<&str>| {
+            let request = request("POST", "/resume", "");
+            let request = match token {
+                Some(token) => as_operator(request, token),
+                None => request,
+            };
+            service.handle(&request)
+        };
+
+        assert_eq!(resume(None).status, 401);
+        assert_eq!(resume(Some(TOKEN)).status, 409);
+
+        {
+            let mut simulator = service.simulator();
+            simulator.enable_flap_detection(&FlapConfig::default());
+            let now = simulator.now();
+            let detector = simulator.detector.as_mut().unwrap();
+            detector.restore_freeze(now, "morph requests reversed 3 times");
+        }
+        assert!(service.status().auto_morph_frozen.is_some());
+        assert_eq!(resume(Some("guess")).status, 401);
+        let response = resume(Some(TOKEN));
+        assert_eq!(response.status, 200);
+        let resumed: ResumeResponse = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(resumed.operator, "ops");
+        assert!(resumed.frozen_on.starts_with("morph requests reversed"));
+        assert!(service.status().auto_morph_frozen.is_none());
+
+        let log = AuditLog::open(&audit).unwrap();
+        let entry = log.last().unwrap();
+        assert_eq!(
+            (entry.trigger, entry.operator.as_str()),
+            (MorphTrigger::Resume, "ops")
+        );
+        assert!(log.verify().is_ok());
+        std::fs::remove_file(&audit).ok();
+        std::fs::remove_file(format!("{}.head", audit)).ok();
+    }
+
+    #[test]
+    fn test_reports_never_skip_the_cooldown() {
+        let service = service_on(BackendType::BLS12_381, 300);
+        let ingest = |reporter: &str, score: u32, tick: u64| {
+            let response = service.handle(&request(
+                "POST",
+                "/threat",
+                &report(reporter, "quantum", score, tick),
+            ));
+            serde_json::from_str::<ThreatResponse>(&response.body).unwrap()
+        };
+        ingest("alice", 5, 0);
+        ingest("bob", 5, 0);
+        assert_eq!(service.controller().current_backend(), BackendType::BN254);
+
+        // An emergency-level consensus inside the cooldown is held
+        ingest("alice", 95, 1);
+        let spike = ingest("bob", 95, 1);
+        assert!(spike.morph.is_none());
+        assert!(!spike.decision.should_morph);
+        assert!(
+            spike.decision.reason.contains("cooldown"),
+            "{}",
+            spike.decision.reason
+        );
+        assert_eq!(service.controller().current_backend(), BackendType::BN254);
+
+        // An operator can still call it
+        let body = r#"{"to": "bls12-381", "emergency": true, "reason": "break detected"}"#;
+        let response = service.handle(&as_operator(request("POST", "/morph", body), TOKEN));
+        assert_eq!(response.status, 200);
+    }
+
+    #[test]
+    fn test_connections_capped() {
+        let server = Server::start("127.0.0.1:0", service(), 1).unwrap();
+        let addr = server.addr();
+        // Holds the only slot until its request arrives
+        let held = TcpStream::connect(addr).unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        // Turned away without the request being read
+        let mut response = String::new();
+        let mut turned_away = TcpStream::connect(addr).unwrap();
+        turned_away.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+        drop(held);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(call(addr, "GET", "/status", "").0, 200);
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_long_header_lines_refused() {
+        let server = Server::start("127.0.0.1:0", service(), 8).unwrap();
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        let long = "x".repeat(http::MAX_LINE_BYTES + 1);
+        write!(stream, "GET /status HTTP/1.1\r\nX-Long: {}\r\n\r\n", long).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        assert!(response.contains("byte limit"));
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_jobs_stay_pinned_across_morphs() {
+        let mut config = Config::default();
+        config.morph.cooldown_seconds = 0;
+        let path = std::env::temp_dir().join(format!("server-jobs-{}.jsonl", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        config.jobs.path = path.to_str().unwrap().to_string();
+        let controller = MorphController::from_config(
+            BackendType::BN254,
+            &config.morph,
+            &ThreatModel::default(),
+        );
+        let simulator = ThreatSimulator::from_config(BackendType::BN254, &config.morph);
+        let queue = Arc::new(JobQueue::open(&config.jobs).unwrap());
+        let service = Arc::new(
+            ProverService::new(controller, simulator, &config.morph)
+                .with_jobs(Arc::clone(&queue))
+                .with_operators(operators()),
+        );
+
+        let submit = |body: &str| {
+            let response = service.handle(&request("POST", "/jobs", body));
+            assert_eq!(response.status, 202);
+            serde_json::from_str::<Submitted>(&response.body).unwrap()
+        };
+        let pinned = submit(r#"{"a": 2, "b": 3}"#);
+        let retargetable = submit(r#"{"a": 2, "b": 4, "retargetable": true}"#);
+        assert_eq!(pinned.backend, BackendType::BN254);
+        let morph = r#"{"to": "bls12-381", "reason": "test"}"#;
+        assert_eq!(
+            service
+                .handle(&as_operator(request("POST", "/morph", morph), TOKEN))
+                .status,
+            200
+        );
+
+        let runner = {
+            let service = Arc::clone(&service);
+            JobRunner::start(queue, move |job| service.prove_job(job))
+        };
+        let wait = |id: u64| {
+            let response = service.handle(&request("GET", &format!("/jobs/{}/wait", id), ""));
+            serde_json::from_str::<Job>(&response.body).unwrap()
+        };
+        let (pinned, retargetable) = (wait(pinned.id), wait(retargetable.id));
+        runner.stop();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(pinned.proof.unwrap().backend, BackendType::BN254);
+        assert_eq!(retargetable.backend, BackendType::BLS12_381);
+        assert_eq!(retargetable.proof.unwrap().public_inputs, vec!["8"]);
+        let status = service.status();
+        assert_eq!(status.jobs.unwrap().get("succeeded"), Some(&2));
+        assert_eq!(service.handle(&request("GET", "/jobs/99", "")).status, 404);
+        assert_eq!(service.handle(&request("GET", "/jobs/x", "")).status, 400);
+    }
+}
@@ -1,52 +1,111 @@
 // Simple threat simulator for Chameleon-ZK
 
+use crate::config::MorphConfig;
+use crate::flapping::{FlapAlert, FlapConfig, FlapDetector};
+use crate::metrics;
+use crate::policy::{MorphPolicy, PolicyExplanation, PolicyInput};
+use crate::threat::ThreatModel;
 use crate::types::BackendType;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreatLevel {
     pub quantum: u32,    // 0-100
     pub regulatory: u32, // 0-100
     pub overall: u32,    // calculated
+    /// Any further categories of the threat model (geographic, performance, ...)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub other: BTreeMap<String, u32>,
+    /// Categories whose feed has gone silent
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub stale: BTreeSet<String>,
 }
 
 impl ThreatLevel {
+    /// Quantum and regulatory readings weighted 60/40, with no other
+    /// categories. `ThreatModel::level` scores the configured categories.
+    #[deprecated(note = "weights ignore the threat model; use ThreatModel::level")]
     pub fn new(quantum: u32, regulatory: u32) -> Self {
         let overall = (quantum * 60 + regulatory * 40) / 100;
         Self {
             quantum,
             regulatory,
             overall,
+            other: BTreeMap::new(),
+            stale: BTreeSet::new(),
         }
     }
 
-    pub fn normal() -> Self {
-        Self::new(10, 15)
+    /// Score of a category by name; unknown categories read as 0
+    pub fn score(&self, category: &str) -> u32 {
+        match category {
+            "quantum" => self.quantum,
+            "regulatory" => self.regulatory,
+            _ => self.other.get(category).copied().unwrap_or(0),
+        }
+    }
+
+    /// Preset readings, scored by `model`
+    pub fn normal(model: &ThreatModel) -> Self {
+        model.level(&[("quantum", 10), ("regulatory", 15)])
     }
 
-    pub fn elevated() -> Self {
-        Self::new(45, 30)
+    pub fn elevated(model: &ThreatModel) -> Self {
+        model.level(&[("quantum", 45), ("regulatory", 30)])
     }
 
-    pub fn high() -> Self {
-        Self::new(70, 50)
+    pub fn high(model: &ThreatModel) -> Self {
+        model.level(&[("quantum", 70), ("regulatory", 50)])
     }
 
-    pub fn critical() -> Self {
-        Self::new(95, 80)
+    pub fn critical(model: &ThreatModel) -> Self {
+        model.level(&[("quantum", 95), ("regulatory", 80)])
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MorphDecision {
     pub should_morph: bool,
     pub recommended_backend: BackendType,
     pub reason: String,
+    pub emergency: bool,
+    /// Policy rule that produced the decision, if any fired
+    pub rule: Option<String>,
 }
 
 pub struct ThreatSimulator {
     pub current_backend: BackendType,
+    /// Overall score at or above which we upgrade to BLS12-381
     pub threat_threshold: u32,
+    /// Overall score below which we fall back to BN254
+    pub recovery_threshold: u32,
+    pub cooldown: Duration,
+    /// Let critical threats morph during the cooldown (logged when used)
+    pub emergency_override: bool,
+    /// Categories, weights and per-category thresholds
+    pub model: ThreatModel,
+    /// Rules loaded from a policy file; `None` uses the thresholds above
+    pub policy: Option<MorphPolicy>,
+    /// Where proofs are being used, for jurisdiction rules
+    pub jurisdiction: Option<String>,
+    clock: Clock,
+    /// Clock reading at the last morph
+    last_morph: Option<Duration>,
+    /// Latest ingested score per category and the clock reading it came at
+    readings: BTreeMap<String, (u32, Duration)>,
+    /// Freezes automatic morphs on flapping or threshold hugging, when set
+    pub detector: Option<FlapDetector>,
+}
+
+/// Where the simulator reads the time from
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    Wall(Instant),
+    /// Moves only when `advance` is called, for scenario runs
+    Simulated(Duration),
 }
 
 impl ThreatSimulator {
@@ -54,57 +113,228 @@ impl ThreatSimulator {
         Self {
             current_backend: initial_backend,
             threat_threshold: threshold,
+            recovery_threshold: 30,
+            cooldown: Duration::ZERO,
+            emergency_override: false,
+            model: ThreatModel::default(),
+            policy: None,
+            jurisdiction: None,
+            clock: Clock::Wall(Instant::now()),
+            last_morph: None,
+            readings: BTreeMap::new(),
+            detector: None,
+        }
+    }
+
+    pub fn from_config(initial_backend: BackendType, config: &MorphConfig) -> Self {
+        let mut simulator = Self::new(initial_backend, config.threat_threshold);
+        simulator.recovery_threshold = config.recovery_threshold;
+        simulator.cooldown = config.cooldown();
+        simulator
+    }
+
+    /// The policy in force: the loaded one, or rules built from the
+    /// thresholds if none is loaded or it has no rules of its own
+    pub fn policy(&self) -> Cow<'_, MorphPolicy> {
+        let builtin = || {
+            MorphPolicy::from_thresholds(
+                &self.model,
+                self.threat_threshold,
+                self.recovery_threshold,
+            )
+        };
+        match &self.policy {
+            Some(policy) if policy.rules.is_empty() => Cow::Owned(MorphPolicy {
+                name: policy.name.clone(),
+                stale_data: policy.stale_data,
+                rules: builtin().rules,
+            }),
+            Some(policy) => Cow::Borrowed(policy),
+            None => Cow::Owned(builtin()),
         }
     }
 
     /// Evaluate threat and decide if morph needed
     pub fn evaluate(&self, threat: &ThreatLevel) -> MorphDecision {
-        if threat.quantum >= 80 {
-            return MorphDecision {
-                should_morph: self.current_backend != BackendType::BLS12_381,
-                recommended_backend: BackendType::BLS12_381,
-                reason: "CRITICAL: Quantum threat level requires maximum security".to_string(),
-            };
+        self.policy().evaluate(&self.policy_input(threat))
+    }
+
+    /// Like `evaluate`, but report which rule fired and why
+    pub fn explain(&self, threat: &ThreatLevel) -> PolicyExplanation {
+        self.policy().explain(&self.policy_input(threat))
+    }
+
+    fn policy_input<'a>(&'a self, threat: &'a ThreatLevel) -> PolicyInput<'a> {
+        PolicyInput {
+            threat,
+            current_backend: self.current_backend,
+            model: &self.model,
+            jurisdiction: self.jurisdiction.as_deref(),
+            since_last_morph: self.last_morph.map(|t| self.now().saturating_sub(t)),
         }
+    }
 
-        if threat.quantum >= 60 {
-            return MorphDecision {
-                should_morph: self.current_backend == BackendType::BN254,
-                recommended_backend: BackendType::BLS12_381,
-                reason: "WARNING: Elevated quantum threat, recommend higher security".to_string(),
-            };
+    /// Time since the simulator was created, or simulated time after
+    /// `use_simulated_clock`
+    pub fn now(&self) -> Duration {
+        match self.clock {
+            Clock::Wall(started) => started.elapsed(),
+            Clock::Simulated(now) => now,
+        }
+    }
+
+    /// Stop following wall time; the clock starts at zero and only moves
+    /// with `advance`. Readings ingested on the old clock are dropped.
+    pub fn use_simulated_clock(&mut self) {
+        self.clock = Clock::Simulated(Duration::ZERO);
+        self.last_morph = None;
+        self.readings.clear();
+    }
+
+    /// Move the simulated clock forward; no effect on wall time
+    pub fn advance(&mut self, by: Duration) {
+        if let Clock::Simulated(now) = &mut self.clock {
+            *now += by;
+        }
+    }
+
+    /// Record a score for one category as of now
+    pub fn ingest(&mut self, category: &str, score: u32) -> Result<(), String> {
+        if self.model.category(category).is_none() {
+            return Err(format!("Unknown threat category '{}'", category));
         }
+        self.readings
+            .insert(category.to_string(), (score.min(100), self.now()));
+        Ok(())
+    }
+
+    /// Latest ingested scores, decayed to now, with categories that have
+    /// gone silent marked stale
+    pub fn current_threat(&self) -> ThreatLevel {
+        let now = self.now();
+        let mut scores = Vec::new();
+        let mut stale = BTreeSet::new();
+
+        for category in &self.model.categories {
+            let reading = self.readings.get(&category.name);
+            let age = reading.map(|(_, at)| now.saturating_sub(*at));
+            if let (Some((score, _)), Some(age)) = (reading, age) {
+                scores.push((
+                    category.name.as_str(),
+                    self.model.decayed(&category.name, *score, age),
+                ));
+            }
+            if self.model.is_stale(&category.name, age) {
+                stale.insert(category.name.clone());
+            }
+        }
+
+        let mut level = self.model.level(&scores);
+        level.stale = stale;
+        level
+    }
+
+    /// Time left before another non-emergency morph is allowed
+    pub fn cooldown_remaining(&self) -> Option<Duration> {
+        let elapsed = self.now().saturating_sub(self.last_morph?);
+        if elapsed >= self.cooldown {
+            return None;
+        }
+        Some(self.cooldown - elapsed)
+    }
 
-        if threat.overall < 30 && self.current_backend != BackendType::BN254 {
-            return MorphDecision {
-                should_morph: true,
-                recommended_backend: BackendType::BN254,
-                reason: "LOW THREAT: Can optimize for speed with BN254".to_string(),
+    /// Watch for flapping with the current model and thresholds
+    pub fn enable_flap_detection(&mut self, config: &FlapConfig) {
+        self.detector = Some(FlapDetector::new(
+            config.clone(),
+            &self.model,
+            self.threat_threshold,
+            self.recovery_threshold,
+        ));
+    }
+
+    /// Operator confirmation that automatic morphing may resume; returns
+    /// the alert it was frozen on
+    pub fn resume_auto_morph(&mut self) -> Option<FlapAlert> {
+        self.detector.as_mut()?.confirm()
+    }
+
+    /// Show a reading to the flapping detector
+    pub fn observe(&mut self, threat: &ThreatLevel) {
+        metrics::global().set_threat(threat);
+        let now = self.now();
+        if let Some(detector) = self.detector.as_mut() {
+            detector.observe(now, threat);
+        }
+    }
+
+    /// Evaluate a reading and apply the decision, respecting the cooldown
+    pub fn step(&mut self, threat: &ThreatLevel) -> MorphDecision {
+        self.observe(threat);
+        let decision = self.evaluate(threat);
+        self.apply(decision)
+    }
+
+    /// Switch to the decision's backend unless a flapping freeze or the
+    /// cooldown holds it back; returns the decision as applied
+    pub fn apply(&mut self, mut decision: MorphDecision) -> MorphDecision {
+        if !decision.should_morph {
+            return decision;
+        }
+
+        let now = self.now();
+        if let Some(detector) = self.detector.as_mut() {
+            let frozen = match detector.frozen() {
+                Some(alert) => Some(alert.clone()),
+                None => detector.record_request(now, decision.recommended_backend),
             };
+            if let Some(alert) = frozen {
+                decision.should_morph = false;
+                decision.reason = format!(
+                    "{} (held: auto-morph frozen, {}; confirm to resume)",
+                    decision.reason, alert.kind
+                );
+                return decision;
+            }
         }
 
-        MorphDecision {
-            should_morph: false,
-            recommended_backend: self.current_backend,
-            reason: "Current backend is appropriate for threat level".to_string(),
+        if let Some(remaining) = self.cooldown_remaining() {
+            if decision.emergency && self.emergency_override {
+                eprintln!(
+                    "[EMERGENCY] {} morph {} -> {} bypassing cooldown ({}s left): {}",
+                    chrono::Utc::now().to_rfc3339(),
+                    self.current_backend.name(),
+                    decision.recommended_backend.name(),
+                    remaining.as_secs(),
+                    decision.reason
+                );
+            } else {
+                decision.should_morph = false;
+                decision.reason = format!(
+                    "{} (held: cooldown active, {}s remaining)",
+                    decision.reason,
+                    remaining.as_secs() + 1
+                );
+                return decision;
+            }
         }
+
+        self.current_backend = decision.recommended_backend;
+        self.last_morph = Some(self.now());
+        decision
     }
 
     /// Simulate a threat scenario
     pub fn simulate(&mut self, scenario: &str) -> (ThreatLevel, MorphDecision) {
         let threat = match scenario.to_lowercase().as_str() {
-            "quantum" | "quantum_emergency" => ThreatLevel::critical(),
-            "elevated" => ThreatLevel::elevated(),
-            "high" => ThreatLevel::high(),
-            "normal" | "low" => ThreatLevel::normal(),
-            _ => ThreatLevel::normal(),
+            "quantum" | "quantum_emergency" => ThreatLevel::critical(&self.model),
+            "elevated" => ThreatLevel::elevated(&self.model),
+            "high" => ThreatLevel::high(&self.model),
+            "normal" | "low" => ThreatLevel::normal(&self.model),
+            _ => ThreatLevel::normal(&self.model),
         };
 
-        let decision = self.evaluate(&threat);
-
-        if decision.should_morph {
-            self.current_backend = decision.recommended_backend;
-        }
+        let decision = self.step(&threat);
 
         (threat, decision)
     }
@@ -113,16 +343,15 @@ impl ThreatSimulator {
     pub fn print_simulation(&self, threat: &ThreatLevel, decision: &MorphDecision) {
         println!("                     THREAT SIMULATION                        ");
         println!("Threat Levels:");
-        println!(
-            "  Quantum:    {:3}/100  {}",
-            threat.quantum,
-            self.threat_bar(threat.quantum)
-        );
-        println!(
-            "  Regulatory: {:3}/100  {}",
-            threat.regulatory,
-            self.threat_bar(threat.regulatory)
-        );
+        for category in &self.model.categories {
+            let score = threat.score(&category.name);
+            println!(
+                "  {:<12}{:3}/100  {}",
+                format!("{}:", capitalize(&category.name)),
+                score,
+                self.threat_bar(score)
+            );
+        }
         println!(
             "  Overall:    {:3}/100  {}",
             threat.overall,
@@ -141,8 +370,156 @@ impl ThreatSimulator {
     }
 
     fn threat_bar(&self, level: u32) -> String {
-        let filled = (level / 5) as usize;
+        let filled = (level.min(100) / 5) as usize;
         let empty = 20 - filled;
         format!("[{}{}]", "#".repeat(filled), "-".repeat(empty))
     }
 }
+
+pub fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prewarm::PrewarmConfig;
+
+    fn config(cooldown_seconds: u32) -> MorphConfig {
+        MorphConfig {
+            auto_morph_enabled: true,
+            threat_threshold: 50,
+            recovery_threshold: 20,
+            cooldown_seconds,
+            rollback_grace_seconds: 900,
+            canary_enabled: false,
+            canary_latency_budget_ms: 2000,
+            drain_timeout_ms: 5000,
+            hedge_verify: "both".to_string(),
+            hedge_bn254_vk: None,
+            hedge_bls12_381_vk: None,
+            flap_detection: FlapConfig::default(),
+            prewarm: PrewarmConfig::default(),
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)] // fixed 60/40 readings, independent of the model
+    fn test_hysteresis_band_holds_backend() {
+        let mut sim = ThreatSimulator::from_config(BackendType::BN254, &config(0));
+
+        // 34 is between recovery (20) and upgrade (50): stay put either way
+        assert!(!sim.step(&ThreatLevel::new(40, 25)).should_morph);
+
+        assert!(sim.step(&ThreatLevel::new(50, 60)).should_morph);
+        assert_eq!(sim.current_backend, BackendType::BLS12_381);
+
+        assert!(!sim.step(&ThreatLevel::new(40, 25)).should_morph);
+        assert_eq!(sim.current_backend, BackendType::BLS12_381);
+
+        assert!(sim.step(&ThreatLevel::new(10, 10)).should_morph);
+        assert_eq!(sim.current_backend, BackendType::BN254);
+    }
+
+    #[test]
+    fn test_hysteresis_band_under_model_weights() {
+        let mut sim = ThreatSimulator::from_config(BackendType::BN254, &config(0));
+        let model = sim.model.clone();
+        let level =
+            |quantum, regulatory| model.level(&[("quantum", quantum), ("regulatory", regulatory)]);
+
+        // 35 is between recovery (20) and upgrade (50) at 40/30 weights
+        assert!(!sim.step(&level(50, 50)).should_morph);
+        assert!(sim.step(&level(55, 95)).should_morph);
+        assert!(!sim.step(&level(50, 50)).should_morph);
+        assert_eq!(sim.current_backend, BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_cooldown_holds_second_morph() {
+        let mut sim = ThreatSimulator::from_config(BackendType::BN254, &config(300));
+
+        assert!(sim.step(&ThreatLevel::high(&sim.model)).should_morph);
+        let decision = sim.step(&ThreatLevel::normal(&sim.model));
+        assert!(!decision.should_morph);
+        assert!(decision.reason.contains("cooldown active"));
+        assert_eq!(sim.current_backend, BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_emergency_needs_explicit_override() {
+        let mut sim = ThreatSimulator::from_config(BackendType::BLS12_381, &config(300));
+        assert!(sim.step(&ThreatLevel::normal(&sim.model)).should_morph);
+
+        assert!(!sim.step(&ThreatLevel::critical(&sim.model)).should_morph);
+        assert_eq!(sim.current_backend, BackendType::BN254);
+
+        sim.emergency_override = true;
+        assert!(sim.step(&ThreatLevel::critical(&sim.model)).should_morph);
+        assert_eq!(sim.current_backend, BackendType::BLS12_381);
+    }
+
+    #[test]
+    fn test_single_reading_decays() {
+        let mut sim = ThreatSimulator::new(BackendType::BN254, 50);
+        sim.model.categories[0].freshness = crate::threat::CategoryFreshness {
+            half_life_seconds: Some(600),
+            stale_after_seconds: Some(3600),
+        };
+        sim.use_simulated_clock();
+
+        sim.ingest("quantum", 95).unwrap();
+        assert!(sim.step(&sim.current_threat()).emergency);
+
+        // Two half-lives later the same reading is no longer critical
+        sim.advance(Duration::from_secs(1200));
+        let threat = sim.current_threat();
+        assert_eq!(threat.quantum, 24);
+        assert!(!sim.evaluate(&threat).emergency);
+
+        sim.advance(Duration::from_secs(3600));
+        assert!(sim.current_threat().stale.contains("quantum"));
+    }
+
+    #[test]
+    fn test_switching_clocks_drops_wall_readings() {
+        let mut sim = ThreatSimulator::new(BackendType::BN254, 50);
+        std::thread::sleep(Duration::from_millis(5));
+        sim.ingest("quantum", 90).unwrap();
+
+        sim.use_simulated_clock();
+        assert_eq!(sim.current_threat().quantum, 0);
+    }
+
+    #[test]
+    fn test_policy_without_rules_uses_thresholds() {
+        let mut sim = ThreatSimulator::from_config(BackendType::BN254, &config(0));
+        sim.policy = Some(serde_json::from_str(r#"{"name": "shipped"}"#).unwrap());
+
+        let decision = sim.evaluate(&sim.model.level(&[("quantum", 55), ("regulatory", 95)]));
+        assert!(decision.should_morph);
+        assert_eq!(decision.rule.as_deref(), Some("overall-upgrade"));
+        assert_eq!(sim.policy().name, "shipped");
+    }
+
+    #[test]
+    fn test_per_category_thresholds() {
+        let mut model = ThreatModel::default();
+        model.categories[2].thresholds = Some(crate::threat::CategoryThresholds {
+            elevated: 70,
+            emergency: 90,
+        });
+        let mut sim = ThreatSimulator::new(BackendType::BN254, 50);
+        sim.model = model.clone();
+
+        let threat = model.level(&[("quantum", 10), ("geographic", 92)]);
+        assert!(threat.overall < 50);
+        let decision = sim.evaluate(&threat);
+        assert!(decision.should_morph && decision.emergency);
+        assert!(decision.reason.contains("Geographic"));
+    }
+}
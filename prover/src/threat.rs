@@ -0,0 +1,328 @@
+// Configurable threat categories and weights
+//
+// Mirrors the arithmetic of ThreatAggregationOracle.sol: category weights
+// are integer percentages and the overall score is
+// sum(score * weight) / 100, truncated.
+
+use crate::config::ThreatConfig;
+use crate::simulator::ThreatLevel;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/// Score at which a single category forces a morph, regardless of overall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryThresholds {
+    /// Recommend the stronger curve
+    pub elevated: u32,
+    /// Morph to the stronger curve as an emergency
+    pub emergency: u32,
+}
+
+/// How fast a category's readings lose weight and when its feed counts as
+/// silent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryFreshness {
+    /// A reading counts half as much after this long
+    #[serde(default)]
+    pub half_life_seconds: Option<u64>,
+    /// No reading for this long marks the category stale
+    #[serde(default)]
+    pub stale_after_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreatCategory {
+    pub name: String,
+    /// Percent; all weights in a model add up to 100
+    pub weight: u32,
+    pub thresholds: Option<CategoryThresholds>,
+    pub freshness: CategoryFreshness,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreatModel {
+    pub categories: Vec<ThreatCategory>,
+}
+
+impl ThreatModel {
+    /// Build a model, checking the weights and thresholds are consistent
+    pub fn new(categories: Vec<ThreatCategory>) -> Result<Self, String> {
+        let model = Self { categories };
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Categories and weights from simulator/threat_config.json
+    pub fn from_config(config: &ThreatConfig) -> Result<Self, String> {
+        let mut categories: Vec<ThreatCategory> = config
+            .weights
+            .iter()
+            .map(|(name, weight)| ThreatCategory {
+                name: name.clone(),
+                weight: (weight * 100.0).round() as u32,
+                thresholds: config.thresholds.get(name).copied(),
+                freshness: config.freshness.get(name).copied().unwrap_or_default(),
+            })
+            .collect();
+        // Heaviest first, so reports and rule order follow importance
+        categories.sort_by(|a, b| b.weight.cmp(&a.weight).then(a.name.cmp(&b.name)));
+
+        for name in config.thresholds.keys() {
+            if !config.weights.contains_key(name) {
+                return Err(format!("Threshold given for unknown category '{}'", name));
+            }
+        }
+        for name in config.freshness.keys() {
+            if !config.weights.contains_key(name) {
+                return Err(format!("Freshness given for unknown category '{}'", name));
+            }
+        }
+
+        Self::new(categories)
+    }
+
+    /// The model in simulator/threat_config.json, or the built-in default
+    /// if there is no such file
+    pub fn load_default() -> Result<Self, String> {
+        match ThreatConfig::load_default()? {
+            Some(config) => Self::from_config(&config),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let total: u32 = self.categories.iter().map(|c| c.weight).sum();
+        if total != 100 {
+            return Err(format!(
+                "Category weights must add up to 100%, got {}%",
+                total
+            ));
+        }
+        for category in &self.categories {
+            if let Some(t) = category.thresholds {
+                if t.elevated > t.emergency || t.emergency > 100 {
+                    return Err(format!(
+                        "Category '{}': need elevated <= emergency <= 100",
+                        category.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn category(&self, name: &str) -> Option<&ThreatCategory> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+
+    /// Weighted overall score, computed the way the on-chain oracle does
+    pub fn overall(&self, scores: &BTreeMap<String, u32>) -> u32 {
+        let weighted: u32 = self
+            .categories
+            .iter()
+            .map(|c| scores.get(&c.name).copied().unwrap_or(0).min(100) * c.weight)
+            .sum();
+        weighted / 100
+    }
+
+    /// A reading of `score` taken `age` ago, after the category's decay
+    pub fn decayed(&self, category: &str, score: u32, age: Duration) -> u32 {
+        let half_life = self
+            .category(category)
+            .and_then(|c| c.freshness.half_life_seconds)
+            .filter(|h| *h > 0);
+        match half_life {
+            Some(half_life) => {
+                let halvings = age.as_secs_f64() / half_life as f64;
+                (score as f64 * 0.5f64.powf(halvings)).round() as u32
+            }
+            None => score,
+        }
+    }
+
+    /// Whether a category whose newest reading is `age` old (None: never
+    /// read) has gone silent. Categories without `stale_after_seconds`
+    /// never do.
+    pub fn is_stale(&self, category: &str, age: Option<Duration>) -> bool {
+        let stale_after = self
+            .category(category)
+            .and_then(|c| c.freshness.stale_after_seconds);
+        match (stale_after, age) {
+            (Some(limit), Some(age)) => age.as_secs() > limit,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// `level` with one category's score replaced, and no longer stale
+    pub fn with_score(&self, level: &ThreatLevel, category: &str, score: u32) -> ThreatLevel {
+        let scores: Vec<(&str, u32)> = self
+            .categories
+            .iter()
+            .map(|c| {
+                let score = if c.name == category {
+                    score
+                } else {
+                    level.score(&c.name)
+                };
+                (c.name.as_str(), score)
+            })
+            .collect();
+        let mut updated = self.level(&scores);
+        updated.stale = level
+            .stale
+            .iter()
+            .filter(|name| *name != category)
+            .cloned()
+            .collect();
+        updated
+    }
+
+    /// Threat level from per-category scores; missing categories count as 0
+    pub fn level(&self, scores: &[(&str, u32)]) -> ThreatLevel {
+        let scores: BTreeMap<String, u32> = scores
+            .iter()
+            .map(|(name, score)| (name.to_string(), (*score).min(100)))
+            .collect();
+        let overall = self.overall(&scores);
+
+        let mut other = BTreeMap::new();
+        for category in &self.categories {
+            if category.name != "quantum" && category.name != "regulatory" {
+                let score = scores.get(&category.name).copied().unwrap_or(0);
+                other.insert(category.name.clone(), score);
+            }
+        }
+
+        ThreatLevel {
+            quantum: scores.get("quantum").copied().unwrap_or(0),
+            regulatory: scores.get("regulatory").copied().unwrap_or(0),
+            overall,
+            other,
+            stale: BTreeSet::new(),
+        }
+    }
+}
+
+impl Default for ThreatModel {
+    /// Fallback for when there is no simulator/threat_config.json; callers
+    /// that have a config use `load_default` or `from_config`
+    fn default() -> Self {
+        let category = |name: &str, weight, thresholds| ThreatCategory {
+            name: name.to_string(),
+            weight,
+            thresholds,
+            freshness: CategoryFreshness::default(),
+        };
+        Self {
+            categories: vec![
+                category(
+                    "quantum",
+                    40,
+                    Some(CategoryThresholds {
+                        elevated: 60,
+                        emergency: 80,
+                    }),
+                ),
+                category("regulatory", 30, None),
+                category("geographic", 20, None),
+                category("performance", 10, None),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overall_matches_oracle_arithmetic() {
+        // Weights from CryptoHealthOracle's constructor
+        let model = ThreatModel::new(
+            [
+                ("cryptographic", 35),
+                ("implementation", 25),
+                ("regulatory", 20),
+                ("network", 15),
+                ("hardware", 5),
+            ]
+            .iter()
+            .map(|(name, weight)| ThreatCategory {
+                name: name.to_string(),
+                weight: *weight,
+                thresholds: None,
+                freshness: CategoryFreshness::default(),
+            })
+            .collect(),
+        )
+        .unwrap();
+
+        let level = model.level(&[
+            ("cryptographic", 90),
+            ("implementation", 33),
+            ("regulatory", 47),
+            ("network", 12),
+            ("hardware", 99),
+        ]);
+        // (90*35 + 33*25 + 47*20 + 12*15 + 99*5) / 100 = 5590 / 100
+        assert_eq!(level.overall, 55);
+        assert_eq!(level.score("hardware"), 99);
+    }
+
+    #[test]
+    fn test_model_from_threat_config() {
+        let config: ThreatConfig = serde_json::from_str(
+            r#"{
+                "morph_threshold": 50,
+                "recovery_threshold": 20,
+                "check_interval_seconds": 5,
+                "total_cycles": 20,
+                "threat_pattern": "rising_falling",
+                "weights": {"quantum": 0.4, "regulatory": 0.3, "geographic": 0.2, "performance": 0.1},
+                "thresholds": {"quantum": {"elevated": 60, "emergency": 80}}
+            }"#,
+        )
+        .unwrap();
+
+        let model = ThreatModel::from_config(&config).unwrap();
+        assert_eq!(model, ThreatModel::default());
+
+        // Same arithmetic as scripts/threat_simulator.sh
+        let level = model.level(&[
+            ("quantum", 80),
+            ("regulatory", 48),
+            ("geographic", 32),
+            ("performance", 20),
+        ]);
+        assert_eq!(level.overall, (80 * 40 + 48 * 30 + 32 * 20 + 20 * 10) / 100);
+    }
+
+    #[test]
+    fn test_decay_and_staleness() {
+        let mut model = ThreatModel::default();
+        model.categories[0].freshness = CategoryFreshness {
+            half_life_seconds: Some(3600),
+            stale_after_seconds: Some(7200),
+        };
+
+        let hour = Duration::from_secs(3600);
+        assert_eq!(model.decayed("quantum", 80, Duration::ZERO), 80);
+        assert_eq!(model.decayed("quantum", 80, hour), 40);
+        assert_eq!(model.decayed("quantum", 80, hour * 2), 20);
+        assert_eq!(model.decayed("regulatory", 80, hour * 2), 80);
+
+        assert!(!model.is_stale("quantum", Some(hour)));
+        assert!(model.is_stale("quantum", Some(hour * 3)));
+        assert!(model.is_stale("quantum", None));
+        assert!(!model.is_stale("regulatory", None));
+    }
+
+    #[test]
+    fn test_weights_must_sum_to_100() {
+        let mut model = ThreatModel::default();
+        model.categories[0].weight = 50;
+        assert!(model.validate().is_err());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::security::SecurityEstimate;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BackendType {
@@ -14,12 +16,35 @@ impl BackendType {
         }
     }
 
-    pub fn security_bits(&self) -> u32 {
+    /// Parse a CLI/config name such as "bn254", "bls12-381" or "BLS12_381"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bn254" => Some(BackendType::BN254),
+            "bls12-381" | "bls12_381" | "bls" => Some(BackendType::BLS12_381),
+            _ => None,
+        }
+    }
+
+    /// Backend id used on-chain (MorphController.sol, RegulatoryRegistry.sol)
+    pub fn id(&self) -> u8 {
         match self {
-            BackendType::BN254 => 100,
-            BackendType::BLS12_381 => 128,
+            BackendType::BN254 => 0,
+            BackendType::BLS12_381 => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(BackendType::BN254),
+            1 => Some(BackendType::BLS12_381),
+            _ => None,
         }
     }
+
+    /// Estimated classical security, see `security::SecurityEstimate`
+    pub fn security_bits(&self) -> u32 {
+        SecurityEstimate::for_backend(*self).bits
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +54,24 @@ pub struct UniversalProof {
     pub public_inputs: Vec<String>,
 }
 
+impl UniversalProof {
+    /// Short content id: backend name plus the first 16 hex chars of SHA-256(proof)
+    pub fn id(&self) -> String {
+        let digest = Sha256::digest(&self.proof_bytes);
+        format!("{}:{}", self.backend.name(), &hex::encode(digest)[..16])
+    }
+}
+
+/// Outcome of a passed canary run
+#[derive(Debug, Clone)]
+pub struct CanaryReport {
+    pub proof_id: String,
+    pub prove_ms: u128,
+    pub verify_ms: u128,
+    /// Including the reference proof on the current backend
+    pub total_ms: u128,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct MorphResult {
@@ -36,4 +79,36 @@ pub struct MorphResult {
     pub old_backend: BackendType,
     pub new_backend: BackendType,
     pub duration_ms: u128,
+    pub canary: Option<CanaryReport>,
+}
+
+/// What caused a morph, or for the last two, an audit entry that is not one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MorphTrigger {
+    Manual,
+    ThreatPolicy,
+    Emergency,
+    Rollback,
+    /// Auto-morph froze on flapping; the backend stays as it is
+    Freeze,
+    /// An operator confirmed that auto-morph may resume after a freeze
+    Resume,
+}
+
+impl MorphTrigger {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MorphTrigger::Manual => "manual",
+            MorphTrigger::ThreatPolicy => "threat-policy",
+            MorphTrigger::Emergency => "emergency",
+            MorphTrigger::Rollback => "rollback",
+            MorphTrigger::Freeze => "freeze",
+            MorphTrigger::Resume => "resume",
+        }
+    }
+
+    /// Whether the entry records a backend switch
+    pub fn is_morph(&self) -> bool {
+        !matches!(self, MorphTrigger::Freeze | MorphTrigger::Resume)
+    }
 }